@@ -22,4 +22,75 @@ contract.on("UserAction", async (user, action, value, details, event) => {
   }
 });
 
-console.log("Escuchando eventos UserAction en el contrato:", CONTRACT_ADDRESS); 
\ No newline at end of file
+console.log("Escuchando eventos UserAction en el contrato:", CONTRACT_ADDRESS); 
+// Eventos del procesador de IA (mismas firmas que IAIProcessor.sol)
+const AI_PROCESSOR_ABI = [
+  "event ModelRegistered(uint256 indexed modelId, uint256 inputSize, uint256 outputSize)",
+  "event ModelUpdated(uint256 indexed modelId, uint256 version)",
+  "event InferenceCompleted(uint256 indexed requestId, uint256 indexed modelId, bytes32 inputHash, bytes32 outputHash, uint256 confidence, uint8 source)",
+  "event CacheHit(bytes32 indexed inputHash, uint256 indexed modelId, uint256 hits)",
+  "event CacheEvicted(bytes32 indexed inputHash, uint256 indexed modelId, uint8 reason)",
+  "event StatsUpdated(uint256 indexed modelId, uint256 totalRequests, uint256 totalGasUsed, uint256 successRate)"
+];
+const INFERENCE_SOURCES = ["on-chain", "off-chain", "cached"];
+const EVICTION_REASONS = ["expired", "capacity", "invalidated"];
+
+async function sendMetric(type, data) {
+  try {
+    await axios.post("http://localhost:4000/metrics", { type, ...data, timestamp: new Date() });
+    console.log("Evento capturado:", type, data);
+  } catch (err) {
+    console.error("Error enviando métrica:", err.message);
+  }
+}
+
+const AI_PROCESSOR_ADDRESS = process.env.AI_PROCESSOR_ADDRESS;
+if (AI_PROCESSOR_ADDRESS) {
+  const aiProcessor = new ethers.Contract(AI_PROCESSOR_ADDRESS, AI_PROCESSOR_ABI, provider);
+
+  aiProcessor.on("ModelRegistered", (modelId, inputSize, outputSize) =>
+    sendMetric("ModelRegistered", {
+      modelId: modelId.toString(),
+      inputSize: inputSize.toString(),
+      outputSize: outputSize.toString()
+    })
+  );
+
+  aiProcessor.on("ModelUpdated", (modelId, version) =>
+    sendMetric("ModelUpdated", { modelId: modelId.toString(), version: version.toString() })
+  );
+
+  aiProcessor.on("InferenceCompleted", (requestId, modelId, inputHash, outputHash, confidence, source) =>
+    sendMetric("InferenceCompleted", {
+      requestId: requestId.toString(),
+      modelId: modelId.toString(),
+      inputHash,
+      outputHash,
+      confidence: confidence.toString(),
+      source: INFERENCE_SOURCES[Number(source)] ?? source.toString()
+    })
+  );
+
+  aiProcessor.on("CacheHit", (inputHash, modelId, hits) =>
+    sendMetric("CacheHit", { inputHash, modelId: modelId.toString(), hits: hits.toString() })
+  );
+
+  aiProcessor.on("CacheEvicted", (inputHash, modelId, reason) =>
+    sendMetric("CacheEvicted", {
+      inputHash,
+      modelId: modelId.toString(),
+      reason: EVICTION_REASONS[Number(reason)] ?? reason.toString()
+    })
+  );
+
+  aiProcessor.on("StatsUpdated", (modelId, totalRequests, totalGasUsed, successRate) =>
+    sendMetric("StatsUpdated", {
+      modelId: modelId.toString(),
+      totalRequests: totalRequests.toString(),
+      totalGasUsed: totalGasUsed.toString(),
+      successRate: successRate.toString()
+    })
+  );
+
+  console.log("Escuchando eventos del procesador de IA en:", AI_PROCESSOR_ADDRESS);
+}
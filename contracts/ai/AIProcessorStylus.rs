@@ -1,5 +1,8 @@
 use stylus_sdk::{
     alloy_primitives::U256,
+    alloy_sol_types::sol,
+    call::{self, Call},
+    contract,
     prelude::*,
     stylus_proc::stylus_fn,
 };
@@ -8,6 +11,62 @@ use alloy_primitives::{Address, Bytes, FixedBytes};
 use core::marker::PhantomData;
 use wee_alloc::WeeAlloc;
 
+// Request id allocator shared with the main AIProcessor and OffChainCompute
+#[path = "src/requests.rs"]
+mod requests;
+
+use requests::{RequestIdAllocator, RequestOrigin};
+
+// Processing stats shared with the main AIProcessor
+#[path = "src/stats.rs"]
+mod stats;
+
+use stats::{ProcessingStats, SOURCE_CACHED, SOURCE_OFF_CHAIN, SOURCE_ON_CHAIN};
+
+// Bounded LRU/TTL result cache
+#[path = "src/cache.rs"]
+mod cache;
+
+use cache::{CacheEntry, CacheScope, CacheStats, Lookup, LruCache};
+
+// Signatures over off-chain results
+#[path = "src/attestation.rs"]
+mod attestation;
+
+// Gas estimates for routing, calibrated by measured on-chain runs
+#[path = "src/gas.rs"]
+mod gas;
+
+use gas::{Calibration, ModelShape};
+
+const DEFAULT_CACHE_CAPACITY: u64 = 1024;
+
+// Off-chain deadlines, in seconds
+const DEFAULT_OFF_CHAIN_TIMEOUT: u64 = 3600;
+const MAX_OFF_CHAIN_TIMEOUT: u64 = 7 * 24 * 3600;
+// Upper bound on requests handled by a single expire() call
+const MAX_EXPIRE_BATCH: usize = 50;
+
+// Seconds a staked submitter waits between unstaking and withdrawing, so it
+// can still be slashed for results it just submitted
+const SUBMITTER_UNBONDING_PERIOD: u64 = 7 * 24 * 3600;
+
+// Gas forwarded to onInferenceResult; retries may forward more
+const CALLBACK_GAS_STIPEND: u64 = 200_000;
+// Gas kept back to finish the submission after the callback returns
+const CALLBACK_GAS_RESERVE: u64 = 50_000;
+
+sol_interface! {
+    interface IInferenceCallback {
+        function onInferenceResult(uint256 requestId, bytes output, uint256 confidence) external;
+    }
+
+    interface IOffChainCompute {
+        function submitComputeRequest(bytes inputData, uint256 modelId, uint256 deadline) external returns (uint256);
+        function getComputeResult(uint256 requestId) external view returns (bool completed, address processor, bytes output, uint256 confidence, bytes signature);
+    }
+}
+
 #[global_allocator]
 static ALLOC: WeeAlloc = WeeAlloc::INIT;
 
@@ -18,8 +77,24 @@ pub struct AIProcessor {
     model_configs: StorageMap<U256, ModelConfig>,
     inference_results: StorageMap<U256, InferenceResult>,
     stats: StorageMap<U256, ProcessingStats>,
-    computation_cache: StorageMap<FixedBytes<32>, CacheEntry>,
+    computation_cache: LruCache,
+    // Bumped to drop every cached result of a model at once
+    cache_generations: StorageMap<U256, U256>,
     off_chain_requests: StorageMap<U256, OffChainRequest>,
+    request_ids: RequestIdAllocator,
+    // Refunds and released escrow, pulled with withdraw()
+    pending_withdrawals: StorageMap<Address, U256>,
+    // Nodes allowed to submit off-chain results
+    submitters: StorageMap<Address, Submitter>,
+    // Stake that authorizes a node without whitelisting; 0 disables staking
+    min_submitter_stake: U256,
+    // OffChainCompute network off-chain requests are dispatched to; 0 disables
+    compute_network: Address,
+    // OffChainCompute request id -> our request id
+    compute_requests: StorageMap<U256, U256>,
+    // Declared model shapes; without one a single dense layer is assumed
+    model_shapes: StorageMap<U256, ModelShape>,
+    gas_calibrations: StorageMap<U256, Calibration>,
     _phantom: PhantomData<()>,
 }
 
@@ -34,6 +109,14 @@ pub struct ModelConfig {
     off_chain_enabled: bool,
     cache_ttl: U256,
     max_gas_limit: U256,
+    // Bumped on every re-registration; part of the cache key
+    version: U256,
+    preprocessing: Bytes,
+    preprocessing_hash: FixedBytes<32>,
+    // Seconds an off-chain request may stay pending before it can be expired
+    off_chain_timeout: U256,
+    // Compute expired off-chain requests on-chain instead of dropping them
+    on_chain_fallback: bool,
 }
 
 #[derive(Debug, Clone, Storage)]
@@ -45,14 +128,8 @@ pub struct InferenceResult {
     timestamp: U256,
     gas_used: U256,
     computation_source: ComputationSource,
-}
-
-#[derive(Debug, Clone, Storage)]
-pub struct CacheEntry {
-    result: Bytes,
-    timestamp: U256,
-    ttl: U256,
-    hits: U256,
+    // false when the model failed; the output is empty
+    success: bool,
 }
 
 #[derive(Debug, Clone, Storage)]
@@ -64,6 +141,42 @@ pub struct OffChainRequest {
     callback_data: Bytes,
     deadline: U256,
     status: RequestStatus,
+    requester: Address,
+    // Fee sent with the request; released on completion, refunded on expiry
+    escrowed_fee: U256,
+    callback_status: CallbackStatus,
+    callback_attempts: U256,
+    // Revert data of the last failed delivery
+    callback_error: Bytes,
+    // Node or compute processor that submitted the result
+    fulfilled_by: Address,
+    // OffChainCompute network handling the request, if any, and its id there
+    dispatched_to: Address,
+    compute_request_id: U256,
+    // Model version, preprocessing and cache generation at submission; the
+    // result is only cached if they still match when it arrives
+    cache_scope: CacheScope,
+}
+
+#[derive(Debug, Clone)]
+pub struct GasEstimate {
+    estimated_gas: U256,
+    // Before applying the model's calibration
+    uncalibrated_gas: U256,
+    calibration_samples: U256,
+    // Whether process_inference would route this input off-chain
+    off_chain: bool,
+}
+
+#[derive(Debug, Clone, Storage)]
+pub struct Submitter {
+    // Key that signs results; may differ from the node's sending address
+    signing_key: Address,
+    whitelisted: bool,
+    stake: U256,
+    // Set by request_unstake; the node is not authorized while unbonding
+    unlock_at: U256,
+    results_submitted: U256,
 }
 
 #[derive(Debug, Clone, Storage)]
@@ -73,6 +186,17 @@ pub enum ComputationSource {
     Cached,
 }
 
+impl ComputationSource {
+    // Encoding used in events and stats
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            ComputationSource::OnChain => SOURCE_ON_CHAIN,
+            ComputationSource::OffChain => SOURCE_OFF_CHAIN,
+            ComputationSource::Cached => SOURCE_CACHED,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Storage)]
 pub enum RequestStatus {
     Pending,
@@ -82,13 +206,12 @@ pub enum RequestStatus {
 }
 
 #[derive(Debug, Clone, Storage)]
-pub struct ProcessingStats {
-    total_requests: U256,
-    total_gas_used: U256,
-    avg_processing_time: U256,
-    success_rate: U256,
-    cache_hit_rate: U256,
-    off_chain_ratio: U256,
+pub enum CallbackStatus {
+    // Requested by an account without code: nothing to call back
+    None,
+    Pending,
+    Delivered,
+    Failed,
 }
 
 #[stylus_fn]
@@ -99,8 +222,17 @@ impl AIProcessor {
             model_configs: StorageMap::new(),
             inference_results: StorageMap::new(),
             stats: StorageMap::new(),
-            computation_cache: StorageMap::new(),
+            computation_cache: LruCache::new(U256::from(DEFAULT_CACHE_CAPACITY)),
+            cache_generations: StorageMap::new(),
             off_chain_requests: StorageMap::new(),
+            request_ids: RequestIdAllocator::new(requests::DOMAIN_AI_PROCESSOR_STYLUS),
+            pending_withdrawals: StorageMap::new(),
+            submitters: StorageMap::new(),
+            min_submitter_stake: U256::ZERO,
+            compute_network: Address::ZERO,
+            compute_requests: StorageMap::new(),
+            model_shapes: StorageMap::new(),
+            gas_calibrations: StorageMap::new(),
             _phantom: PhantomData,
         }
     }
@@ -118,7 +250,26 @@ impl AIProcessor {
         max_gas_limit: U256,
     ) -> Result<bool, Vec<u8>> {
         self.ensure_owner()?;
-        
+
+        let previous = self.model_configs.get(&model_id);
+        let exists = previous.is_some();
+        let (version, preprocessing, preprocessing_hash, off_chain_timeout, on_chain_fallback) =
+            match previous {
+                Some(previous) => (
+                    previous.version + U256::from(1),
+                    previous.preprocessing,
+                    previous.preprocessing_hash,
+                    previous.off_chain_timeout,
+                    previous.on_chain_fallback,
+                ),
+                None => (
+                    U256::from(1),
+                    Bytes::new(),
+                    FixedBytes::ZERO,
+                    U256::from(DEFAULT_OFF_CHAIN_TIMEOUT),
+                    false,
+                ),
+            };
         let config = ModelConfig {
             model_id,
             input_size,
@@ -129,17 +280,160 @@ impl AIProcessor {
             off_chain_enabled,
             cache_ttl,
             max_gas_limit,
+            version,
+            preprocessing,
+            preprocessing_hash,
+            off_chain_timeout,
+            on_chain_fallback,
         };
         
         self.model_configs.insert(model_id, config);
+
+        // Re-registering an existing id replaces its configuration, so
+        // results cached for the old one must not be served again
+        if exists {
+            self.bump_cache_generation(model_id);
+            // Measurements of the old version no longer apply, and a shape
+            // that does not fit the new sizes is dropped
+            self.gas_calibrations.remove(&model_id);
+            if let Some(shape) = self.model_shapes.get(&model_id) {
+                if !shape_fits(&shape, input_size, output_size) {
+                    self.model_shapes.remove(&model_id);
+                }
+            }
+            evm::log(ModelUpdated { model_id, version });
+        } else {
+            evm::log(ModelRegistered {
+                model_id,
+                input_size,
+                output_size,
+            });
+        }
+
+        Ok(true)
+    }
+
+    // Preprocessing changes what the model sees for the same input bytes,
+    // so the cached results of the model are invalidated
+    #[stylus_fn(name = "setModelPreprocessing")]
+    pub fn set_model_preprocessing(
+        &mut self,
+        model_id: U256,
+        preprocessing: Bytes,
+    ) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        let mut config = self.model_configs.get(&model_id)
+            .ok_or("Model not found")?;
+
+        config.preprocessing_hash = if preprocessing.is_empty() {
+            FixedBytes::ZERO
+        } else {
+            evm::keccak256(&preprocessing).into()
+        };
+        config.preprocessing = preprocessing;
+        self.model_configs.insert(model_id, config);
+        self.bump_cache_generation(model_id);
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "setModelShape")]
+    pub fn set_model_shape(
+        &mut self,
+        model_id: U256,
+        kind: u8,
+        dims: Vec<u32>,
+        num_trees: u32,
+        tree_depth: u32,
+        weight_bytes: u32,
+    ) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        let config = self.model_configs.get(&model_id)
+            .ok_or("Model not found")?;
+
+        let shape = ModelShape {
+            kind,
+            dims,
+            num_trees,
+            tree_depth,
+            weight_bytes,
+            pipeline_steps: gas::pipeline_steps(&config.preprocessing),
+        };
+        shape.validate()?;
+        require!(
+            shape_fits(&shape, config.input_size, config.output_size),
+            "Shape does not match model sizes"
+        );
+
+        self.model_shapes.insert(model_id, shape);
+        // Calibrated against the old shape's estimates
+        self.gas_calibrations.remove(&model_id);
+
+        Ok(true)
+    }
+
+    // Lets callers see ahead of time what an input will cost and whether
+    // it would be routed off-chain
+    #[stylus_fn(name = "estimateGas")]
+    pub fn estimate_gas(&self, model_id: U256, input_size: U256) -> Result<GasEstimate, Vec<u8>> {
+        let config = self.model_configs.get(&model_id)
+            .ok_or("Model not found")?;
+        require!(input_size <= config.input_size, "Input too large");
+
+        let input_len = input_size.as_usize();
+        let uncalibrated_gas = self.uncalibrated_gas(&config, input_len);
+        let calibration = self.gas_calibrations.get(&model_id).unwrap_or_default();
+        let estimated_gas = calibration.apply(uncalibrated_gas);
+
+        Ok(GasEstimate {
+            estimated_gas,
+            uncalibrated_gas,
+            calibration_samples: calibration.samples,
+            off_chain: config.off_chain_enabled && estimated_gas > config.max_gas_limit,
+        })
+    }
+
+    #[stylus_fn(name = "setOffChainPolicy")]
+    pub fn set_off_chain_policy(
+        &mut self,
+        model_id: U256,
+        timeout: U256,
+        on_chain_fallback: bool,
+    ) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        require!(
+            !timeout.is_zero() && timeout <= U256::from(MAX_OFF_CHAIN_TIMEOUT),
+            "Invalid timeout"
+        );
+        let mut config = self.model_configs.get(&model_id)
+            .ok_or("Model not found")?;
+
+        // Only affects new requests; pending ones keep their deadline
+        config.off_chain_timeout = timeout;
+        config.on_chain_fallback = on_chain_fallback;
+        self.model_configs.insert(model_id, config);
+
         Ok(true)
     }
 
+    // Any value sent is escrowed if the request goes off-chain and credited
+    // back to the caller otherwise
     #[stylus_fn(name = "processInference")]
+    #[payable]
     pub fn process_inference(
         &mut self,
         model_id: U256,
         input_data: Bytes,
+    ) -> Result<InferenceResult, Vec<u8>> {
+        let result = self.process(model_id, input_data, msg::value())?;
+        Ok(result)
+    }
+
+    fn process(
+        &mut self,
+        model_id: U256,
+        input_data: Bytes,
+        payment: U256,
     ) -> Result<InferenceResult, Vec<u8>> {
         let config = self.model_configs.get(&model_id)
             .ok_or("Model not found")?;
@@ -147,28 +441,51 @@ impl AIProcessor {
         require!(config.is_active, "Model not active");
         require!(input_data.len() <= config.input_size.as_usize(), "Input too large");
 
+        let start_gas = evm::gas_left();
+
         // Check cache first
-        let input_hash = evm::keccak256(&input_data);
-        if let Some(cached) = self.check_cache(&input_hash.into())? {
-            return Ok(self.create_result_from_cache(cached, input_hash.into()));
+        let input_hash: FixedBytes<32> = evm::keccak256(&input_data).into();
+        if let Some(cached) = self.check_cache(model_id, input_hash) {
+            evm::log(CacheHit {
+                input_hash: input_hash.0,
+                model_id,
+                hits: cached.hits,
+            });
+            let result = self.create_result_from_cache(model_id, cached);
+            let gas_used = start_gas - evm::gas_left();
+            self.update_stats(model_id, &result, input_data.len(), gas_used.into());
+            self.emit_inference_completed(model_id, &result);
+            self.credit(msg::sender(), payment);
+            return Ok(result);
         }
 
         // Check if should process off-chain
         if self.should_process_off_chain(&config, &input_data)? {
-            return self.submit_off_chain_request(model_id, input_data);
+            self.relax_calibration(model_id);
+            return self.submit_off_chain_request(&config, input_data, payment);
         }
+        self.credit(msg::sender(), payment);
 
-        // Process on-chain
-        let start_gas = evm::gas_left();
-        let start_time = evm::block_timestamp();
-
-        let result = self.run_inference(model_id, &input_data)?;
+        // Process on-chain. A model failure is recorded and returned instead
+        // of reverting, which would also roll back the failure in the stats
+        let result = match self.run_inference(model_id, &input_data) {
+            Ok(result) => result,
+            Err(_) => {
+                let request_id = self.request_ids.allocate(msg::sender(), model_id);
+                let result = self.failed_result(request_id, input_hash);
+                let gas_used = start_gas - evm::gas_left();
+                self.update_stats(model_id, &result, input_data.len(), gas_used.into());
+                self.emit_inference_completed(model_id, &result);
+                return Ok(result);
+            }
+        };
         
         // Update stats and cache
         let gas_used = start_gas - evm::gas_left();
-        let processing_time = evm::block_timestamp() - start_time;
-        self.update_stats(model_id, gas_used.into(), processing_time.into())?;
-        self.cache_result(input_hash.into(), &result, config.cache_ttl)?;
+        self.update_stats(model_id, &result, input_data.len(), gas_used.into());
+        self.calibrate(&config, input_data.len(), gas_used.into());
+        self.cache_result(model_id, input_hash, &result, config.cache_ttl);
+        self.emit_inference_completed(model_id, &result);
 
         Ok(result)
     }
@@ -192,14 +509,14 @@ impl AIProcessor {
         for chunk in chunks {
             let mut chunk_results = Vec::with_capacity(chunk.len());
             for input in chunk {
-                let result = self.process_inference(model_id, input.clone())?;
+                let result = self.process(model_id, input.clone(), U256::ZERO)?;
                 chunk_results.push(result);
             }
             results.extend(chunk_results);
         }
 
         let total_gas = start_gas - evm::gas_left();
-        emit!(BatchProcessed {
+        evm::log(BatchProcessed {
             model_id,
             count: inputs.len() as u32,
             gas_used: total_gas
@@ -214,225 +531,969 @@ impl AIProcessor {
         request_id: U256,
         result: Bytes,
         confidence: U256,
+        signature: Bytes,
+    ) -> Result<bool, Vec<u8>> {
+        self.accept_signed_result(msg::sender(), request_id, result, confidence, &signature)?;
+        Ok(true)
+    }
+
+    // Push path from OffChainCompute: called when the assigned processor
+    // submits its ComputeResult. Anyone can register as a processor there,
+    // so its result is held to the same standard as a direct submission:
+    // the processor must be an authorized submitter here and sign it.
+    #[stylus_fn(name = "onComputeResult")]
+    pub fn on_compute_result(
+        &mut self,
+        compute_request_id: U256,
+        processor: Address,
+        output: Bytes,
+        confidence: U256,
+        signature: Bytes,
     ) -> Result<bool, Vec<u8>> {
+        let request_id = self.compute_requests.get(&compute_request_id)
+            .ok_or("Unknown compute request")?;
         let request = self.off_chain_requests.get(&request_id)
             .ok_or("Request not found")?;
-        
-        require!(request.status == RequestStatus::Pending, "Invalid request status");
-        
-        let inference_result = InferenceResult {
-            request_id,
-            input_hash: evm::keccak256(&request.input_data).into(),
-            output: result.clone(),
-            confidence,
-            timestamp: evm::block_timestamp().into(),
-            gas_used: U256::ZERO,
-            computation_source: ComputationSource::OffChain,
-        };
+        require!(msg::sender() == request.dispatched_to, "Not the compute network");
 
-        self.inference_results.insert(request_id, inference_result);
-        
-        // Update request status
-        let mut updated_request = request;
-        updated_request.status = RequestStatus::Completed;
-        self.off_chain_requests.insert(request_id, updated_request);
+        self.accept_signed_result(processor, request_id, output, confidence, &signature)?;
+        Ok(true)
+    }
 
-        // Cache the result
-        let config = self.model_configs.get(&request.model_id)
-            .ok_or("Model not found")?;
-        self.cache_result(
-            evm::keccak256(&request.input_data).into(),
-            &inference_result,
-            config.cache_ttl,
-        )?;
+    // Pull path: anyone can import a completed ComputeResult, e.g. when the
+    // push ran out of gas. Checked like the push path.
+    #[stylus_fn(name = "pullComputeResult")]
+    pub fn pull_compute_result(&mut self, request_id: U256) -> Result<bool, Vec<u8>> {
+        let request = self.off_chain_requests.get(&request_id)
+            .ok_or("Request not found")?;
+        require!(request.dispatched_to != Address::ZERO, "Not dispatched");
 
+        let (completed, processor, output, confidence, signature) =
+            IOffChainCompute::new(request.dispatched_to)
+                .get_compute_result(Call::new(), request.compute_request_id)
+                .map_err(|_| "Compute network call failed")?;
+        require!(completed, "Compute result not ready");
+
+        self.accept_signed_result(processor, request_id, output, confidence, &signature)?;
         Ok(true)
     }
 
-    // Helper functions
-    fn check_cache(&self, input_hash: &FixedBytes<32>) -> Result<Option<CacheEntry>, Vec<u8>> {
-        if let Some(entry) = self.computation_cache.get(input_hash) {
-            if entry.timestamp + entry.ttl > evm::block_timestamp().into() {
-                return Ok(Some(entry));
-            }
-        }
-        Ok(None)
+    // Address 0 stops dispatching; requests already dispatched keep their network
+    #[stylus_fn(name = "setComputeNetwork")]
+    pub fn set_compute_network(&mut self, network: Address) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        self.compute_network = network;
+        Ok(true)
     }
 
-    fn cache_result(
-        &mut self,
-        input_hash: FixedBytes<32>,
-        result: &InferenceResult,
-        ttl: U256,
-    ) -> Result<(), Vec<u8>> {
-        let entry = CacheEntry {
-            result: result.output.clone(),
-            timestamp: evm::block_timestamp().into(),
-            ttl,
-            hits: U256::from(1),
-        };
-        self.computation_cache.insert(input_hash, entry);
-        Ok(())
+    // What a node signs for submit_off_chain_result
+    #[stylus_fn(name = "resultDigest")]
+    pub fn result_digest(&self, request_id: U256, output: Bytes, confidence: U256) -> FixedBytes<32> {
+        attestation::result_digest(contract::address(), request_id, &output, confidence)
     }
 
-    fn should_process_off_chain(
-        &self,
-        config: &ModelConfig,
-        input_data: &Bytes,
+    #[stylus_fn(name = "setSubmitter")]
+    pub fn set_submitter(
+        &mut self,
+        node: Address,
+        signing_key: Address,
+        whitelisted: bool,
     ) -> Result<bool, Vec<u8>> {
-        if !config.off_chain_enabled {
-            return Ok(false);
-        }
+        self.ensure_owner()?;
+        require!(signing_key != Address::ZERO, "Invalid signing key");
 
-        // Estimate gas cost
-        let estimated_gas = self.estimate_computation_gas(input_data)?;
-        Ok(estimated_gas > config.max_gas_limit)
+        let mut submitter = self.submitter(node);
+        submitter.signing_key = signing_key;
+        submitter.whitelisted = whitelisted;
+        self.submitters.insert(node, submitter);
+
+        evm::log(SubmitterUpdated {
+            node,
+            signing_key,
+            whitelisted,
+        });
+
+        Ok(true)
     }
 
-    fn submit_off_chain_request(
-        &mut self,
-        model_id: U256,
-        input_data: Bytes,
-    ) -> Result<InferenceResult, Vec<u8>> {
-        let request_id = self.get_next_request_id();
-        let deadline = evm::block_timestamp() + 3600; // 1 hour deadline
+    #[stylus_fn(name = "setMinSubmitterStake")]
+    pub fn set_min_submitter_stake(&mut self, amount: U256) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        self.min_submitter_stake = amount;
+        Ok(true)
+    }
 
-        let request = OffChainRequest {
-            request_id,
-            model_id,
-            input_data: input_data.clone(),
-            callback_address: msg::sender(),
-            callback_data: Bytes::new(),
-            deadline: deadline.into(),
-            status: RequestStatus::Pending,
-        };
+    #[stylus_fn(name = "stakeAsSubmitter")]
+    #[payable]
+    pub fn stake_as_submitter(&mut self, signing_key: Address) -> Result<U256, Vec<u8>> {
+        require!(!self.min_submitter_stake.is_zero(), "Staking disabled");
+        require!(signing_key != Address::ZERO, "Invalid signing key");
+        require!(!msg::value().is_zero(), "Nothing staked");
 
-        self.off_chain_requests.insert(request_id, request);
+        let node = msg::sender();
+        let mut submitter = self.submitter(node);
+        submitter.signing_key = signing_key;
+        submitter.stake += msg::value();
+        // Topping up cancels a pending unstake
+        submitter.unlock_at = U256::ZERO;
+        let stake = submitter.stake;
+        self.submitters.insert(node, submitter);
 
-        emit!(OffChainRequestSubmitted {
-            request_id,
-            model_id,
-            deadline: deadline as u64,
+        evm::log(SubmitterStaked {
+            node,
+            stake,
         });
 
-        // Return a pending result
-        Ok(InferenceResult {
-            request_id,
-            input_hash: evm::keccak256(&input_data).into(),
-            output: Bytes::new(),
-            confidence: U256::ZERO,
-            timestamp: evm::block_timestamp().into(),
-            gas_used: U256::ZERO,
-            computation_source: ComputationSource::OffChain,
-        })
+        Ok(stake)
     }
 
-    fn estimate_computation_gas(&self, input_data: &Bytes) -> Result<U256, Vec<u8>> {
-        // Implement gas estimation logic
-        Ok(U256::from(input_data.len() * 100_000))
-    }
+    #[stylus_fn(name = "requestUnstake")]
+    pub fn request_unstake(&mut self) -> Result<U256, Vec<u8>> {
+        let node = msg::sender();
+        let mut submitter = self.submitters.get(&node).ok_or("Not a submitter")?;
+        require!(!submitter.stake.is_zero(), "Nothing staked");
 
-    fn create_result_from_cache(
-        &self,
-        cache: CacheEntry,
-        input_hash: FixedBytes<32>,
-    ) -> InferenceResult {
-        InferenceResult {
-            request_id: U256::ZERO,
-            input_hash: input_hash.to_vec().into(),
-            output: cache.result,
-            confidence: U256::from(100),
-            timestamp: evm::block_timestamp().into(),
-            gas_used: U256::ZERO,
-            computation_source: ComputationSource::Cached,
-        }
-    }
+        let unlock_at = U256::from(evm::block_timestamp() + SUBMITTER_UNBONDING_PERIOD);
+        submitter.unlock_at = unlock_at;
+        self.submitters.insert(node, submitter);
 
-    fn get_next_request_id(&self) -> U256 {
-        // Implementar generación segura de IDs
-        let block_number = evm::block_number();
-        let timestamp = evm::block_timestamp();
-        let sender = msg::sender();
-        
-        let mut data = Vec::with_capacity(32 * 3);
-        data.extend_from_slice(&block_number.to_be_bytes());
-        data.extend_from_slice(&timestamp.to_be_bytes());
-        data.extend_from_slice(sender.as_bytes());
-        
-        U256::from_be_bytes(evm::keccak256(&data))
+        Ok(unlock_at)
     }
 
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        require!(msg::sender() == self.owner, "Not owner");
-        Ok(())
+    // Moves an unbonded stake to pending withdrawals
+    #[stylus_fn(name = "withdrawStake")]
+    pub fn withdraw_stake(&mut self) -> Result<U256, Vec<u8>> {
+        let node = msg::sender();
+        let mut submitter = self.submitters.get(&node).ok_or("Not a submitter")?;
+        require!(!submitter.unlock_at.is_zero(), "Unstake not requested");
+        require!(U256::from(evm::block_timestamp()) >= submitter.unlock_at, "Stake still bonded");
+
+        let amount = submitter.stake;
+        submitter.stake = U256::ZERO;
+        submitter.unlock_at = U256::ZERO;
+        self.submitters.insert(node, submitter);
+        self.credit(node, amount);
+
+        Ok(amount)
     }
-}
 
-#[derive(Debug)]
-pub struct BatchProcessed {
-    model_id: U256,
-    count: u32,
-    gas_used: u64,
-}
+    #[stylus_fn(name = "slashSubmitter")]
+    pub fn slash_submitter(&mut self, node: Address, amount: U256) -> Result<U256, Vec<u8>> {
+        self.ensure_owner()?;
+        let mut submitter = self.submitters.get(&node).ok_or("Not a submitter")?;
 
-#[derive(Debug)]
-pub struct OffChainRequestSubmitted {
-    request_id: U256,
-    model_id: U256,
-    deadline: u64,
-}
+        let slashed = amount.min(submitter.stake);
+        submitter.stake -= slashed;
+        self.submitters.insert(node, submitter);
+        self.credit(self.owner, slashed);
 
-impl Event for BatchProcessed {
-    const SIGNATURE: [u8; 32] = keccak256!("BatchProcessed(uint256,uint32,uint64)");
-}
+        evm::log(SubmitterSlashed {
+            node,
+            amount: slashed,
+        });
 
-impl Event for OffChainRequestSubmitted {
-    const SIGNATURE: [u8; 32] = keccak256!("OffChainRequestSubmitted(uint256,uint256,uint64)");
-}
+        Ok(slashed)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[stylus_fn(name = "getSubmitter")]
+    pub fn get_submitter(&self, node: Address) -> Submitter {
+        self.submitter(node)
+    }
 
-    #[test]
-    fn test_register_model() {
-        let mut processor = AIProcessor::new();
-        let result = processor.register_model(
-            U256::from(1),
-            U256::from(1024),
-            U256::from(128),
-            U256::from(32),
-            U256::from(100),
-            true,
-            U256::from(3600),
-            U256::from(1_000_000),
-        );
-        assert!(result.is_ok());
+    #[stylus_fn(name = "isAuthorizedSubmitter")]
+    pub fn is_authorized_submitter(&self, node: Address) -> bool {
+        self.is_authorized(&self.submitter(node))
     }
 
-    #[test]
-    fn test_process_inference() {
-        let mut processor = AIProcessor::new();
-        processor.register_model(
-            U256::from(1),
-            U256::from(1024),
-            U256::from(128),
-            U256::from(32),
-            U256::from(100),
-            true,
-            U256::from(3600),
-            U256::from(1_000_000),
-        ).unwrap();
+    // Anyone may retry a failed delivery, forwarding at least the stipend
+    #[stylus_fn(name = "retryCallback")]
+    pub fn retry_callback(&mut self, request_id: U256, gas_limit: U256) -> Result<bool, Vec<u8>> {
+        let request = self.off_chain_requests.get(&request_id)
+            .ok_or("Request not found")?;
+        require!(request.callback_status == CallbackStatus::Failed, "No failed callback");
+        require!(gas_limit >= U256::from(CALLBACK_GAS_STIPEND), "Gas limit below stipend");
+        let gas_limit: u64 = gas_limit.try_into().map_err(|_| "Gas limit too large")?;
 
-        let input = vec![1, 2, 3, 4];
-        let result = processor.process_inference(U256::from(1), input.into());
-        assert!(result.is_ok());
+        let result = self.inference_results.get(&request_id)
+            .ok_or("Result not found")?;
+        self.deliver_callback(request_id, &result, gas_limit)
     }
 
-    #[test]
-    fn test_cache_hit() {
+    // Permissionless sweep: anyone can time out overdue requests. Ids that are
+    // unknown, already settled or not yet overdue are skipped so one stale id
+    // does not revert the whole sweep. Only refunds and marks the requests:
+    // computing them on-chain is left to `fallback`, one request per call, so
+    // a single expensive model cannot run the sweep out of gas. Returns how
+    // many requests expired.
+    #[stylus_fn(name = "expire")]
+    pub fn expire(&mut self, request_ids: Vec<U256>) -> Result<U256, Vec<u8>> {
+        require!(request_ids.len() <= MAX_EXPIRE_BATCH, "Too many requests");
+        let now = U256::from(evm::block_timestamp());
+
+        let mut expired = 0u64;
+        for request_id in request_ids {
+            let Some(mut request) = self.off_chain_requests.get(&request_id) else {
+                continue;
+            };
+            if request.status != RequestStatus::Pending || now <= request.deadline {
+                continue;
+            }
+
+            let refunded = request.escrowed_fee;
+            self.credit(request.requester, refunded);
+            request.status = RequestStatus::TimedOut;
+            request.escrowed_fee = U256::ZERO;
+            let model_id = request.model_id;
+            self.off_chain_requests.insert(request_id, request);
+
+            evm::log(OffChainRequestExpired {
+                request_id,
+                model_id,
+                refunded,
+            });
+            expired += 1;
+        }
+
+        Ok(U256::from(expired))
+    }
+
+    // Computes a timed-out request on-chain when its model allows it and
+    // delivers the result to the requester. Permissionless: the fee was
+    // already refunded, so the caller pays the gas. The request ends up
+    // Completed or, if the model fails, Failed, so it runs at most once.
+    #[stylus_fn(name = "fallback")]
+    pub fn fallback(&mut self, request_id: U256) -> Result<bool, Vec<u8>> {
+        let request = self.off_chain_requests.get(&request_id)
+            .ok_or("Request not found")?;
+        require!(request.status == RequestStatus::TimedOut, "Request not timed out");
+        let config = self.model_configs.get(&request.model_id)
+            .ok_or("Model not found")?;
+        require!(config.on_chain_fallback && config.is_active, "Fallback disabled");
+
+        let mut required = self.estimate_computation_gas(&config, request.input_data.len());
+        if request.callback_status == CallbackStatus::Pending {
+            required += U256::from((CALLBACK_GAS_STIPEND * 64 / 63) + CALLBACK_GAS_RESERVE);
+        }
+        require!(U256::from(evm::gas_left()) >= required, "Insufficient gas for fallback");
+
+        let fell_back = self.fall_back_on_chain(&request);
+        let mut updated_request = request;
+        if fell_back {
+            updated_request.status = RequestStatus::Completed;
+            updated_request.fulfilled_by = contract::address();
+        } else {
+            updated_request.status = RequestStatus::Failed;
+        }
+        self.off_chain_requests.insert(request_id, updated_request);
+
+        if fell_back {
+            if let Some(result) = self.inference_results.get(&request_id) {
+                self.deliver_callback(request_id, &result, CALLBACK_GAS_STIPEND)?;
+            }
+        }
+        Ok(fell_back)
+    }
+
+    #[stylus_fn(name = "getOffChainRequest")]
+    pub fn get_off_chain_request(&self, request_id: U256) -> Result<OffChainRequest, Vec<u8>> {
+        self.off_chain_requests.get(&request_id)
+            .ok_or_else(|| "Request not found".into())
+    }
+
+    #[stylus_fn(name = "pendingWithdrawal")]
+    pub fn pending_withdrawal(&self, account: Address) -> U256 {
+        self.pending_withdrawals.get(&account).unwrap_or(U256::ZERO)
+    }
+
+    #[stylus_fn(name = "withdraw")]
+    pub fn withdraw(&mut self) -> Result<U256, Vec<u8>> {
+        let account = msg::sender();
+        let amount = self.pending_withdrawal(account);
+        require!(!amount.is_zero(), "Nothing to withdraw");
+
+        // Zeroed before the transfer so a re-entrant call finds nothing
+        self.pending_withdrawals.insert(account, U256::ZERO);
+        call::transfer_eth(account, amount).map_err(|_| "Transfer failed")?;
+
+        evm::log(Withdrawal {
+            account,
+            amount,
+        });
+
+        Ok(amount)
+    }
+
+    #[stylus_fn(name = "getRequestOrigin")]
+    pub fn get_request_origin(&self, request_id: U256) -> Result<RequestOrigin, Vec<u8>> {
+        self.request_ids.origin(request_id)
+            .ok_or_else(|| "Request not found".into())
+    }
+
+    #[stylus_fn(name = "getProcessingStats")]
+    pub fn get_processing_stats(&self, model_id: U256) -> Result<ProcessingStats, Vec<u8>> {
+        self.stats.get(&model_id)
+            .ok_or_else(|| "Stats not found".into())
+    }
+
+    #[stylus_fn(name = "setCacheCapacity")]
+    pub fn set_cache_capacity(&mut self, capacity: U256) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        for entry in self.computation_cache.set_capacity(capacity) {
+            emit_evicted(&entry, cache::EVICT_CAPACITY);
+        }
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "invalidateCacheEntry")]
+    pub fn invalidate_cache_entry(
+        &mut self,
+        model_id: U256,
+        input_hash: FixedBytes<32>,
+    ) -> Result<bool, Vec<u8>> {
+        self.ensure_owner()?;
+        let key = self.cache_key(model_id, input_hash);
+        let entry = self.computation_cache.remove(key)
+            .ok_or("Cache entry not found")?;
+        emit_evicted(&entry, cache::EVICT_INVALIDATED);
+        Ok(true)
+    }
+
+    // Drops every cached result of the model. Old entries become unreachable
+    // and age out of the LRU instead of being deleted one by one.
+    #[stylus_fn(name = "invalidateModelCache")]
+    pub fn invalidate_model_cache(&mut self, model_id: U256) -> Result<U256, Vec<u8>> {
+        self.ensure_owner()?;
+        Ok(self.bump_cache_generation(model_id))
+    }
+
+    // Peeks without counting a hit or refreshing recency
+    #[stylus_fn(name = "getCacheEntry")]
+    pub fn get_cache_entry(
+        &self,
+        model_id: U256,
+        input_hash: FixedBytes<32>,
+    ) -> Result<CacheEntry, Vec<u8>> {
+        self.computation_cache.peek(self.cache_key(model_id, input_hash))
+            .filter(|entry| !entry.is_expired(evm::block_timestamp().into()))
+            .ok_or_else(|| "Cache entry not found".into())
+    }
+
+    #[stylus_fn(name = "getCacheStats")]
+    pub fn get_cache_stats(&self) -> CacheStats {
+        self.computation_cache.stats()
+    }
+
+    // Helper functions
+    fn cache_scope(&self, model_id: U256) -> CacheScope {
+        let (version, preprocessing_hash) = self.model_configs.get(&model_id)
+            .map(|config| (config.version, config.preprocessing_hash))
+            .unwrap_or((U256::ZERO, FixedBytes::ZERO));
+        CacheScope {
+            model_id,
+            version,
+            preprocessing_hash,
+            generation: self.cache_generations.get(&model_id).unwrap_or(U256::ZERO),
+        }
+    }
+
+    fn cache_key(&self, model_id: U256, input_hash: FixedBytes<32>) -> FixedBytes<32> {
+        cache::cache_key(&self.cache_scope(model_id), input_hash)
+    }
+
+    fn bump_cache_generation(&mut self, model_id: U256) -> U256 {
+        let generation = self.cache_generations.get(&model_id).unwrap_or(U256::ZERO) + U256::from(1);
+        self.cache_generations.insert(model_id, generation);
+
+        evm::log(ModelCacheInvalidated {
+            model_id,
+            generation,
+        });
+
+        generation
+    }
+
+    fn check_cache(&mut self, model_id: U256, input_hash: FixedBytes<32>) -> Option<CacheEntry> {
+        let key = self.cache_key(model_id, input_hash);
+        match self.computation_cache.lookup(key, evm::block_timestamp().into()) {
+            Lookup::Hit(entry) => Some(entry),
+            Lookup::Expired(entry) => {
+                emit_evicted(&entry, cache::EVICT_EXPIRED);
+                None
+            }
+            Lookup::Miss => None,
+        }
+    }
+
+    fn cache_result(
+        &mut self,
+        model_id: U256,
+        input_hash: FixedBytes<32>,
+        result: &InferenceResult,
+        ttl: U256,
+    ) {
+        // A zero TTL opts the model out of caching
+        if ttl.is_zero() {
+            return;
+        }
+        let key = self.cache_key(model_id, input_hash);
+        let entry = cache::new_entry(
+            model_id,
+            input_hash,
+            result.output.clone(),
+            result.confidence,
+            evm::block_timestamp().into(),
+            ttl,
+        );
+        for evicted in self.computation_cache.insert(key, entry) {
+            emit_evicted(&evicted, cache::EVICT_CAPACITY);
+        }
+    }
+
+    // An off-chain result was computed against the model as it was when the
+    // request was submitted. If the model was re-registered or its cache
+    // invalidated since, caching it under the current scope would serve a
+    // stale result, so it is only returned to the requester.
+    fn cache_off_chain_result(&mut self, request: &OffChainRequest, result: &InferenceResult) {
+        if request.cache_scope != self.cache_scope(request.model_id) {
+            return;
+        }
+        let Some(config) = self.model_configs.get(&request.model_id) else {
+            return;
+        };
+        self.cache_result(
+            request.model_id,
+            evm::keccak256(&request.input_data).into(),
+            result,
+            config.cache_ttl,
+        );
+    }
+
+    // Gas for cached and off-chain results is what this contract spent
+    // serving them, not the cost of the original computation
+    fn update_stats(
+        &mut self,
+        model_id: U256,
+        result: &InferenceResult,
+        input_size: usize,
+        gas_used: U256,
+    ) {
+        let mut stats = self.stats.get(&model_id).unwrap_or_default();
+        // Confidence is a percentage here; stats keep basis points
+        stats.record(
+            result.computation_source.to_u8(),
+            input_size,
+            gas_used,
+            result.success,
+            result.confidence * U256::from(100),
+        );
+
+        evm::log(StatsUpdated {
+            model_id,
+            total_requests: stats.total_requests,
+            total_gas_used: stats.total_gas_used,
+            success_rate: stats.success_rate,
+        });
+
+        self.stats.insert(model_id, stats);
+    }
+
+    fn emit_inference_completed(&self, model_id: U256, result: &InferenceResult) {
+        evm::log(InferenceCompleted {
+            request_id: result.request_id,
+            model_id,
+            input_hash: FixedBytes::<32>::from_slice(&result.input_hash).0,
+            output_hash: evm::keccak256(&result.output).into(),
+            confidence: result.confidence,
+            source: result.computation_source.to_u8(),
+        });
+    }
+
+    fn should_process_off_chain(
+        &self,
+        config: &ModelConfig,
+        input_data: &Bytes,
+    ) -> Result<bool, Vec<u8>> {
+        if !config.off_chain_enabled {
+            return Ok(false);
+        }
+
+        // Estimate gas cost
+        let estimated_gas = self.estimate_computation_gas(config, input_data.len());
+        Ok(estimated_gas > config.max_gas_limit)
+    }
+
+    fn submit_off_chain_request(
+        &mut self,
+        config: &ModelConfig,
+        input_data: Bytes,
+        payment: U256,
+    ) -> Result<InferenceResult, Vec<u8>> {
+        let model_id = config.model_id;
+        let request_id = self.request_ids.allocate(msg::sender(), model_id);
+        let timeout = if config.off_chain_timeout.is_zero() {
+            U256::from(DEFAULT_OFF_CHAIN_TIMEOUT)
+        } else {
+            config.off_chain_timeout
+        };
+        let deadline = U256::from(evm::block_timestamp()) + timeout;
+
+        let request = OffChainRequest {
+            request_id,
+            model_id,
+            input_data: input_data.clone(),
+            callback_address: msg::sender(),
+            callback_data: Bytes::new(),
+            deadline,
+            status: RequestStatus::Pending,
+            requester: msg::sender(),
+            escrowed_fee: payment,
+            callback_status: if msg::sender().has_code() {
+                CallbackStatus::Pending
+            } else {
+                CallbackStatus::None
+            },
+            callback_attempts: U256::ZERO,
+            callback_error: Bytes::new(),
+            fulfilled_by: Address::ZERO,
+            dispatched_to: Address::ZERO,
+            compute_request_id: U256::ZERO,
+            cache_scope: self.cache_scope(model_id),
+        };
+
+        self.off_chain_requests.insert(request_id, request);
+        if self.compute_network != Address::ZERO {
+            self.dispatch_to_compute_network(request_id);
+        }
+
+        evm::log(OffChainRequestSubmitted {
+            request_id,
+            model_id,
+            deadline: deadline.to::<u64>(),
+        });
+
+        // Return a pending result
+        Ok(InferenceResult {
+            request_id,
+            input_hash: evm::keccak256(&input_data).into(),
+            output: Bytes::new(),
+            confidence: U256::ZERO,
+            timestamp: evm::block_timestamp().into(),
+            gas_used: U256::ZERO,
+            computation_source: ComputationSource::OffChain,
+            success: true,
+        })
+    }
+
+    // Computes a timed-out request on-chain. A model failure is recorded in
+    // the stats and reported as false rather than reverting, so the request
+    // can be marked Failed.
+    fn fall_back_on_chain(&mut self, request: &OffChainRequest) -> bool {
+        let start_gas = evm::gas_left();
+        let Ok(mut result) = self.run_inference(request.model_id, &request.input_data) else {
+            let result = self.failed_result(
+                request.request_id,
+                evm::keccak256(&request.input_data).into(),
+            );
+            let gas_used = start_gas - evm::gas_left();
+            self.update_stats(request.model_id, &result, request.input_data.len(), gas_used.into());
+            return false;
+        };
+        result.request_id = request.request_id;
+
+        self.inference_results.insert(request.request_id, result.clone());
+        let gas_used = start_gas - evm::gas_left();
+        self.update_stats(request.model_id, &result, request.input_data.len(), gas_used.into());
+        if let Some(config) = self.model_configs.get(&request.model_id) {
+            self.calibrate(&config, request.input_data.len(), gas_used.into());
+        }
+        self.cache_off_chain_result(request, &result);
+        self.emit_inference_completed(request.model_id, &result);
+        true
+    }
+
+    // Calls onInferenceResult on the requesting contract with a bounded amount
+    // of gas and records the outcome. Returns whether it was delivered; errors
+    // only when there is not enough gas left to make the call at all.
+    fn deliver_callback(
+        &mut self,
+        request_id: U256,
+        result: &InferenceResult,
+        gas_limit: u64,
+    ) -> Result<bool, Vec<u8>> {
+        let Some(mut request) = self.off_chain_requests.get(&request_id) else {
+            return Ok(false);
+        };
+        if request.callback_status != CallbackStatus::Pending
+            && request.callback_status != CallbackStatus::Failed
+        {
+            return Ok(false);
+        }
+
+        // Only 63/64 of the remaining gas reaches the callee, so a caller
+        // could otherwise starve the callback on purpose and have it recorded
+        // as the requester's failure
+        require!(
+            evm::gas_left() >= gas_limit.saturating_mul(64) / 63 + CALLBACK_GAS_RESERVE,
+            "Insufficient gas for callback"
+        );
+        let target = request.callback_address;
+        let outcome = IInferenceCallback::new(target).on_inference_result(
+            Call::new_in(self).gas(gas_limit),
+            request_id,
+            result.output.clone(),
+            result.confidence,
+        );
+
+        request.callback_attempts += U256::from(1);
+        let delivered = outcome.is_ok();
+        match outcome {
+            Ok(_) => {
+                request.callback_status = CallbackStatus::Delivered;
+                request.callback_error = Bytes::new();
+                evm::log(CallbackDelivered {
+                    request_id,
+                    target,
+                });
+            }
+            Err(error) => {
+                let reason: Vec<u8> = error.into();
+                request.callback_status = CallbackStatus::Failed;
+                request.callback_error = reason.clone().into();
+                evm::log(CallbackFailed {
+                    request_id,
+                    target,
+                    attempts: request.callback_attempts,
+                    reason,
+                });
+            }
+        }
+        self.off_chain_requests.insert(request_id, request);
+        Ok(delivered)
+    }
+
+    // Shared by every way a result can arrive: signed node submissions and
+    // the OffChainCompute network
+    fn complete_off_chain_request(
+        &mut self,
+        request_id: U256,
+        fulfilled_by: Address,
+        output: Bytes,
+        confidence: U256,
+    ) -> Result<(), Vec<u8>> {
+        let start_gas = evm::gas_left();
+        let request = self.off_chain_requests.get(&request_id)
+            .ok_or("Request not found")?;
+
+        require!(request.status == RequestStatus::Pending, "Invalid request status");
+        require!(U256::from(evm::block_timestamp()) <= request.deadline, "Request expired");
+        // A percentage, like on-chain results; stats scale it to basis points
+        require!(confidence <= U256::from(100), "Confidence out of range");
+
+        let inference_result = InferenceResult {
+            request_id,
+            input_hash: evm::keccak256(&request.input_data).into(),
+            output: output.clone(),
+            confidence,
+            timestamp: evm::block_timestamp().into(),
+            gas_used: U256::ZERO,
+            computation_source: ComputationSource::OffChain,
+            success: true,
+        };
+
+        self.inference_results.insert(request_id, inference_result.clone());
+        let gas_used = start_gas - evm::gas_left();
+        self.update_stats(request.model_id, &inference_result, request.input_data.len(), gas_used.into());
+        self.emit_inference_completed(request.model_id, &inference_result);
+
+        self.cache_off_chain_result(&request, &inference_result);
+
+        // The escrowed fee pays whoever did the off-chain work
+        self.credit(fulfilled_by, request.escrowed_fee);
+
+        // Update request status
+        let mut updated_request = request;
+        updated_request.status = RequestStatus::Completed;
+        updated_request.escrowed_fee = U256::ZERO;
+        updated_request.fulfilled_by = fulfilled_by;
+        self.off_chain_requests.insert(request_id, updated_request);
+
+        evm::log(OffChainResultSubmitted {
+            request_id,
+            submitter: fulfilled_by,
+            output_hash: evm::keccak256(&output).into(),
+        });
+
+        // Delivered last, once all state is final; a failing callback is
+        // recorded and can be retried, it never reverts the submission.
+        // Running out of gas for it does, so it cannot be starved on purpose
+        self.deliver_callback(request_id, &inference_result, CALLBACK_GAS_STIPEND)?;
+
+        Ok(())
+    }
+
+    // Hands the request to the OffChainCompute network. If the network cannot
+    // take it (e.g. no active processors) the request stays open for signed
+    // node submissions instead of failing the inference.
+    fn dispatch_to_compute_network(&mut self, request_id: U256) {
+        let network = self.compute_network;
+        let Some(mut request) = self.off_chain_requests.get(&request_id) else {
+            return;
+        };
+
+        let dispatched = IOffChainCompute::new(network).submit_compute_request(
+            Call::new_in(self),
+            request.input_data.clone(),
+            request.model_id,
+            request.deadline,
+        );
+        let Ok(compute_request_id) = dispatched else {
+            evm::log(ComputeDispatchFailed {
+                request_id,
+                network,
+            });
+            return;
+        };
+
+        request.dispatched_to = network;
+        request.compute_request_id = compute_request_id;
+        self.off_chain_requests.insert(request_id, request);
+        self.compute_requests.insert(compute_request_id, request_id);
+
+        evm::log(ComputeRequestDispatched {
+            request_id,
+            network,
+            compute_request_id,
+        });
+    }
+
+    fn submitter(&self, node: Address) -> Submitter {
+        self.submitters.get(&node).unwrap_or(Submitter {
+            signing_key: Address::ZERO,
+            whitelisted: false,
+            stake: U256::ZERO,
+            unlock_at: U256::ZERO,
+            results_submitted: U256::ZERO,
+        })
+    }
+
+    fn is_authorized(&self, submitter: &Submitter) -> bool {
+        if submitter.signing_key == Address::ZERO {
+            return false;
+        }
+        let staked = !self.min_submitter_stake.is_zero()
+            && submitter.unlock_at.is_zero()
+            && submitter.stake >= self.min_submitter_stake;
+        submitter.whitelisted || staked
+    }
+
+    // Every off-chain result, whoever relays it, must come from an authorized
+    // node and be signed with its registered key over this contract's digest
+    fn accept_signed_result(
+        &mut self,
+        node: Address,
+        request_id: U256,
+        output: Bytes,
+        confidence: U256,
+        signature: &[u8],
+    ) -> Result<(), Vec<u8>> {
+        let mut submitter = self.submitters.get(&node)
+            .filter(|submitter| self.is_authorized(submitter))
+            .ok_or("Not an authorized submitter")?;
+
+        let digest = attestation::result_digest(contract::address(), request_id, &output, confidence);
+        let signer = self.recover_signer(digest, signature).ok_or("Invalid signature")?;
+        require!(signer == submitter.signing_key, "Signer is not the node's key");
+
+        self.complete_off_chain_request(request_id, node, output, confidence)?;
+        submitter.results_submitted += U256::from(1);
+        self.submitters.insert(node, submitter);
+        Ok(())
+    }
+
+    fn recover_signer(&self, digest: FixedBytes<32>, signature: &[u8]) -> Option<Address> {
+        let input = attestation::ecrecover_input(digest, signature)?;
+        let output = call::static_call(Call::new(), attestation::ECRECOVER, &input).ok()?;
+        attestation::recovered_address(&output)
+    }
+
+    fn credit(&mut self, account: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let balance = self.pending_withdrawal(account);
+        self.pending_withdrawals.insert(account, balance + amount);
+    }
+
+    fn estimate_computation_gas(&self, config: &ModelConfig, input_len: usize) -> U256 {
+        let calibration = self.gas_calibrations.get(&config.model_id).unwrap_or_default();
+        calibration.apply(self.uncalibrated_gas(config, input_len))
+    }
+
+    fn uncalibrated_gas(&self, config: &ModelConfig, input_len: usize) -> U256 {
+        let mut shape = self.model_shapes.get(&config.model_id).unwrap_or_else(|| {
+            ModelShape::dense(
+                config.input_size.saturating_to::<u32>(),
+                config.output_size.saturating_to::<u32>(),
+            )
+        });
+        // Taken from the current preprocessing, which can change after the
+        // shape was registered
+        shape.pipeline_steps = gas::pipeline_steps(&config.preprocessing);
+        gas::estimate(&shape, input_len)
+    }
+
+    // Only on-chain runs, direct or as a fallback, are comparable with the
+    // estimate: cached and off-chain results never execute the model here
+    fn calibrate(&mut self, config: &ModelConfig, input_len: usize, measured: U256) {
+        let estimated = self.uncalibrated_gas(config, input_len);
+        let mut calibration = self.gas_calibrations.get(&config.model_id).unwrap_or_default();
+        calibration.record(estimated, measured, evm::block_timestamp().into());
+        self.gas_calibrations.insert(config.model_id, calibration);
+    }
+
+    // Routing off-chain means no new measurement, so an estimate inflated by
+    // one bad sample would keep the model off-chain for good. Calibration
+    // rate-limits this so a burst of requests cannot wipe it out
+    fn relax_calibration(&mut self, model_id: U256) {
+        let Some(mut calibration) = self.gas_calibrations.get(&model_id) else {
+            return;
+        };
+        if calibration.relax(evm::block_timestamp().into()) {
+            self.gas_calibrations.insert(model_id, calibration);
+        }
+    }
+
+    // Cache hits and failed runs get an id from the same allocator as every
+    // other request, so callers can always trace a result to its origin
+    fn create_result_from_cache(&mut self, model_id: U256, cache: CacheEntry) -> InferenceResult {
+        InferenceResult {
+            request_id: self.request_ids.allocate(msg::sender(), model_id),
+            input_hash: cache.input_hash.to_vec().into(),
+            output: cache.result,
+            confidence: cache.confidence,
+            timestamp: evm::block_timestamp().into(),
+            gas_used: U256::ZERO,
+            computation_source: ComputationSource::Cached,
+            success: true,
+        }
+    }
+
+    fn failed_result(&self, request_id: U256, input_hash: FixedBytes<32>) -> InferenceResult {
+        InferenceResult {
+            request_id,
+            input_hash: input_hash.to_vec().into(),
+            output: Bytes::new(),
+            confidence: U256::ZERO,
+            timestamp: evm::block_timestamp().into(),
+            gas_used: U256::ZERO,
+            computation_source: ComputationSource::OnChain,
+            success: false,
+        }
+    }
+
+    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
+        require!(msg::sender() == self.owner, "Not owner");
+        Ok(())
+    }
+}
+
+fn shape_fits(shape: &ModelShape, input_size: U256, output_size: U256) -> bool {
+    U256::from(shape.input_dim()) == input_size && U256::from(shape.output_dim()) == output_size
+}
+
+fn emit_evicted(entry: &CacheEntry, reason: u8) {
+    evm::log(CacheEvicted {
+        input_hash: entry.input_hash.0,
+        model_id: entry.model_id,
+        reason,
+    });
+}
+
+sol! {
+    event BatchProcessed(uint256 model_id, uint32 count, uint64 gas_used);
+
+    event OffChainRequestSubmitted(uint256 request_id, uint256 model_id, uint64 deadline);
+
+    event OffChainRequestExpired(
+        uint256 indexed request_id,
+        uint256 indexed model_id,
+        uint256 refunded
+    );
+
+    event OffChainResultSubmitted(
+        uint256 indexed request_id,
+        address indexed submitter,
+        bytes32 output_hash
+    );
+
+    event ComputeRequestDispatched(
+        uint256 indexed request_id,
+        address indexed network,
+        uint256 compute_request_id
+    );
+
+    event ComputeDispatchFailed(uint256 indexed request_id, address indexed network);
+
+    event SubmitterUpdated(address indexed node, address signing_key, bool whitelisted);
+
+    event SubmitterStaked(address indexed node, uint256 stake);
+
+    event SubmitterSlashed(address indexed node, uint256 amount);
+
+    event CallbackDelivered(uint256 indexed request_id, address indexed target);
+
+    event CallbackFailed(
+        uint256 indexed request_id,
+        address indexed target,
+        uint256 attempts,
+        bytes reason
+    );
+
+    event Withdrawal(address indexed account, uint256 amount);
+
+    // Model events share their signatures with lib.rs and IAIProcessor.sol so
+    // the dashboard listener decodes them from either processor
+    event ModelRegistered(uint256 indexed model_id, uint256 input_size, uint256 output_size);
+
+    event ModelUpdated(uint256 indexed model_id, uint256 version);
+
+    event InferenceCompleted(
+        uint256 indexed request_id,
+        uint256 indexed model_id,
+        bytes32 input_hash,
+        bytes32 output_hash,
+        uint256 confidence,
+        uint8 source
+    );
+
+    event CacheHit(bytes32 indexed input_hash, uint256 indexed model_id, uint256 hits);
+
+    event CacheEvicted(
+        bytes32 indexed input_hash,
+        uint256 indexed model_id,
+        // 0 expired, 1 capacity (LRU), 2 invalidated
+        uint8 reason
+    );
+
+    event ModelCacheInvalidated(uint256 indexed model_id, uint256 generation);
+
+    event StatsUpdated(
+        uint256 indexed model_id,
+        uint256 total_requests,
+        uint256 total_gas_used,
+        uint256 success_rate
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_sol_types::SolEvent;
+
+    #[test]
+    fn test_register_model() {
         let mut processor = AIProcessor::new();
-        processor.register_model(
+        let result = processor.register_model(
             U256::from(1),
             U256::from(1024),
             U256::from(128),
@@ -441,7 +1502,71 @@ mod tests {
             true,
             U256::from(3600),
             U256::from(1_000_000),
+        );
+        assert!(result.is_ok());
+    }
+
+    // A 1024x128 dense layer: on-chain under a 1M gas limit, off-chain
+    // (when enabled) under 100k
+    fn register(processor: &mut AIProcessor, model_id: u64, off_chain: bool, max_gas_limit: u64) {
+        processor.register_model(
+            U256::from(model_id),
+            U256::from(1024),
+            U256::from(128),
+            U256::from(32),
+            U256::from(100),
+            off_chain,
+            U256::from(3600),
+            U256::from(max_gas_limit),
+        ).unwrap();
+    }
+
+    // An 8x2 dense layer: cheap enough to run on-chain under 100k, so only
+    // its declared shape or calibration sends it off-chain
+    fn register_small(processor: &mut AIProcessor, model_id: u64) {
+        processor.register_model(
+            U256::from(model_id),
+            U256::from(8),
+            U256::from(2),
+            U256::from(1),
+            U256::from(100),
+            true,
+            U256::from(3600),
+            U256::from(100_000),
         ).unwrap();
+    }
+
+    // A fixed secp256k1 key standing in for a node's signing key: its
+    // address, and a signer producing r || s || v over a digest
+    fn test_signer() -> (Address, impl Fn(FixedBytes<32>) -> Bytes) {
+        use k256::ecdsa::SigningKey;
+
+        let key = SigningKey::from_bytes(&[0x42; 32].into()).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let address = Address::from_slice(&evm::keccak256(&point.as_bytes()[1..])[12..]);
+        let sign = move |digest: FixedBytes<32>| {
+            let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_slice()).unwrap();
+            let mut bytes = signature.to_bytes().to_vec();
+            bytes.push(recovery_id.to_byte());
+            Bytes::from(bytes)
+        };
+        (address, sign)
+    }
+
+    #[test]
+    fn test_process_inference() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 1_000_000);
+
+        let input = vec![1, 2, 3, 4];
+        let result = processor.process_inference(U256::from(1), input.into());
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
 
         let input = vec![1, 2, 3, 4];
         
@@ -450,11 +1575,570 @@ mod tests {
         assert!(result1.is_ok());
         
         // Second call should hit cache
-        let result2 = processor.process_inference(U256::from(1), input.into());
+        let result2 = processor.process_inference(U256::from(1), input.clone().into());
         assert!(result2.is_ok());
+        let result2 = result2.unwrap();
+        assert_eq!(result2.computation_source, ComputationSource::Cached);
+
+        // Hits get their own request id, traceable like any other
+        assert_ne!(result2.request_id, U256::ZERO);
+        let origin = processor.get_request_origin(result2.request_id).unwrap();
+        assert_eq!(origin.model_id, U256::from(1));
+        assert_eq!(origin.sender, msg::sender());
+
+        let input_hash: FixedBytes<32> = evm::keccak256(&input).into();
+        processor.process_inference(U256::from(1), input.into()).unwrap();
+        let entry = processor.get_cache_entry(U256::from(1), input_hash).unwrap();
+        assert_eq!(entry.hits, U256::from(2));
+        assert_eq!(processor.get_cache_stats().hits, U256::from(2));
+    }
+
+    // Topics must match the `indexed` fields in IAIProcessor.sol
+    #[test]
+    fn test_cache_events_index_interface_topics() {
+        let hit = CacheHit {
+            input_hash: [0x11; 32],
+            model_id: U256::from(1),
+            hits: U256::from(3),
+        };
+        let topics = hit.encode_topics();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(topics[0].0, evm::keccak256(b"CacheHit(bytes32,uint256,uint256)"));
+        assert_eq!(topics[1].0, FixedBytes::repeat_byte(0x11));
+        assert_eq!(topics[2].0, FixedBytes::from(U256::from(1).to_be_bytes::<32>()));
+
+        let evicted = CacheEvicted {
+            input_hash: [0x11; 32],
+            model_id: U256::from(1),
+            reason: 1,
+        };
+        let topics = evicted.encode_topics();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(topics[0].0, evm::keccak256(b"CacheEvicted(bytes32,uint256,uint8)"));
+
+        let completed = InferenceCompleted {
+            request_id: U256::from(7),
+            model_id: U256::from(1),
+            input_hash: [0x11; 32],
+            output_hash: [0x22; 32],
+            confidence: U256::from(90),
+            source: SOURCE_CACHED,
+        };
+        let topics = completed.encode_topics();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(topics[1].0, FixedBytes::from(U256::from(7).to_be_bytes::<32>()));
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
+
+        let input = vec![1, 2, 3, 4];
+        let input_hash: FixedBytes<32> = evm::keccak256(&input).into();
+        processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+
+        processor.invalidate_cache_entry(U256::from(1), input_hash).unwrap();
+        assert!(processor.get_cache_entry(U256::from(1), input_hash).is_err());
+        let result = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+
+        // Bumping the generation hides every entry of the model
+        processor.invalidate_model_cache(U256::from(1)).unwrap();
+        assert!(processor.get_cache_entry(U256::from(1), input_hash).is_err());
+    }
+
+    #[test]
+    fn test_cache_capacity_is_bounded() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
+        processor.set_cache_capacity(U256::from(2)).unwrap();
+
+        for byte in 1..=3u8 {
+            processor.process_inference(U256::from(1), vec![byte].into()).unwrap();
+        }
+
+        let stats = processor.get_cache_stats();
+        assert_eq!(stats.entries, U256::from(2));
+        assert_eq!(stats.evictions, U256::from(1));
+        let oldest: FixedBytes<32> = evm::keccak256(&[1u8]).into();
+        assert!(processor.get_cache_entry(U256::from(1), oldest).is_err());
+    }
+
+    #[test]
+    fn test_cache_is_isolated_per_model() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
+        register(&mut processor, 2, false, 1_000_000);
+        let input = vec![1, 2, 3, 4];
+
+        let first = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(first.computation_source, ComputationSource::OnChain);
+
+        // Same bytes, different model: must not reuse model 1's result
+        let other = processor.process_inference(U256::from(2), input.clone().into()).unwrap();
+        assert_eq!(other.computation_source, ComputationSource::OnChain);
+
+        let cached = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(cached.computation_source, ComputationSource::Cached);
+        assert_eq!(cached.output, first.output);
+        let cached = processor.process_inference(U256::from(2), input.into()).unwrap();
+        assert_eq!(cached.output, other.output);
+    }
+
+    #[test]
+    fn test_model_changes_invalidate_cache() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
+        register(&mut processor, 2, false, 1_000_000);
+        let input = vec![1, 2, 3, 4];
+        let input_hash: FixedBytes<32> = evm::keccak256(&input).into();
+
+        processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        processor.process_inference(U256::from(2), input.clone().into()).unwrap();
+
+        // Re-registering model 1 drops its entries but leaves model 2's alone
+        register(&mut processor, 1, false, 1_000_000);
+        assert!(processor.get_cache_entry(U256::from(1), input_hash).is_err());
+        assert!(processor.get_cache_entry(U256::from(2), input_hash).is_ok());
+        let result = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+
+        processor.invalidate_model_cache(U256::from(1)).unwrap();
+        let result = processor.process_inference(U256::from(1), input.into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+    }
+
+    #[test]
+    fn test_cache_is_isolated_per_preprocessing() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, false, 1_000_000);
+        let input = vec![1, 2, 3, 4];
+        let input_hash: FixedBytes<32> = evm::keccak256(&input).into();
+
+        processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        let cached = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(cached.computation_source, ComputationSource::Cached);
+        let scope = processor.cache_scope(U256::from(1));
+
+        // Same model, same input: the new pipeline must not see the old result
+        processor.set_model_preprocessing(U256::from(1), vec![0x42].into()).unwrap();
+        assert_ne!(processor.cache_scope(U256::from(1)).preprocessing_hash, scope.preprocessing_hash);
+        assert!(processor.get_cache_entry(U256::from(1), input_hash).is_err());
+        let result = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+
+        // Switching back still misses: the generation moved on as well
+        processor.set_model_preprocessing(U256::from(1), Bytes::new()).unwrap();
+        assert_eq!(processor.cache_scope(U256::from(1)).preprocessing_hash, scope.preprocessing_hash);
+        let result = processor.process_inference(U256::from(1), input.into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+    }
+
+    #[test]
+    fn test_late_off_chain_results_are_not_cached() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let node = msg::sender();
+
+        let stale = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+        let fresh = processor.process_inference(U256::from(1), vec![5, 6, 7, 8].into()).unwrap();
+        let stale_hash: FixedBytes<32> = evm::keccak256(&[1u8, 2, 3, 4]).into();
+        let fresh_hash: FixedBytes<32> = evm::keccak256(&[5u8, 6, 7, 8]).into();
+
+        // The model changes while the first request is being computed
+        register(&mut processor, 1, true, 100_000);
+        processor.complete_off_chain_request(stale.request_id, node, vec![7].into(), U256::from(90)).unwrap();
+        let request = processor.get_off_chain_request(stale.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Completed);
+        assert!(processor.get_cache_entry(U256::from(1), stale_hash).is_err());
+
+        // Submitted under the current version, so its result is cached
+        let current = processor.process_inference(U256::from(1), vec![9].into()).unwrap();
+        processor.complete_off_chain_request(current.request_id, node, vec![8].into(), U256::from(90)).unwrap();
+        let current_hash: FixedBytes<32> = evm::keccak256(&[9u8]).into();
+        assert!(processor.get_cache_entry(U256::from(1), current_hash).is_ok());
+
+        // Invalidating the model's cache counts as a change too
+        processor.invalidate_model_cache(U256::from(1)).unwrap();
+        processor.complete_off_chain_request(fresh.request_id, node, vec![6].into(), U256::from(90)).unwrap();
+        assert!(processor.get_cache_entry(U256::from(1), fresh_hash).is_err());
+    }
+
+    #[test]
+    fn test_off_chain_deadlines() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        assert!(processor.set_off_chain_policy(U256::from(1), U256::ZERO, true).is_err());
+        processor.set_off_chain_policy(U256::from(1), U256::from(600), true).unwrap();
+
+        let pending = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+        assert_eq!(pending.computation_source, ComputationSource::OffChain);
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.deadline, U256::from(evm::block_timestamp()) + U256::from(600));
+
+        // Not overdue yet: the sweep skips it, as it does unknown ids
+        let expired = processor.expire(vec![pending.request_id, U256::from(999)]).unwrap();
+        assert_eq!(expired, U256::ZERO);
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(processor.fallback(pending.request_id).unwrap_err(), b"Request not timed out".to_vec());
+
+        // The policy survives a re-register
+        register(&mut processor, 1, true, 100_000);
+        let second = processor.process_inference(U256::from(1), vec![5, 6, 7, 8].into()).unwrap();
+        let request = processor.get_off_chain_request(second.request_id).unwrap();
+        assert_eq!(request.deadline, U256::from(evm::block_timestamp()) + U256::from(600));
+        assert!(processor.expire(vec![U256::ZERO; 51]).is_err());
+    }
+
+    // Moves a pending request past its deadline
+    fn make_overdue(processor: &mut AIProcessor, request_id: U256) {
+        let mut request = processor.get_off_chain_request(request_id).unwrap();
+        request.deadline = U256::from(evm::block_timestamp()) - U256::from(1);
+        processor.off_chain_requests.insert(request_id, request);
+    }
+
+    #[test]
+    fn test_expired_requests_are_refunded_and_fall_back() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        processor.set_off_chain_policy(U256::from(1), U256::from(600), true).unwrap();
+        let requester = msg::sender();
+
+        let pending = processor.process(U256::from(1), vec![1, 2, 3, 4].into(), U256::from(1_000)).unwrap();
+        let request_id = pending.request_id;
+        assert_eq!(processor.get_off_chain_request(request_id).unwrap().escrowed_fee, U256::from(1_000));
+        assert_eq!(processor.pending_withdrawal(requester), U256::ZERO);
+
+        make_overdue(&mut processor, request_id);
+        assert_eq!(processor.expire(vec![request_id]).unwrap(), U256::from(1));
+        let request = processor.get_off_chain_request(request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::TimedOut);
+        assert_eq!(request.escrowed_fee, U256::ZERO);
+        assert_eq!(processor.pending_withdrawal(requester), U256::from(1_000));
+
+        // Expiring only refunds: the model has not run and late results are refused
+        assert!(processor.inference_results.get(&request_id).is_none());
+        assert_eq!(processor.expire(vec![request_id]).unwrap(), U256::ZERO);
         assert_eq!(
-            result2.unwrap().computation_source,
-            ComputationSource::Cached
+            processor.complete_off_chain_request(request_id, requester, vec![7].into(), U256::from(90)).unwrap_err(),
+            b"Invalid request status".to_vec()
         );
+
+        assert_eq!(processor.withdraw().unwrap(), U256::from(1_000));
+        assert_eq!(processor.pending_withdrawal(requester), U256::ZERO);
+        assert!(processor.withdraw().is_err());
+
+        // The fallback computes it on-chain, once
+        assert!(processor.fallback(request_id).unwrap());
+        let request = processor.get_off_chain_request(request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Completed);
+        assert_eq!(request.fulfilled_by, contract::address());
+        let result = processor.inference_results.get(&request_id).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+        assert!(result.success);
+        assert!(processor.fallback(request_id).is_err());
+
+        // Without the policy a timed-out request is only refunded
+        processor.set_off_chain_policy(U256::from(1), U256::from(600), false).unwrap();
+        let dropped = processor.process_inference(U256::from(1), vec![5, 6, 7, 8].into()).unwrap();
+        make_overdue(&mut processor, dropped.request_id);
+        assert_eq!(processor.expire(vec![dropped.request_id]).unwrap(), U256::from(1));
+        assert_eq!(processor.fallback(dropped.request_id).unwrap_err(), b"Fallback disabled".to_vec());
+    }
+
+    #[test]
+    fn test_callbacks_skip_accounts_without_code() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let node = msg::sender();
+        let (signing_key, sign) = test_signer();
+        processor.set_submitter(node, signing_key, true).unwrap();
+
+        let pending = processor.process(U256::from(1), vec![1, 2, 3, 4].into(), U256::from(500)).unwrap();
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.callback_status, CallbackStatus::None);
+
+        let digest = processor.result_digest(pending.request_id, vec![7].into(), U256::from(90));
+        processor
+            .submit_off_chain_result(pending.request_id, vec![7].into(), U256::from(90), sign(digest))
+            .unwrap();
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Completed);
+        assert_eq!(request.callback_attempts, U256::ZERO);
+        assert_eq!(request.fulfilled_by, node);
+        assert_eq!(request.escrowed_fee, U256::ZERO);
+        assert_eq!(processor.pending_withdrawal(node), U256::from(500));
+        assert_eq!(processor.get_submitter(node).results_submitted, U256::from(1));
+
+        // Nothing failed, so there is nothing to retry
+        assert!(processor.retry_callback(pending.request_id, U256::from(CALLBACK_GAS_STIPEND)).is_err());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_signed_results_are_checked() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let node = msg::sender();
+        let (signing_key, sign) = test_signer();
+        processor.set_submitter(node, signing_key, true).unwrap();
+        let pending = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+
+        // A signature over other values does not carry over
+        let digest = processor.result_digest(pending.request_id, vec![7].into(), U256::from(90));
+        assert_eq!(
+            processor
+                .submit_off_chain_result(pending.request_id, vec![8].into(), U256::from(90), sign(digest))
+                .unwrap_err(),
+            b"Signer is not the node's key".to_vec()
+        );
+
+        // Confidence is a percentage, even when correctly signed
+        let digest = processor.result_digest(pending.request_id, vec![7].into(), U256::from(101));
+        assert_eq!(
+            processor
+                .submit_off_chain_result(pending.request_id, vec![7].into(), U256::from(101), sign(digest))
+                .unwrap_err(),
+            b"Confidence out of range".to_vec()
+        );
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(processor.get_submitter(node).results_submitted, U256::ZERO);
+    }
+
+
+    // Points a pending request's callback at `target`, as if a contract at
+    // that address had sent it
+    fn expect_callback(processor: &mut AIProcessor, request_id: U256, target: Address) {
+        let mut request = processor.get_off_chain_request(request_id).unwrap();
+        request.callback_address = target;
+        request.callback_status = CallbackStatus::Pending;
+        processor.off_chain_requests.insert(request_id, request);
+    }
+
+    #[test]
+    fn test_callback_delivery_and_retry() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let node = msg::sender();
+
+        // A target that accepts the call
+        let delivered = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+        expect_callback(&mut processor, delivered.request_id, Address::repeat_byte(0x77));
+        processor.complete_off_chain_request(delivered.request_id, node, vec![7].into(), U256::from(90)).unwrap();
+        let request = processor.get_off_chain_request(delivered.request_id).unwrap();
+        assert_eq!(request.callback_status, CallbackStatus::Delivered);
+        assert_eq!(request.callback_attempts, U256::from(1));
+        assert!(processor.retry_callback(delivered.request_id, U256::from(CALLBACK_GAS_STIPEND)).is_err());
+
+        // This contract has no onInferenceResult, so the call reverts. The
+        // result is still stored and the request completed
+        let failed = processor.process_inference(U256::from(1), vec![5, 6, 7, 8].into()).unwrap();
+        expect_callback(&mut processor, failed.request_id, contract::address());
+        processor.complete_off_chain_request(failed.request_id, node, vec![8].into(), U256::from(90)).unwrap();
+        let request = processor.get_off_chain_request(failed.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Completed);
+        assert_eq!(request.callback_status, CallbackStatus::Failed);
+        assert_eq!(request.callback_attempts, U256::from(1));
+        assert!(processor.inference_results.get(&failed.request_id).is_some());
+
+        // Retries must forward at least the stipend and be able to pay for it
+        assert_eq!(
+            processor.retry_callback(failed.request_id, U256::from(CALLBACK_GAS_STIPEND - 1)).unwrap_err(),
+            b"Gas limit below stipend".to_vec()
+        );
+        assert_eq!(
+            processor.retry_callback(failed.request_id, U256::from(u64::MAX)).unwrap_err(),
+            b"Insufficient gas for callback".to_vec()
+        );
+        assert!(!processor.retry_callback(failed.request_id, U256::from(CALLBACK_GAS_STIPEND)).unwrap());
+        let request = processor.get_off_chain_request(failed.request_id).unwrap();
+        assert_eq!(request.callback_status, CallbackStatus::Failed);
+        assert_eq!(request.callback_attempts, U256::from(2));
+
+        // Once the target accepts the call, a retry delivers it
+        let mut request = processor.get_off_chain_request(failed.request_id).unwrap();
+        request.callback_address = Address::repeat_byte(0x77);
+        processor.off_chain_requests.insert(failed.request_id, request);
+        assert!(processor.retry_callback(failed.request_id, U256::from(CALLBACK_GAS_STIPEND)).unwrap());
+        let request = processor.get_off_chain_request(failed.request_id).unwrap();
+        assert_eq!(request.callback_status, CallbackStatus::Delivered);
+        assert_eq!(request.callback_attempts, U256::from(3));
+        assert!(request.callback_error.is_empty());
+    }
+
+    #[test]
+    fn test_only_authorized_submitters() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let pending = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+        let node = msg::sender();
+        let submit = |processor: &mut AIProcessor| {
+            processor.submit_off_chain_result(
+                pending.request_id,
+                vec![7].into(),
+                U256::from(90),
+                vec![0u8; 65].into(),
+            )
+        };
+
+        assert_eq!(submit(&mut processor).unwrap_err(), b"Not an authorized submitter".to_vec());
+        assert!(!processor.is_authorized_submitter(node));
+
+        // Whitelisted, but the signature does not come from its key
+        processor.set_submitter(node, Address::repeat_byte(0x51), true).unwrap();
+        assert!(processor.is_authorized_submitter(node));
+        assert_eq!(submit(&mut processor).unwrap_err(), b"Invalid signature".to_vec());
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(request.fulfilled_by, Address::ZERO);
+
+        // Staking only counts once enabled, and not while unbonding
+        processor.set_submitter(node, Address::repeat_byte(0x51), false).unwrap();
+        assert!(processor.stake_as_submitter(Address::repeat_byte(0x51)).is_err());
+        processor.set_min_submitter_stake(U256::from(1)).unwrap();
+        assert!(!processor.is_authorized_submitter(node));
+        assert!(processor.request_unstake().is_err());
+        assert!(processor.withdraw_stake().is_err());
+    }
+
+    #[test]
+    fn test_compute_results_need_a_dispatched_request() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let pending = processor.process_inference(U256::from(1), vec![1, 2, 3, 4].into()).unwrap();
+
+        // No network configured: the request is left to signed node submissions
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.dispatched_to, Address::ZERO);
+        assert_eq!(processor.pull_compute_result(pending.request_id).unwrap_err(), b"Not dispatched".to_vec());
+        assert!(processor
+            .on_compute_result(U256::from(1), msg::sender(), vec![7].into(), U256::from(90), Bytes::new())
+            .is_err());
+
+        let request = processor.get_off_chain_request(pending.request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+    }
+
+    #[test]
+    fn test_compute_results_need_an_authorized_processor() {
+        let mut processor = AIProcessor::new();
+        register(&mut processor, 1, true, 100_000);
+        let pending = processor.process(U256::from(1), vec![1, 2, 3, 4].into(), U256::from(500)).unwrap();
+        let request_id = pending.request_id;
+
+        // As if dispatched to a network at the test sender's address
+        let compute_request_id = U256::from(77);
+        let mut request = processor.get_off_chain_request(request_id).unwrap();
+        request.dispatched_to = msg::sender();
+        request.compute_request_id = compute_request_id;
+        processor.off_chain_requests.insert(request_id, request);
+        processor.compute_requests.insert(compute_request_id, request_id);
+
+        // Registering with the network is open to anyone: a correctly signed
+        // result from a processor that is not a submitter here is refused
+        let compute_node = Address::repeat_byte(0x66);
+        let (signing_key, sign) = test_signer();
+        let digest = processor.result_digest(request_id, vec![7].into(), U256::from(90));
+        let signature = sign(digest);
+        let push = |processor: &mut AIProcessor, signature: Bytes| {
+            processor.on_compute_result(compute_request_id, compute_node, vec![7].into(), U256::from(90), signature)
+        };
+        assert_eq!(push(&mut processor, signature.clone()).unwrap_err(), b"Not an authorized submitter".to_vec());
+
+        // Authorized, but the result must still carry its signature
+        processor.set_submitter(compute_node, signing_key, true).unwrap();
+        assert_eq!(push(&mut processor, vec![0u8; 65].into()).unwrap_err(), b"Invalid signature".to_vec());
+        let request = processor.get_off_chain_request(request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Pending);
+        assert_eq!(processor.pending_withdrawal(compute_node), U256::ZERO);
+
+        assert!(push(&mut processor, signature).unwrap());
+        let request = processor.get_off_chain_request(request_id).unwrap();
+        assert_eq!(request.status, RequestStatus::Completed);
+        assert_eq!(request.fulfilled_by, compute_node);
+        assert_eq!(processor.pending_withdrawal(compute_node), U256::from(500));
+        assert_eq!(processor.get_submitter(compute_node).results_submitted, U256::from(1));
+    }
+
+    #[test]
+    fn test_gas_estimate_drives_routing() {
+        let mut processor = AIProcessor::new();
+        register_small(&mut processor, 1);
+
+        let small = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert!(!small.off_chain);
+        assert_eq!(small.calibration_samples, U256::ZERO);
+        assert!(processor.estimate_gas(U256::from(1), U256::from(9)).is_err());
+
+        // On-chain runs calibrate the estimate
+        let result = processor.process_inference(U256::from(1), vec![1; 8].into()).unwrap();
+        assert_eq!(result.computation_source, ComputationSource::OnChain);
+        let calibrated = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert_eq!(calibrated.calibration_samples, U256::from(1));
+        assert_eq!(calibrated.uncalibrated_gas, small.uncalibrated_gas);
+
+        // The declared shape must match the model's sizes
+        assert!(processor.set_model_shape(U256::from(1), gas::KIND_NETWORK, vec![8, 4], 0, 0, 4).is_err());
+        processor.set_model_shape(U256::from(1), gas::KIND_NETWORK, vec![8, 512, 512, 2], 0, 0, 4).unwrap();
+        let deep = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert!(deep.off_chain);
+        assert_eq!(deep.calibration_samples, U256::ZERO);
+
+        // Quantizing the same layers makes it cheaper
+        processor.set_model_shape(U256::from(1), gas::KIND_QUANTIZED, vec![8, 512, 512, 2], 0, 0, 1).unwrap();
+        let quantized = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert!(quantized.estimated_gas < deep.estimated_gas);
+
+        // Each preprocessing step adds to the estimate
+        processor.set_model_preprocessing(U256::from(1), b"BSPP\x01\x03".to_vec().into()).unwrap();
+        let preprocessed = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert!(preprocessed.uncalibrated_gas > quantized.uncalibrated_gas);
+    }
+
+    #[test]
+    fn test_inflated_calibration_recovers() {
+        let mut processor = AIProcessor::new();
+        register_small(&mut processor, 1);
+        let input = vec![1; 8];
+        assert!(!processor.estimate_gas(U256::from(1), U256::from(8)).unwrap().off_chain);
+
+        // A run of bad measurements made the model look ten times as expensive
+        let mut calibration = gas::Calibration::default();
+        for _ in 0..21 {
+            calibration.record(U256::from(1), U256::from(1_000), evm::block_timestamp().into());
+        }
+        processor.gas_calibrations.insert(U256::from(1), calibration);
+        assert!(processor.estimate_gas(U256::from(1), U256::from(8)).unwrap().off_chain);
+
+        // Right after a measurement, off-chain requests leave it alone
+        let inflated = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        for _ in 0..10 {
+            processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+        }
+        let unchanged = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert_eq!(unchanged.estimated_gas, inflated.estimated_gas);
+
+        // Once per interval, a request routed off-chain eases it back until
+        // one runs here; the interval is rewound instead of waited out
+        let mut off_chain = 0;
+        loop {
+            let mut calibration = processor.gas_calibrations.get(&U256::from(1)).unwrap();
+            calibration.updated_at = U256::ZERO;
+            processor.gas_calibrations.insert(U256::from(1), calibration);
+            let result = processor.process_inference(U256::from(1), input.clone().into()).unwrap();
+            if result.computation_source == ComputationSource::OnChain {
+                break;
+            }
+            off_chain += 1;
+            assert!(off_chain < 40, "estimate never recovered");
+        }
+        assert!(off_chain > 0);
+
+        // That run measured the real cost, which keeps it on-chain
+        let estimate = processor.estimate_gas(U256::from(1), U256::from(8)).unwrap();
+        assert!(!estimate.off_chain);
+        assert_eq!(estimate.calibration_samples, U256::from(22));
+    }
+}
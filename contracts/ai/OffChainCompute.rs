@@ -1,5 +1,6 @@
 use stylus_sdk::{
     alloy_primitives::U256,
+    call::Call,
     prelude::*,
     stylus_proc::stylus_fn,
 };
@@ -8,6 +9,22 @@ use alloy_primitives::{Address, Bytes};
 use core::marker::PhantomData;
 use wee_alloc::WeeAlloc;
 
+// Request id allocator shared with AIProcessor (src/lib.rs)
+#[path = "src/requests.rs"]
+mod requests;
+
+use requests::{RequestIdAllocator, RequestOrigin};
+
+// Gas forwarded to the requester's onComputeResult. AIProcessorStylus needs
+// room to store the result and run its own user callback.
+const COMPUTE_CALLBACK_GAS: u64 = 1_000_000;
+
+sol_interface! {
+    interface IComputeCallback {
+        function onComputeResult(uint256 requestId, address processor, bytes output, uint256 confidence, bytes signature) external;
+    }
+}
+
 #[global_allocator]
 static ALLOC: WeeAlloc = WeeAlloc::INIT;
 
@@ -16,6 +33,7 @@ pub struct OffChainCompute {
     owner: Address,
     processors: StorageMap<Address, ProcessorInfo>,
     compute_requests: StorageMap<U256, ComputeRequest>,
+    request_ids: RequestIdAllocator,
     _phantom: PhantomData<()>,
 }
 
@@ -38,12 +56,18 @@ pub struct ComputeRequest {
     deadline: U256,
     status: RequestStatus,
     result: Option<ComputeResult>,
+    // Requesting contract notified on completion; 0 for accounts without code
+    callback: Address,
 }
 
 #[derive(Debug, Clone, Storage)]
 pub struct ComputeResult {
     output: Bytes,
     confidence: U256,
+    // Processor's attestation of the output, in whatever scheme the requester
+    // checks (AIProcessorStylus: its resultDigest signed with the node key).
+    // Opaque here; registering as a processor alone earns no trust.
+    signature: Bytes,
     compute_time: U256,
     resources_used: U256,
 }
@@ -64,6 +88,7 @@ impl OffChainCompute {
             owner: msg::sender(),
             processors: StorageMap::new(),
             compute_requests: StorageMap::new(),
+            request_ids: RequestIdAllocator::new(requests::DOMAIN_OFF_CHAIN_COMPUTE),
             _phantom: PhantomData,
         }
     }
@@ -101,7 +126,7 @@ impl OffChainCompute {
     ) -> Result<U256, Vec<u8>> {
         require!(deadline > evm::block_timestamp().into(), "Invalid deadline");
 
-        let request_id = self.get_next_request_id();
+        let request_id = self.request_ids.allocate(msg::sender(), model_id);
         let processor = self.select_best_processor()?;
 
         let request = ComputeRequest {
@@ -112,6 +137,11 @@ impl OffChainCompute {
             deadline,
             status: RequestStatus::Pending,
             result: None,
+            callback: if msg::sender().has_code() {
+                msg::sender()
+            } else {
+                Address::ZERO
+            },
         };
 
         self.compute_requests.insert(request_id, request);
@@ -134,6 +164,7 @@ impl OffChainCompute {
         confidence: U256,
         compute_time: U256,
         resources_used: U256,
+        signature: Bytes,
     ) -> Result<bool, Vec<u8>> {
         let mut request = self.compute_requests.get(&request_id)
             .ok_or("Request not found")?;
@@ -142,11 +173,13 @@ impl OffChainCompute {
         require!(request.status == RequestStatus::Pending, "Invalid status");
         require!(request.deadline > evm::block_timestamp().into(), "Request expired");
 
+        let callback = request.callback;
         let result = ComputeResult {
-            output,
+            output: output.clone(),
             confidence,
             compute_time,
             resources_used,
+            signature: signature.clone(),
         };
 
         request.result = Some(result.clone());
@@ -166,9 +199,50 @@ impl OffChainCompute {
             compute_time: compute_time.as_u64(),
         });
 
+        // A failing callback must not lose the processor's result; the
+        // requester can still read it with getComputeResult
+        if callback != Address::ZERO {
+            let delivered = IComputeCallback::new(callback).on_compute_result(
+                Call::new_in(self).gas(COMPUTE_CALLBACK_GAS),
+                request_id,
+                msg::sender(),
+                output,
+                confidence,
+                signature,
+            );
+            if delivered.is_err() {
+                emit!(ComputeCallbackFailed {
+                    request_id,
+                    callback,
+                });
+            }
+        }
+
         Ok(true)
     }
 
+    // (completed, processor, output, confidence, signature); disputed results
+    // are not reported as completed
+    #[stylus_fn(name = "getComputeResult")]
+    pub fn get_compute_result(
+        &self,
+        request_id: U256,
+    ) -> Result<(bool, Address, Bytes, U256, Bytes), Vec<u8>> {
+        let request = self.compute_requests.get(&request_id)
+            .ok_or("Request not found")?;
+
+        match (&request.status, request.result) {
+            (RequestStatus::Completed, Some(result)) => Ok((
+                true,
+                request.processor,
+                result.output,
+                result.confidence,
+                result.signature,
+            )),
+            _ => Ok((false, request.processor, Bytes::new(), U256::ZERO, Bytes::new())),
+        }
+    }
+
     #[stylus_fn(name = "disputeResult")]
     pub fn dispute_result(
         &mut self,
@@ -202,6 +276,12 @@ impl OffChainCompute {
     }
 
     // Helper functions
+    #[stylus_fn(name = "getRequestOrigin")]
+    pub fn get_request_origin(&self, request_id: U256) -> Result<RequestOrigin, Vec<u8>> {
+        self.request_ids.origin(request_id)
+            .ok_or_else(|| "Request not found".into())
+    }
+
     fn select_best_processor(&self) -> Result<Address, Vec<u8>> {
         let mut best_processor = None;
         let mut highest_score = U256::ZERO;
@@ -232,19 +312,6 @@ impl OffChainCompute {
             .saturating_add(reputation_factor)
             .saturating_add(success_factor)
     }
-
-    fn get_next_request_id(&self) -> U256 {
-        let block_number = evm::block_number();
-        let timestamp = evm::block_timestamp();
-        let sender = msg::sender();
-        
-        let mut data = Vec::with_capacity(32 * 3);
-        data.extend_from_slice(&block_number.to_be_bytes());
-        data.extend_from_slice(&timestamp.to_be_bytes());
-        data.extend_from_slice(sender.as_bytes());
-        
-        U256::from_be_bytes(evm::keccak256(&data))
-    }
 }
 
 #[derive(Debug)]
@@ -269,6 +336,12 @@ pub struct ComputeResultSubmitted {
     compute_time: u64,
 }
 
+#[derive(Debug)]
+pub struct ComputeCallbackFailed {
+    request_id: U256,
+    callback: Address,
+}
+
 #[derive(Debug)]
 pub struct ResultDisputed {
     request_id: U256,
@@ -288,6 +361,10 @@ impl Event for ComputeResultSubmitted {
     const SIGNATURE: [u8; 32] = keccak256!("ComputeResultSubmitted(uint256,address,uint64,uint64)");
 }
 
+impl Event for ComputeCallbackFailed {
+    const SIGNATURE: [u8; 32] = keccak256!("ComputeCallbackFailed(uint256,address)");
+}
+
 impl Event for ResultDisputed {
     const SIGNATURE: [u8; 32] = keccak256!("ResultDisputed(uint256,address,bytes32)");
 }
@@ -342,7 +419,32 @@ mod tests {
             U256::from(95),
             U256::from(100),
             U256::from(1000),
+            vec![0xaa; 65].into(),
         );
         assert!(result.is_ok());
+
+        let (completed, processor, output, confidence, signature) =
+            compute.get_compute_result(request_id).unwrap();
+        assert!(completed);
+        assert_eq!(processor, msg::sender());
+        assert_eq!(output, Bytes::from(vec![5, 6, 7, 8]));
+        assert_eq!(confidence, U256::from(95));
+        assert_eq!(signature, Bytes::from(vec![0xaa; 65]));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_request_ids_are_unique() {
+        let mut compute = OffChainCompute::new();
+        compute.register_processor(U256::from(1000)).unwrap();
+        let deadline = evm::block_timestamp() + 3600;
+
+        // Same sender, same block, same payload
+        let first = compute.submit_compute_request(vec![1].into(), U256::from(1), deadline.into()).unwrap();
+        let second = compute.submit_compute_request(vec![1].into(), U256::from(1), deadline.into()).unwrap();
+        assert_ne!(first, second);
+
+        let origin = compute.get_request_origin(second).unwrap();
+        assert_eq!(origin.sender, msg::sender());
+        assert_eq!(origin.model_id, U256::from(1));
+    }
+}
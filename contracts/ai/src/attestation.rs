@@ -0,0 +1,120 @@
+// Firmas de los nodos que entregan resultados off-chain. Cada nodo firma con
+// su clave registrada, con el prefijo de eth_sign, el hash de (contrato,
+// request_id, keccak(output), confidence). Meter la dirección del contrato
+// impide reutilizar una firma en otro despliegue. La recuperación la hace el
+// precompilado ecrecover; aquí solo se arma su entrada y se lee su salida.
+// AIProcessorStylus la incluye con #[path = "src/attestation.rs"].
+
+use stylus_sdk::alloy_primitives::U256;
+
+use alloy_primitives::{keccak256, Address, FixedBytes};
+
+pub const ECRECOVER: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+]);
+
+pub const SIGNATURE_LENGTH: usize = 65;
+
+// n/2 de secp256k1: una s mayor es la versión maleable de otra firma válida
+const HALF_CURVE_ORDER: U256 = U256::from_limbs([
+    0xdfe9_2f46_681b_20a0,
+    0x5d57_6e73_57a4_501d,
+    0xffff_ffff_ffff_ffff,
+    0x7fff_ffff_ffff_ffff,
+]);
+
+pub fn result_digest(
+    contract: Address,
+    request_id: U256,
+    output: &[u8],
+    confidence: U256,
+) -> FixedBytes<32> {
+    let mut data = [0u8; 128];
+    data[12..32].copy_from_slice(contract.as_slice());
+    data[32..64].copy_from_slice(&request_id.to_be_bytes::<32>());
+    data[64..96].copy_from_slice(keccak256(output).as_slice());
+    data[96..].copy_from_slice(&confidence.to_be_bytes::<32>());
+
+    let mut prefixed = [0u8; 60];
+    prefixed[..28].copy_from_slice(b"\x19Ethereum Signed Message:\n32");
+    prefixed[28..].copy_from_slice(keccak256(data).as_slice());
+    keccak256(prefixed)
+}
+
+// Entrada de ecrecover (hash, v, r, s) para una firma r || s || v. Acepta v
+// como 27/28 o 0/1; None si la firma está mal formada o es maleable.
+pub fn ecrecover_input(digest: FixedBytes<32>, signature: &[u8]) -> Option<[u8; 128]> {
+    if signature.len() != SIGNATURE_LENGTH {
+        return None;
+    }
+    let r = U256::from_be_slice(&signature[..32]);
+    let s = U256::from_be_slice(&signature[32..64]);
+    let v = match signature[64] {
+        0 | 1 => signature[64] + 27,
+        27 | 28 => signature[64],
+        _ => return None,
+    };
+    if r.is_zero() || s.is_zero() || s > HALF_CURVE_ORDER {
+        return None;
+    }
+
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(digest.as_slice());
+    input[63] = v;
+    input[64..].copy_from_slice(&signature[..64]);
+    Some(input)
+}
+
+// ecrecover devuelve la dirección en 32 bytes, o nada si la firma no vale
+pub fn recovered_address(output: &[u8]) -> Option<Address> {
+    if output.len() != 32 {
+        return None;
+    }
+    let address = Address::from_slice(&output[12..]);
+    (address != Address::ZERO).then_some(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(s: U256, v: u8) -> Vec<u8> {
+        let mut signature = vec![0x11; 32];
+        signature.extend_from_slice(&s.to_be_bytes::<32>());
+        signature.push(v);
+        signature
+    }
+
+    #[test]
+    fn test_digest_binds_every_field() {
+        let contract = Address::repeat_byte(0xaa);
+        let base = result_digest(contract, U256::from(1), &[1, 2], U256::from(9_000));
+
+        assert_ne!(base, result_digest(Address::repeat_byte(0xbb), U256::from(1), &[1, 2], U256::from(9_000)));
+        assert_ne!(base, result_digest(contract, U256::from(2), &[1, 2], U256::from(9_000)));
+        assert_ne!(base, result_digest(contract, U256::from(1), &[1, 3], U256::from(9_000)));
+        assert_ne!(base, result_digest(contract, U256::from(1), &[1, 2], U256::from(8_000)));
+        assert_eq!(base, result_digest(contract, U256::from(1), &[1, 2], U256::from(9_000)));
+    }
+
+    #[test]
+    fn test_rejects_malformed_signatures() {
+        let digest = FixedBytes::repeat_byte(0x01);
+
+        let input = ecrecover_input(digest, &signature(U256::from(5), 0)).unwrap();
+        assert_eq!(input[63], 27);
+        assert_eq!(&input[..32], digest.as_slice());
+        assert!(ecrecover_input(digest, &signature(U256::from(5), 28)).is_some());
+
+        assert!(ecrecover_input(digest, &signature(U256::from(5), 2)).is_none());
+        assert!(ecrecover_input(digest, &signature(U256::ZERO, 27)).is_none());
+        assert!(ecrecover_input(digest, &signature(HALF_CURVE_ORDER, 27)).is_some());
+        assert!(ecrecover_input(digest, &signature(HALF_CURVE_ORDER + U256::from(1), 27)).is_none());
+        assert!(ecrecover_input(digest, &[0u8; 64]).is_none());
+
+        assert!(recovered_address(&[0u8; 32]).is_none());
+        let mut output = [0u8; 32];
+        output[31] = 7;
+        assert!(recovered_address(&output).is_some());
+    }
+}
@@ -0,0 +1,339 @@
+// Caché acotada de resultados con caducidad (TTL) y expulsión LRU. Las
+// entradas forman una lista doblemente enlazada en almacenamiento, de la más
+// usada (head) a la menos usada (tail), así tocar, insertar y expulsar cuesta
+// lo mismo con 10 que con 10.000 entradas. La clave cero hace de nulo: nunca
+// sale de keccak. AIProcessorStylus la incluye con #[path = "src/cache.rs"].
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+use alloy_primitives::{keccak256, Bytes, FixedBytes};
+
+pub const EVICT_EXPIRED: u8 = 0;
+pub const EVICT_CAPACITY: u8 = 1;
+pub const EVICT_INVALIDATED: u8 = 2;
+
+const NIL: FixedBytes<32> = FixedBytes::ZERO;
+
+#[derive(Debug, Clone, Storage)]
+pub struct CacheEntry {
+    pub model_id: U256,
+    pub input_hash: FixedBytes<32>,
+    pub result: Bytes,
+    pub confidence: U256,
+    pub timestamp: U256,
+    pub ttl: U256,
+    pub hits: U256,
+    prev: FixedBytes<32>,
+    next: FixedBytes<32>,
+}
+
+impl CacheEntry {
+    // Un ttl enorme significa que no caduca, no un desbordamiento
+    pub fn is_expired(&self, now: U256) -> bool {
+        self.timestamp.saturating_add(self.ttl) <= now
+    }
+}
+
+#[derive(Debug, Clone, Storage)]
+pub struct CacheStats {
+    pub entries: U256,
+    pub capacity: U256,
+    pub hits: U256,
+    pub misses: U256,
+    pub evictions: U256,
+}
+
+#[derive(Debug)]
+pub enum Lookup {
+    Hit(CacheEntry),
+    // La entrada existía pero había caducado; ya se ha quitado
+    Expired(CacheEntry),
+    Miss,
+}
+
+#[derive(Debug)]
+pub struct LruCache {
+    entries: StorageMap<FixedBytes<32>, CacheEntry>,
+    head: FixedBytes<32>,
+    tail: FixedBytes<32>,
+    len: U256,
+    capacity: U256,
+    hits: U256,
+    misses: U256,
+    evictions: U256,
+}
+
+impl LruCache {
+    pub fn new(capacity: U256) -> Self {
+        Self {
+            entries: StorageMap::new(),
+            head: NIL,
+            tail: NIL,
+            len: U256::ZERO,
+            capacity,
+            hits: U256::ZERO,
+            misses: U256::ZERO,
+            evictions: U256::ZERO,
+        }
+    }
+
+    // Un acierto suma a los contadores y pasa la entrada al frente
+    pub fn lookup(&mut self, key: FixedBytes<32>, now: U256) -> Lookup {
+        let Some(mut entry) = self.entries.get(&key) else {
+            self.misses += U256::from(1);
+            return Lookup::Miss;
+        };
+        if entry.is_expired(now) {
+            self.misses += U256::from(1);
+            self.evictions += U256::from(1);
+            return Lookup::Expired(self.unlink(key, entry));
+        }
+
+        self.hits += U256::from(1);
+        entry.hits += U256::from(1);
+        let entry = self.unlink(key, entry);
+        Lookup::Hit(self.link_front(key, entry))
+    }
+
+    // Inserta o reemplaza `key`; devuelve las entradas expulsadas para hacerle
+    // sitio (siempre la menos usada)
+    pub fn insert(&mut self, key: FixedBytes<32>, mut entry: CacheEntry) -> Vec<CacheEntry> {
+        if self.capacity.is_zero() {
+            return Vec::new();
+        }
+        if let Some(previous) = self.entries.get(&key) {
+            self.unlink(key, previous);
+        }
+
+        let mut evicted = Vec::new();
+        while self.len >= self.capacity {
+            match self.pop_back() {
+                Some(old) => evicted.push(old),
+                None => break,
+            }
+        }
+
+        entry.hits = U256::ZERO;
+        self.link_front(key, entry);
+        evicted
+    }
+
+    pub fn remove(&mut self, key: FixedBytes<32>) -> Option<CacheEntry> {
+        let entry = self.entries.get(&key)?;
+        self.evictions += U256::from(1);
+        Some(self.unlink(key, entry))
+    }
+
+    // Reducir la capacidad expulsa lo que sobre, empezando por lo menos usado
+    pub fn set_capacity(&mut self, capacity: U256) -> Vec<CacheEntry> {
+        self.capacity = capacity;
+        let mut evicted = Vec::new();
+        while self.len > self.capacity {
+            match self.pop_back() {
+                Some(old) => evicted.push(old),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    pub fn peek(&self, key: FixedBytes<32>) -> Option<CacheEntry> {
+        self.entries.get(&key)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.len,
+            capacity: self.capacity,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+
+    fn pop_back(&mut self) -> Option<CacheEntry> {
+        if self.tail == NIL {
+            return None;
+        }
+        let key = self.tail;
+        let entry = self.entries.get(&key)?;
+        self.evictions += U256::from(1);
+        Some(self.unlink(key, entry))
+    }
+
+    fn link_front(&mut self, key: FixedBytes<32>, mut entry: CacheEntry) -> CacheEntry {
+        entry.prev = NIL;
+        entry.next = self.head;
+        if let Some(mut first) = self.entries.get(&self.head) {
+            first.prev = key;
+            self.entries.insert(self.head, first);
+        } else {
+            self.tail = key;
+        }
+        self.head = key;
+        self.len += U256::from(1);
+        self.entries.insert(key, entry.clone());
+        entry
+    }
+
+    fn unlink(&mut self, key: FixedBytes<32>, entry: CacheEntry) -> CacheEntry {
+        match self.entries.get(&entry.prev) {
+            Some(mut prev) => {
+                prev.next = entry.next;
+                self.entries.insert(entry.prev, prev);
+            }
+            None => self.head = entry.next,
+        }
+        match self.entries.get(&entry.next) {
+            Some(mut next) => {
+                next.prev = entry.prev;
+                self.entries.insert(entry.next, next);
+            }
+            None => self.tail = entry.prev,
+        }
+        self.entries.remove(&key);
+        self.len -= U256::from(1);
+        entry
+    }
+}
+
+pub fn new_entry(
+    model_id: U256,
+    input_hash: FixedBytes<32>,
+    result: Bytes,
+    confidence: U256,
+    timestamp: U256,
+    ttl: U256,
+) -> CacheEntry {
+    CacheEntry {
+        model_id,
+        input_hash,
+        result,
+        confidence,
+        timestamp,
+        ttl,
+        hits: U256::ZERO,
+        prev: NIL,
+        next: NIL,
+    }
+}
+
+// Lo que determina el resultado de un modelo para una entrada dada. Cambiar
+// cualquier campo deja inalcanzables las entradas anteriores, que salen
+// después por LRU; `generation` permite invalidarlas sin cambiar el modelo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Storage)]
+pub struct CacheScope {
+    pub model_id: U256,
+    pub version: U256,
+    pub preprocessing_hash: FixedBytes<32>,
+    pub generation: U256,
+}
+
+pub fn cache_key(scope: &CacheScope, input_hash: FixedBytes<32>) -> FixedBytes<32> {
+    let mut data = [0u8; 160];
+    data[..32].copy_from_slice(&scope.model_id.to_be_bytes::<32>());
+    data[32..64].copy_from_slice(&scope.version.to_be_bytes::<32>());
+    data[64..96].copy_from_slice(scope.preprocessing_hash.as_slice());
+    data[96..128].copy_from_slice(&scope.generation.to_be_bytes::<32>());
+    data[128..].copy_from_slice(input_hash.as_slice());
+    keccak256(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(model_id: u64, ttl: u64) -> CacheEntry {
+        new_entry(
+            U256::from(model_id),
+            FixedBytes::repeat_byte(model_id as u8),
+            Bytes::from(vec![model_id as u8]),
+            U256::from(9_000),
+            U256::from(100),
+            U256::from(ttl),
+        )
+    }
+
+    fn scope(model_id: u64) -> CacheScope {
+        CacheScope {
+            model_id: U256::from(model_id),
+            version: U256::from(1),
+            preprocessing_hash: FixedBytes::ZERO,
+            generation: U256::ZERO,
+        }
+    }
+
+    fn key(n: u64) -> FixedBytes<32> {
+        cache_key(&scope(n), FixedBytes::repeat_byte(0xaa))
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = LruCache::new(U256::from(2));
+        assert!(cache.insert(key(1), entry(1, 60)).is_empty());
+        assert!(cache.insert(key(2), entry(2, 60)).is_empty());
+
+        // Tocar la 1 deja la 2 como la menos usada
+        assert!(matches!(cache.lookup(key(1), U256::from(110)), Lookup::Hit(_)));
+        let evicted = cache.insert(key(3), entry(3, 60));
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].model_id, U256::from(2));
+
+        assert!(matches!(cache.lookup(key(2), U256::from(110)), Lookup::Miss));
+        match cache.lookup(key(1), U256::from(120)) {
+            Lookup::Hit(hit) => assert_eq!(hit.hits, U256::from(2)),
+            other => panic!("expected hit, got {:?}", other),
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, U256::from(2));
+        assert_eq!(stats.hits, U256::from(2));
+        assert_eq!(stats.misses, U256::from(1));
+        assert_eq!(stats.evictions, U256::from(1));
+    }
+
+    #[test]
+    fn test_expired_entries_are_dropped() {
+        let mut cache = LruCache::new(U256::from(4));
+        cache.insert(key(1), entry(1, 10));
+
+        assert!(matches!(cache.lookup(key(1), U256::from(109)), Lookup::Hit(_)));
+        assert!(matches!(cache.lookup(key(1), U256::from(110)), Lookup::Expired(_)));
+        assert!(cache.peek(key(1)).is_none());
+        assert_eq!(cache.stats().entries, U256::ZERO);
+
+        let forever = CacheEntry { ttl: U256::MAX, ..entry(2, 0) };
+        assert!(!forever.is_expired(U256::from(1_000_000)));
+    }
+
+    #[test]
+    fn test_keys_are_namespaced() {
+        let input = FixedBytes::repeat_byte(0x01);
+        let base = cache_key(&scope(1), input);
+
+        assert_ne!(base, cache_key(&scope(2), input));
+        let variants = [
+            CacheScope { version: U256::from(2), ..scope(1) },
+            CacheScope { preprocessing_hash: FixedBytes::repeat_byte(0x02), ..scope(1) },
+            CacheScope { generation: U256::from(1), ..scope(1) },
+        ];
+        for variant in &variants {
+            assert_ne!(base, cache_key(variant, input));
+        }
+        assert_eq!(base, cache_key(&scope(1), input));
+    }
+
+    #[test]
+    fn test_shrinking_capacity_evicts() {
+        let mut cache = LruCache::new(U256::from(3));
+        for n in 1..=3 {
+            cache.insert(key(n), entry(n, 60));
+        }
+        let evicted = cache.set_capacity(U256::from(1));
+        let models: Vec<U256> = evicted.iter().map(|e| e.model_id).collect();
+        assert_eq!(models, vec![U256::from(1), U256::from(2)]);
+        assert!(cache.peek(key(3)).is_some());
+        assert!(cache.remove(key(3)).is_some());
+        assert_eq!(cache.stats().entries, U256::ZERO);
+    }
+}
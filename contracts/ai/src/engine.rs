@@ -0,0 +1,520 @@
+// Motor de inferencia determinista en punto fijo Q16.16.
+// Solo usa aritmética entera para que todos los nodos obtengan
+// exactamente los mismos bytes de salida.
+
+use crate::linear::LinearModel;
+use crate::quant::QuantizedNetwork;
+use crate::tree::TreeEnsemble;
+
+pub const FRAC_BITS: u32 = 16;
+pub const ONE: i32 = 1 << FRAC_BITS;
+pub const BPS: u32 = 10_000;
+
+// sigmoid(i / 16) en Q16.16 para i = 0..=128, es decir x en [0, 8].
+const SIGMOID_LUT: [i32; 129] = [
+    32768, 33792, 34813, 35831, 36843, 37847, 38841, 39824,
+    40793, 41748, 42687, 43608, 44511, 45393, 46254, 47094,
+    47911, 48704, 49474, 50220, 50941, 51638, 52310, 52957,
+    53581, 54179, 54754, 55306, 55834, 56339, 56822, 57284,
+    57724, 58144, 58544, 58925, 59287, 59632, 59959, 60270,
+    60565, 60844, 61109, 61360, 61598, 61823, 62036, 62238,
+    62428, 62608, 62778, 62938, 63090, 63233, 63368, 63495,
+    63615, 63728, 63835, 63935, 64030, 64119, 64203, 64283,
+    64357, 64427, 64494, 64556, 64614, 64669, 64721, 64770,
+    64816, 64859, 64900, 64938, 64974, 65008, 65039, 65069,
+    65097, 65124, 65149, 65172, 65194, 65215, 65234, 65252,
+    65269, 65285, 65300, 65315, 65328, 65341, 65352, 65364,
+    65374, 65384, 65393, 65402, 65410, 65417, 65425, 65431,
+    65438, 65444, 65449, 65454, 65459, 65464, 65468, 65472,
+    65476, 65480, 65483, 65486, 65489, 65492, 65495, 65497,
+    65500, 65502, 65504, 65506, 65508, 65509, 65511, 65513,
+    65514,
+];
+const LUT_STEP_BITS: u32 = FRAC_BITS - 4;
+
+const NETWORK_MAGIC: &[u8; 4] = b"BSNN";
+const NETWORK_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    Identity,
+    Relu,
+    Sigmoid,
+}
+
+impl Activation {
+    pub(crate) fn from_u8(tag: u8) -> Result<Self, Vec<u8>> {
+        match tag {
+            0 => Ok(Activation::Identity),
+            1 => Ok(Activation::Relu),
+            2 => Ok(Activation::Sigmoid),
+            _ => Err("Unknown activation".into()),
+        }
+    }
+
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            Activation::Identity => 0,
+            Activation::Relu => 1,
+            Activation::Sigmoid => 2,
+        }
+    }
+
+    pub(crate) fn apply(&self, x: i32) -> i32 {
+        match self {
+            Activation::Identity => x,
+            Activation::Relu => x.max(0),
+            Activation::Sigmoid => sigmoid(x),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DenseLayer {
+    pub input_dim: usize,
+    pub output_dim: usize,
+    pub activation: Activation,
+    // Pesos fila por neurona de salida: weights[o * input_dim + i]
+    pub weights: Vec<i32>,
+    pub biases: Vec<i32>,
+}
+
+impl DenseLayer {
+    fn forward(&self, input: &[i32]) -> Vec<i32> {
+        let mut output = Vec::with_capacity(self.output_dim);
+        for o in 0..self.output_dim {
+            let row = &self.weights[o * self.input_dim..(o + 1) * self.input_dim];
+            let mut acc: i64 = 0;
+            for (w, x) in row.iter().zip(input.iter()) {
+                acc = acc.saturating_add(*w as i64 * *x as i64);
+            }
+            let value = (acc >> FRAC_BITS).saturating_add(self.biases[o] as i64);
+            output.push(self.activation.apply(saturate(value)));
+        }
+        output
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub layers: Vec<DenseLayer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prediction {
+    pub scores: Vec<i32>,
+    pub class: usize,
+    pub confidence_bps: u32,
+}
+
+impl Network {
+    // Formato: "BSNN" | version u8 | num_layers u8 |
+    // por capa: input_dim u16 | output_dim u16 | activation u8 |
+    // pesos i32[output_dim * input_dim] | biases i32[output_dim]
+    // Todos los enteros en big-endian.
+    pub fn decode(data: &[u8]) -> Result<Self, Vec<u8>> {
+        let mut reader = Reader::new(data);
+        if reader.take(4)? != NETWORK_MAGIC {
+            return Err("Invalid network magic".into());
+        }
+        if reader.u8()? != NETWORK_VERSION {
+            return Err("Unsupported network version".into());
+        }
+
+        let num_layers = reader.u8()? as usize;
+        if num_layers == 0 {
+            return Err("Network has no layers".into());
+        }
+
+        let mut layers: Vec<DenseLayer> = Vec::with_capacity(num_layers);
+        for _ in 0..num_layers {
+            let input_dim = reader.u16()? as usize;
+            let output_dim = reader.u16()? as usize;
+            let activation = Activation::from_u8(reader.u8()?)?;
+
+            if input_dim == 0 || output_dim == 0 {
+                return Err("Empty layer".into());
+            }
+            if let Some(prev) = layers.last() {
+                if prev.output_dim != input_dim {
+                    return Err("Layer shape mismatch".into());
+                }
+            }
+
+            let weights = reader.i32_vec(input_dim * output_dim)?;
+            let biases = reader.i32_vec(output_dim)?;
+            layers.push(DenseLayer {
+                input_dim,
+                output_dim,
+                activation,
+                weights,
+                biases,
+            });
+        }
+
+        if !reader.is_empty() {
+            return Err("Trailing network data".into());
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(NETWORK_MAGIC);
+        out.push(NETWORK_VERSION);
+        out.push(self.layers.len() as u8);
+        for layer in &self.layers {
+            out.extend_from_slice(&(layer.input_dim as u16).to_be_bytes());
+            out.extend_from_slice(&(layer.output_dim as u16).to_be_bytes());
+            out.push(layer.activation.to_u8());
+            for w in layer.weights.iter().chain(layer.biases.iter()) {
+                out.extend_from_slice(&w.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn input_dim(&self) -> usize {
+        self.layers[0].input_dim
+    }
+
+    pub fn output_dim(&self) -> usize {
+        self.layers[self.layers.len() - 1].output_dim
+    }
+
+    pub fn evaluate(&self, features: &[i32]) -> Result<Vec<i32>, Vec<u8>> {
+        if features.len() != self.input_dim() {
+            return Err("Feature count mismatch".into());
+        }
+        let mut activations = features.to_vec();
+        for layer in &self.layers {
+            activations = layer.forward(&activations);
+        }
+        Ok(activations)
+    }
+
+    pub fn predict(&self, input: &[u8]) -> Result<Prediction, Vec<u8>> {
+        let features = bytes_to_features(input, self.input_dim())?;
+        Ok(Prediction::from_scores(self.evaluate(&features)?))
+    }
+}
+
+impl Prediction {
+    pub fn from_scores(scores: Vec<i32>) -> Self {
+        let class = argmax(&scores);
+        let confidence_bps = confidence_bps(&scores, class);
+        Self {
+            scores,
+            class,
+            confidence_bps,
+        }
+    }
+
+    pub fn confidence_percent(&self) -> u32 {
+        self.confidence_bps / 100
+    }
+}
+
+// Modelo ya decodificado, independiente de su tipo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Model {
+    Network(Network),
+    Trees(TreeEnsemble),
+    Linear(LinearModel),
+    Quantized(QuantizedNetwork),
+}
+
+impl Model {
+    pub fn input_dim(&self) -> usize {
+        match self {
+            Model::Network(network) => network.input_dim(),
+            Model::Trees(ensemble) => ensemble.num_features,
+            Model::Linear(linear) => linear.num_features,
+            Model::Quantized(network) => network.input_dim(),
+        }
+    }
+
+    pub fn output_dim(&self) -> usize {
+        match self {
+            Model::Network(network) => network.output_dim(),
+            Model::Trees(ensemble) => ensemble.num_outputs,
+            Model::Linear(linear) => linear.num_outputs,
+            Model::Quantized(network) => network.output_dim(),
+        }
+    }
+
+    pub fn check_shape(&self, input_size: usize, output_size: usize) -> Result<(), Vec<u8>> {
+        if self.input_dim() != input_size {
+            return Err("Model input size mismatch".into());
+        }
+        if self.output_dim() != output_size {
+            return Err("Model output size mismatch".into());
+        }
+        Ok(())
+    }
+
+    pub fn predict(&self, input: &[u8]) -> Result<Prediction, Vec<u8>> {
+        let features = bytes_to_features(input, self.input_dim())?;
+        self.predict_features(&features)
+    }
+
+    pub fn predict_features(&self, features: &[i32]) -> Result<Prediction, Vec<u8>> {
+        let features = pad_features(features, self.input_dim())?;
+        match self {
+            Model::Network(network) => Ok(Prediction::from_scores(network.evaluate(&features)?)),
+            Model::Trees(ensemble) => Ok(Prediction::from_scores(ensemble.evaluate(&features)?)),
+            Model::Linear(linear) => linear.predict_features(&features),
+            Model::Quantized(network) => Ok(Prediction::from_scores(network.evaluate(&features)?)),
+        }
+    }
+}
+
+// Rellena con ceros hasta `input_dim`.
+pub fn pad_features(features: &[i32], input_dim: usize) -> Result<Vec<i32>, Vec<u8>> {
+    if features.len() > input_dim {
+        return Err("Too many input features".into());
+    }
+    let mut padded = features.to_vec();
+    padded.resize(input_dim, 0);
+    Ok(padded)
+}
+
+// Cada byte de entrada se interpreta como b/256 en Q16.16; el resto
+// hasta `input_dim` se rellena con ceros.
+pub fn bytes_to_features(input: &[u8], input_dim: usize) -> Result<Vec<i32>, Vec<u8>> {
+    if input.len() > input_dim {
+        return Err("Input too large".into());
+    }
+    let mut features = vec![0i32; input_dim];
+    for (slot, byte) in features.iter_mut().zip(input.iter()) {
+        *slot = (*byte as i32) << (FRAC_BITS - 8);
+    }
+    Ok(features)
+}
+
+pub fn encode_scores(scores: &[i32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(scores.len() * 4);
+    for score in scores {
+        out.extend_from_slice(&score.to_be_bytes());
+    }
+    out
+}
+
+// Ante empate gana el índice menor, así el resultado es estable.
+pub fn argmax(scores: &[i32]) -> usize {
+    let mut best = 0;
+    for (i, score) in scores.iter().enumerate() {
+        if *score > scores[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+// Con una sola salida la confianza es sigmoid(score); con varias, el
+// margen entre la mejor y la segunda clase pasa por la sigmoide.
+pub fn confidence_bps(scores: &[i32], class: usize) -> u32 {
+    let top = scores[class];
+    let margin = if scores.len() == 1 {
+        top
+    } else {
+        let runner_up = scores
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != class)
+            .map(|(_, s)| *s)
+            .max()
+            .unwrap_or(top);
+        top.saturating_sub(runner_up)
+    };
+    to_bps(sigmoid(margin))
+}
+
+// Probabilidad Q16.16 en [0, 1] a puntos básicos.
+pub fn to_bps(probability: i32) -> u32 {
+    ((probability.clamp(0, ONE) as i64 * BPS as i64) >> FRAC_BITS) as u32
+}
+
+// Sigmoide por tabla con interpolación lineal; simétrica alrededor de 0
+// y saturada fuera de [-8, 8].
+pub fn sigmoid(x: i32) -> i32 {
+    let abs = x.saturating_abs();
+    let index = (abs >> LUT_STEP_BITS) as usize;
+    let y = if index >= SIGMOID_LUT.len() - 1 {
+        SIGMOID_LUT[SIGMOID_LUT.len() - 1]
+    } else {
+        let frac = (abs & ((1 << LUT_STEP_BITS) - 1)) as i64;
+        let low = SIGMOID_LUT[index];
+        let high = SIGMOID_LUT[index + 1];
+        low + (((high - low) as i64 * frac) >> LUT_STEP_BITS) as i32
+    };
+    if x < 0 {
+        ONE - y
+    } else {
+        y
+    }
+}
+
+pub fn to_fixed(value: i32) -> i32 {
+    value.saturating_mul(ONE)
+}
+
+fn saturate(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], Vec<u8>> {
+        let end = self.pos.checked_add(len).ok_or("Truncated model data")?;
+        if end > self.data.len() {
+            return Err("Truncated model data".into());
+        }
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, Vec<u8>> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, Vec<u8>> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub(crate) fn i32(&mut self) -> Result<i32, Vec<u8>> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub(crate) fn i64(&mut self) -> Result<i64, Vec<u8>> {
+        let bytes = self.take(8)?;
+        let mut word = [0u8; 8];
+        word.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(word))
+    }
+
+    pub(crate) fn i32_vec(&mut self, count: usize) -> Result<Vec<i32>, Vec<u8>> {
+        let bytes = self.take(count.checked_mul(4).ok_or("Truncated model data")?)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|c| i32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+            .collect())
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xor_like_network() -> Network {
+        Network {
+            layers: vec![
+                DenseLayer {
+                    input_dim: 2,
+                    output_dim: 2,
+                    activation: Activation::Relu,
+                    weights: vec![ONE, ONE, ONE, -ONE],
+                    biases: vec![0, 0],
+                },
+                DenseLayer {
+                    input_dim: 2,
+                    output_dim: 2,
+                    activation: Activation::Identity,
+                    weights: vec![ONE, 0, -ONE, 2 * ONE],
+                    biases: vec![0, ONE / 4],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let network = xor_like_network();
+        let decoded = Network::decode(&network.encode()).unwrap();
+        assert_eq!(decoded, network);
+    }
+
+    #[test]
+    fn test_decode_rejects_bad_data() {
+        let encoded = xor_like_network().encode();
+        assert!(Network::decode(&encoded[..encoded.len() - 1]).is_err());
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert!(Network::decode(&trailing).is_err());
+
+        let mut bad_magic = encoded;
+        bad_magic[0] = b'X';
+        assert!(Network::decode(&bad_magic).is_err());
+    }
+
+    #[test]
+    fn test_dense_forward() {
+        let network = xor_like_network();
+        // x = [1.0, 0.5] -> h = relu([1.5, 0.5]) -> y = [1.5, -0.5]
+        let scores = network.evaluate(&[ONE, ONE / 2]).unwrap();
+        assert_eq!(scores, vec![3 * ONE / 2, -ONE / 2 + ONE / 4]);
+        assert_eq!(argmax(&scores), 0);
+    }
+
+    #[test]
+    fn test_prediction_is_deterministic() {
+        let network = xor_like_network();
+        let first = network.predict(&[200, 17]).unwrap();
+        let second = network.predict(&[200, 17]).unwrap();
+        assert_eq!(first, second);
+        assert!(first.confidence_bps >= BPS / 2 && first.confidence_bps <= BPS);
+    }
+
+    #[test]
+    fn test_input_larger_than_network() {
+        let network = xor_like_network();
+        assert!(network.predict(&[1, 2, 3]).is_err());
+        assert!(network.predict(&[1]).is_ok());
+    }
+
+    #[test]
+    fn test_sigmoid_approximation() {
+        assert_eq!(sigmoid(0), ONE / 2);
+        assert_eq!(sigmoid(ONE), 47911);
+        assert_eq!(sigmoid(10 * ONE), SIGMOID_LUT[128]);
+        assert_eq!(sigmoid(ONE) + sigmoid(-ONE), ONE);
+        assert_eq!(sigmoid(i32::MIN) + sigmoid(i32::MAX), ONE);
+
+        // Monótona en todo el rango
+        let mut previous = sigmoid(-9 * ONE);
+        for x in (-9 * ONE..9 * ONE).step_by(997) {
+            let y = sigmoid(x);
+            assert!(y >= previous);
+            previous = y;
+        }
+
+        // Entre puntos de la tabla: sigmoid(0.53125) = 0.62977
+        let y = sigmoid(ONE / 2 + ONE / 32);
+        assert!((y - 41273).abs() < 66);
+    }
+
+    #[test]
+    fn test_confidence_bps() {
+        assert_eq!(confidence_bps(&[0], 0), BPS / 2);
+        assert_eq!(to_bps(ONE), BPS);
+        assert_eq!(to_bps(-ONE), 0);
+        let scores = [3 * ONE, ONE, -ONE];
+        assert_eq!(confidence_bps(&scores, 0), to_bps(sigmoid(2 * ONE)));
+    }
+}
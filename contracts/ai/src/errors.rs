@@ -0,0 +1,345 @@
+// Errores del procesador. Cada variante se codifica como un error
+// personalizado de Solidity (selector de 4 bytes + parámetros en ABI), de
+// modo que AIOracle y las herramientas off-chain pueden distinguir la causa
+// con `catch (bytes memory reason)` o decodificando el revert.
+
+use alloy_primitives::{keccak256, Address, FixedBytes, U256};
+
+use crate::schema::SchemaError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AIError {
+    NotOwner { caller: Address },
+    NotPendingOwner { caller: Address },
+    MissingRole { role: FixedBytes<32>, account: Address },
+    OwnerAdminRole { account: Address },
+    ModelNotFound { model_id: U256 },
+    ModelVersionNotFound { model_id: U256, version: U256 },
+    ModelInactive { model_id: U256, version: U256 },
+    VersionAlreadyActive { model_id: U256, version: U256 },
+    NoPreviousVersion { model_id: U256 },
+    WeightsAlreadyCommitted { model_id: U256, version: U256 },
+    WeightsNotSet { model_id: U256, version: U256 },
+    UploadNotStarted { model_id: U256, version: U256 },
+    UploadTooLarge { total_size: U256, max_size: U256 },
+    UnexpectedChunk { expected: U256, actual: U256 },
+    InvalidChunkSize { size: U256 },
+    UploadIncomplete { received: U256, expected: U256 },
+    UploadSizeMismatch { expected: U256, actual: U256 },
+    WeightsRootMismatch { committed: FixedBytes<32>, computed: FixedBytes<32> },
+    InvalidModelData { reason: String },
+    InvalidSchema { reason: String },
+    InvalidPipeline { reason: String },
+    FeatureCountExceeded { max: U256, actual: U256 },
+    InputTooLarge { max: U256, actual: U256 },
+    InputTooShort { expected: U256, actual: U256 },
+    TrailingInput { expected: U256, actual: U256 },
+    MalformedInput { field: U256, reason: String },
+    InvalidInput { reason: String },
+    BatchTooLarge { max: U256, actual: U256 },
+    InferenceFailed { reason: String },
+    StatsNotFound { model_id: U256 },
+    RequestExpired { request_id: U256, deadline: U256 },
+    RequestNotFound { request_id: U256 },
+    InvalidPageSize { limit: U256, max: U256 },
+    InvalidTimeRange { from_time: U256, to_time: U256 },
+    Paused,
+    NotPaused,
+    CircuitBreakerOpen { model_id: U256 },
+    BreakerNotConfigured { model_id: U256 },
+    InvalidBreakerConfig,
+    // account es Address::ZERO cuando el límite agotado es el del modelo
+    RequestQuotaExceeded { model_id: U256, account: Address, limit: U256 },
+    ComputeQuotaExceeded { model_id: U256, account: Address, limit: U256 },
+    InvalidQuotaConfig { reason: String },
+    IncorrectPayment { expected: U256, actual: U256 },
+    UnsupportedFeeToken { token: Address },
+    NothingToWithdraw { account: Address, token: Address },
+    FeeTransferFailed { token: Address, amount: U256 },
+    NotModelPublisher { model_id: U256, account: Address },
+    LocalIdTooLarge { local_id: U256 },
+    LicenseNotAccepted { model_id: U256, account: Address },
+    LicenseTermsMismatch { model_id: U256, expected: FixedBytes<32>, actual: FixedBytes<32> },
+    InvalidPlatformFee { fee_bps: U256, max: U256 },
+}
+
+enum Param<'a> {
+    Uint(U256),
+    Address(Address),
+    Bytes32(FixedBytes<32>),
+    Str(&'a str),
+}
+
+impl AIError {
+    // Los módulos de formato (engine, tree, quant, ...) devuelven mensajes;
+    // aquí se les asigna la categoría según dónde falló.
+    pub fn model_data(reason: Vec<u8>) -> Self {
+        AIError::InvalidModelData { reason: lossy(reason) }
+    }
+
+    pub fn pipeline(reason: Vec<u8>) -> Self {
+        AIError::InvalidPipeline { reason: lossy(reason) }
+    }
+
+    pub fn quota(reason: Vec<u8>) -> Self {
+        AIError::InvalidQuotaConfig { reason: lossy(reason) }
+    }
+
+    pub fn input(reason: Vec<u8>) -> Self {
+        AIError::InvalidInput { reason: lossy(reason) }
+    }
+
+    pub fn inference(reason: Vec<u8>) -> Self {
+        AIError::InferenceFailed { reason: lossy(reason) }
+    }
+
+    pub fn signature(&self) -> &'static str {
+        match self {
+            AIError::NotOwner { .. } => "NotOwner(address)",
+            AIError::NotPendingOwner { .. } => "NotPendingOwner(address)",
+            AIError::MissingRole { .. } => "MissingRole(bytes32,address)",
+            AIError::OwnerAdminRole { .. } => "OwnerAdminRole(address)",
+            AIError::ModelNotFound { .. } => "ModelNotFound(uint256)",
+            AIError::ModelVersionNotFound { .. } => "ModelVersionNotFound(uint256,uint256)",
+            AIError::ModelInactive { .. } => "ModelInactive(uint256,uint256)",
+            AIError::VersionAlreadyActive { .. } => "VersionAlreadyActive(uint256,uint256)",
+            AIError::NoPreviousVersion { .. } => "NoPreviousVersion(uint256)",
+            AIError::WeightsAlreadyCommitted { .. } => "WeightsAlreadyCommitted(uint256,uint256)",
+            AIError::WeightsNotSet { .. } => "WeightsNotSet(uint256,uint256)",
+            AIError::UploadNotStarted { .. } => "UploadNotStarted(uint256,uint256)",
+            AIError::UploadTooLarge { .. } => "UploadTooLarge(uint256,uint256)",
+            AIError::UnexpectedChunk { .. } => "UnexpectedChunk(uint256,uint256)",
+            AIError::InvalidChunkSize { .. } => "InvalidChunkSize(uint256)",
+            AIError::UploadIncomplete { .. } => "UploadIncomplete(uint256,uint256)",
+            AIError::UploadSizeMismatch { .. } => "UploadSizeMismatch(uint256,uint256)",
+            AIError::WeightsRootMismatch { .. } => "WeightsRootMismatch(bytes32,bytes32)",
+            AIError::InvalidModelData { .. } => "InvalidModelData(string)",
+            AIError::InvalidSchema { .. } => "InvalidSchema(string)",
+            AIError::InvalidPipeline { .. } => "InvalidPipeline(string)",
+            AIError::FeatureCountExceeded { .. } => "FeatureCountExceeded(uint256,uint256)",
+            AIError::InputTooLarge { .. } => "InputTooLarge(uint256,uint256)",
+            AIError::InputTooShort { .. } => "InputTooShort(uint256,uint256)",
+            AIError::TrailingInput { .. } => "TrailingInput(uint256,uint256)",
+            AIError::MalformedInput { .. } => "MalformedInput(uint256,string)",
+            AIError::InvalidInput { .. } => "InvalidInput(string)",
+            AIError::BatchTooLarge { .. } => "BatchTooLarge(uint256,uint256)",
+            AIError::InferenceFailed { .. } => "InferenceFailed(string)",
+            AIError::StatsNotFound { .. } => "StatsNotFound(uint256)",
+            AIError::RequestExpired { .. } => "RequestExpired(uint256,uint256)",
+            AIError::RequestNotFound { .. } => "RequestNotFound(uint256)",
+            AIError::InvalidPageSize { .. } => "InvalidPageSize(uint256,uint256)",
+            AIError::InvalidTimeRange { .. } => "InvalidTimeRange(uint256,uint256)",
+            AIError::Paused => "Paused()",
+            AIError::NotPaused => "NotPaused()",
+            AIError::CircuitBreakerOpen { .. } => "CircuitBreakerOpen(uint256)",
+            AIError::BreakerNotConfigured { .. } => "BreakerNotConfigured(uint256)",
+            AIError::InvalidBreakerConfig => "InvalidBreakerConfig()",
+            AIError::RequestQuotaExceeded { .. } => "RequestQuotaExceeded(uint256,address,uint256)",
+            AIError::ComputeQuotaExceeded { .. } => "ComputeQuotaExceeded(uint256,address,uint256)",
+            AIError::InvalidQuotaConfig { .. } => "InvalidQuotaConfig(string)",
+            AIError::IncorrectPayment { .. } => "IncorrectPayment(uint256,uint256)",
+            AIError::UnsupportedFeeToken { .. } => "UnsupportedFeeToken(address)",
+            AIError::NothingToWithdraw { .. } => "NothingToWithdraw(address,address)",
+            AIError::FeeTransferFailed { .. } => "FeeTransferFailed(address,uint256)",
+            AIError::NotModelPublisher { .. } => "NotModelPublisher(uint256,address)",
+            AIError::LocalIdTooLarge { .. } => "LocalIdTooLarge(uint256)",
+            AIError::LicenseNotAccepted { .. } => "LicenseNotAccepted(uint256,address)",
+            AIError::LicenseTermsMismatch { .. } => "LicenseTermsMismatch(uint256,bytes32,bytes32)",
+            AIError::InvalidPlatformFee { .. } => "InvalidPlatformFee(uint256,uint256)",
+        }
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    fn params(&self) -> Vec<Param<'_>> {
+        use Param::*;
+        match self {
+            AIError::NotOwner { caller: account }
+            | AIError::NotPendingOwner { caller: account }
+            | AIError::OwnerAdminRole { account }
+            | AIError::UnsupportedFeeToken { token: account } => vec![Address(*account)],
+            AIError::NothingToWithdraw { account, token } => vec![Address(*account), Address(*token)],
+            AIError::FeeTransferFailed { token, amount } => vec![Address(*token), Uint(*amount)],
+            AIError::MissingRole { role, account } => vec![Bytes32(*role), Address(*account)],
+            AIError::ModelNotFound { model_id }
+            | AIError::NoPreviousVersion { model_id }
+            | AIError::StatsNotFound { model_id }
+            | AIError::CircuitBreakerOpen { model_id }
+            | AIError::BreakerNotConfigured { model_id } => vec![Uint(*model_id)],
+            AIError::ModelVersionNotFound { model_id, version }
+            | AIError::ModelInactive { model_id, version }
+            | AIError::VersionAlreadyActive { model_id, version }
+            | AIError::WeightsAlreadyCommitted { model_id, version }
+            | AIError::WeightsNotSet { model_id, version }
+            | AIError::UploadNotStarted { model_id, version } => {
+                vec![Uint(*model_id), Uint(*version)]
+            }
+            AIError::UploadTooLarge { total_size: a, max_size: b }
+            | AIError::UnexpectedChunk { expected: a, actual: b }
+            | AIError::UploadIncomplete { received: a, expected: b }
+            | AIError::UploadSizeMismatch { expected: a, actual: b }
+            | AIError::FeatureCountExceeded { max: a, actual: b }
+            | AIError::InputTooLarge { max: a, actual: b }
+            | AIError::InputTooShort { expected: a, actual: b }
+            | AIError::TrailingInput { expected: a, actual: b }
+            | AIError::BatchTooLarge { max: a, actual: b }
+            | AIError::RequestExpired { request_id: a, deadline: b }
+            | AIError::InvalidPageSize { limit: a, max: b }
+            | AIError::IncorrectPayment { expected: a, actual: b }
+            | AIError::InvalidPlatformFee { fee_bps: a, max: b }
+            | AIError::InvalidTimeRange { from_time: a, to_time: b } => vec![Uint(*a), Uint(*b)],
+            AIError::InvalidChunkSize { size }
+            | AIError::LocalIdTooLarge { local_id: size } => vec![Uint(*size)],
+            AIError::NotModelPublisher { model_id, account }
+            | AIError::LicenseNotAccepted { model_id, account } => {
+                vec![Uint(*model_id), Address(*account)]
+            }
+            AIError::LicenseTermsMismatch { model_id, expected, actual } => {
+                vec![Uint(*model_id), Bytes32(*expected), Bytes32(*actual)]
+            }
+            AIError::RequestNotFound { request_id } => vec![Uint(*request_id)],
+            AIError::WeightsRootMismatch { committed, computed } => {
+                vec![Bytes32(*committed), Bytes32(*computed)]
+            }
+            AIError::InvalidModelData { reason }
+            | AIError::InvalidSchema { reason }
+            | AIError::InvalidPipeline { reason }
+            | AIError::InvalidInput { reason }
+            | AIError::InferenceFailed { reason }
+            | AIError::InvalidQuotaConfig { reason } => vec![Str(reason)],
+            AIError::MalformedInput { field, reason } => vec![Uint(*field), Str(reason)],
+            AIError::RequestQuotaExceeded { model_id, account, limit }
+            | AIError::ComputeQuotaExceeded { model_id, account, limit } => {
+                vec![Uint(*model_id), Address(*account), Uint(*limit)]
+            }
+            AIError::Paused | AIError::NotPaused | AIError::InvalidBreakerConfig => Vec::new(),
+        }
+    }
+
+    // selector || abi.encode(params...)
+    pub fn encode(&self) -> Vec<u8> {
+        let params = self.params();
+        let mut head = Vec::with_capacity(params.len() * 32);
+        let mut tail = Vec::new();
+        for param in &params {
+            match param {
+                Param::Uint(v) => head.extend_from_slice(&v.to_be_bytes::<32>()),
+                Param::Address(a) => head.extend_from_slice(a.into_word().as_slice()),
+                Param::Bytes32(b) => head.extend_from_slice(b.as_slice()),
+                Param::Str(s) => {
+                    let offset = params.len() * 32 + tail.len();
+                    head.extend_from_slice(&U256::from(offset).to_be_bytes::<32>());
+                    tail.extend_from_slice(&U256::from(s.len()).to_be_bytes::<32>());
+                    tail.extend_from_slice(s.as_bytes());
+                    tail.resize(tail.len().next_multiple_of(32), 0);
+                }
+            }
+        }
+
+        let mut out = Vec::with_capacity(4 + head.len() + tail.len());
+        out.extend_from_slice(&self.selector());
+        out.extend_from_slice(&head);
+        out.extend_from_slice(&tail);
+        out
+    }
+}
+
+fn lossy(reason: Vec<u8>) -> String {
+    String::from_utf8_lossy(&reason).into_owned()
+}
+
+impl From<AIError> for Vec<u8> {
+    fn from(error: AIError) -> Self {
+        error.encode()
+    }
+}
+
+impl From<SchemaError> for AIError {
+    fn from(error: SchemaError) -> Self {
+        let reason = String::from(error.message());
+        match error {
+            SchemaError::EmptySchema
+            | SchemaError::TooManyFields { .. }
+            | SchemaError::UnknownType { .. } => AIError::InvalidSchema { reason },
+            SchemaError::InputTooShort { expected, actual } => AIError::InputTooShort {
+                expected: U256::from(expected),
+                actual: U256::from(actual),
+            },
+            SchemaError::TrailingData { expected, actual } => AIError::TrailingInput {
+                expected: U256::from(expected),
+                actual: U256::from(actual),
+            },
+            SchemaError::TooManyFeatures { expected, actual } => AIError::FeatureCountExceeded {
+                max: U256::from(expected),
+                actual: U256::from(actual),
+            },
+            SchemaError::InvalidOffset { field }
+            | SchemaError::InvalidArrayLength { field }
+            | SchemaError::InvalidAddress { field }
+            | SchemaError::InvalidBool { field } => AIError::MalformedInput {
+                field: U256::from(field),
+                reason,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_params_encoding() {
+        let error = AIError::BatchTooLarge {
+            max: U256::from(32),
+            actual: U256::from(40),
+        };
+        let encoded = error.encode();
+        assert_eq!(encoded.len(), 4 + 64);
+        assert_eq!(&encoded[..4], &keccak256(b"BatchTooLarge(uint256,uint256)")[..4]);
+        assert_eq!(U256::from_be_slice(&encoded[4..36]), U256::from(32));
+        assert_eq!(U256::from_be_slice(&encoded[36..68]), U256::from(40));
+    }
+
+    #[test]
+    fn test_string_param_encoding() {
+        let error = AIError::MalformedInput {
+            field: U256::from(1),
+            reason: "Invalid ABI bool".into(),
+        };
+        let encoded = error.encode();
+        // field | offset | len | datos rellenados a 32 bytes
+        assert_eq!(encoded.len(), 4 + 32 * 4);
+        assert_eq!(U256::from_be_slice(&encoded[36..68]), U256::from(64));
+        assert_eq!(U256::from_be_slice(&encoded[68..100]), U256::from(16));
+        assert_eq!(&encoded[100..116], b"Invalid ABI bool");
+        assert!(encoded[116..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_address_is_left_padded() {
+        let caller = Address::repeat_byte(0xab);
+        let encoded = AIError::NotOwner { caller }.encode();
+        assert!(encoded[4..16].iter().all(|b| *b == 0));
+        assert_eq!(&encoded[16..36], caller.as_slice());
+    }
+
+    #[test]
+    fn test_schema_errors_keep_parameters() {
+        let error = AIError::from(SchemaError::InputTooShort { expected: 64, actual: 32 });
+        assert_eq!(
+            error,
+            AIError::InputTooShort {
+                expected: U256::from(64),
+                actual: U256::from(32),
+            }
+        );
+        assert!(matches!(
+            AIError::from(SchemaError::UnknownType { tag: 9 }),
+            AIError::InvalidSchema { .. }
+        ));
+    }
+}
@@ -0,0 +1,318 @@
+// Estimación del gas de una inferencia a partir de la forma del modelo:
+// multiplicaciones de cada capa densa, profundidad de los árboles, ancho de
+// los pesos cuantizados y pasos de preprocesado. Las constantes solo fijan
+// el peso relativo de cada operación; la escala absoluta la corrige una
+// calibración por modelo con el gas medido en ejecuciones on-chain.
+// AIProcessorStylus la incluye con #[path = "src/gas.rs"].
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+// Mismo orden que ModelKind en lib.rs
+pub const KIND_NETWORK: u8 = 0;
+pub const KIND_TREES: u8 = 1;
+pub const KIND_LINEAR: u8 = 2;
+pub const KIND_QUANTIZED: u8 = 3;
+
+const BASE_GAS: u128 = 50_000;
+const GAS_PER_INPUT_BYTE: u128 = 20;
+// Lectura de los pesos: un slot de almacenamiento por cada 32 bytes
+const GAS_PER_WEIGHT_WORD: u128 = 2_100;
+const GAS_PER_MAC: u128 = 2;
+// int8 cabe en i32 sin saturar, pero la entrada se cuantiza antes
+const GAS_PER_QUANTIZED_MAC: u128 = 1;
+const GAS_PER_ACTIVATION: u128 = 20;
+const GAS_PER_TREE_LEVEL: u128 = 40;
+const GAS_PER_PIPELINE_STEP: u128 = 800;
+
+const PIPELINE_MAGIC: &[u8; 4] = b"BSPP";
+
+// La calibración se mueve 1/8 hacia cada medida, igual que la EMA de stats,
+// también en la primera (desde 1.0), y se acota para que una medida rara no
+// dispare las decisiones
+const CALIBRATION_WEIGHT: u32 = 8;
+const MIN_RATIO_BPS: u32 = 1_000;
+const MAX_RATIO_BPS: u32 = 100_000;
+const BPS: u32 = 10_000;
+// Segundos entre dos relajaciones; sin esto bastaría mandar peticiones
+// off-chain seguidas para deshacer cualquier calibración
+const RELAX_INTERVAL: u64 = 600;
+
+#[derive(Debug, Clone, Storage)]
+pub struct ModelShape {
+    pub kind: u8,
+    // [entrada, ocultas..., salida]; en árboles y lineales [features, salidas]
+    pub dims: Vec<u32>,
+    pub num_trees: u32,
+    pub tree_depth: u32,
+    // 4 en Q16.16; 1 o 2 en redes cuantizadas
+    pub weight_bytes: u32,
+    // Pasos del preprocesado del modelo, ver pipeline_steps
+    pub pipeline_steps: u32,
+}
+
+impl ModelShape {
+    // Sin forma registrada se supone una red densa de una capa
+    pub fn dense(input_dim: u32, output_dim: u32) -> Self {
+        Self {
+            kind: KIND_NETWORK,
+            dims: vec![input_dim, output_dim],
+            num_trees: 0,
+            tree_depth: 0,
+            weight_bytes: 4,
+            pipeline_steps: 0,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), Vec<u8>> {
+        if self.dims.len() < 2 || self.dims.contains(&0) {
+            return Err(b"Invalid layer dimensions".to_vec());
+        }
+        let weight_bytes_ok = match self.kind {
+            KIND_NETWORK | KIND_LINEAR => self.weight_bytes == 4,
+            KIND_QUANTIZED => self.weight_bytes == 1 || self.weight_bytes == 2,
+            KIND_TREES => true,
+            _ => return Err(b"Unknown model kind".to_vec()),
+        };
+        if !weight_bytes_ok {
+            return Err(b"Invalid weight width".to_vec());
+        }
+        if self.kind == KIND_TREES && (self.num_trees == 0 || self.dims.len() != 2) {
+            return Err(b"Invalid tree ensemble".to_vec());
+        }
+        Ok(())
+    }
+
+    pub fn input_dim(&self) -> u32 {
+        self.dims.first().copied().unwrap_or(0)
+    }
+
+    pub fn output_dim(&self) -> u32 {
+        self.dims.last().copied().unwrap_or(0)
+    }
+
+    // Suma de entrada x salida de cada capa
+    fn macs(&self) -> u128 {
+        self.dims
+            .windows(2)
+            .map(|pair| pair[0] as u128 * pair[1] as u128)
+            .sum()
+    }
+
+    fn activations(&self) -> u128 {
+        self.dims.iter().skip(1).map(|dim| *dim as u128).sum()
+    }
+
+    fn model_gas(&self) -> u128 {
+        let pipeline_gas = self.pipeline_steps as u128 * GAS_PER_PIPELINE_STEP;
+        pipeline_gas + match self.kind {
+            KIND_TREES => {
+                // Un nodo por nivel y árbol, más sumar la hoja
+                let levels = self.num_trees as u128 * self.tree_depth as u128;
+                let leaves = self.num_trees as u128 * self.output_dim() as u128;
+                levels * GAS_PER_TREE_LEVEL + leaves * GAS_PER_MAC
+            }
+            _ => {
+                let weights = self.macs() + self.activations();
+                let words = (weights * self.weight_bytes as u128).div_ceil(32);
+                let mac_gas = if self.kind == KIND_QUANTIZED {
+                    GAS_PER_QUANTIZED_MAC
+                } else {
+                    GAS_PER_MAC
+                };
+                words * GAS_PER_WEIGHT_WORD
+                    + self.macs() * mac_gas
+                    + self.activations() * GAS_PER_ACTIVATION
+            }
+        }
+    }
+}
+
+// Gas estimado sin calibrar; satura en vez de desbordar
+pub fn estimate(shape: &ModelShape, input_len: usize) -> U256 {
+    let gas = BASE_GAS
+        .saturating_add(input_len as u128 * GAS_PER_INPUT_BYTE)
+        .saturating_add(shape.model_gas());
+    U256::from(gas)
+}
+
+// Pasos de un pipeline de preprocess.rs sin decodificarlo entero:
+// "BSPP" | version u8 | num_steps u8 | ...
+pub fn pipeline_steps(blob: &[u8]) -> u32 {
+    if blob.len() < 6 || &blob[..4] != PIPELINE_MAGIC {
+        return 0;
+    }
+    blob[5] as u32
+}
+
+// Relación entre el gas medido y el estimado, en puntos básicos
+#[derive(Debug, Clone, Default, Storage)]
+pub struct Calibration {
+    pub samples: U256,
+    pub ratio_bps: U256,
+    // Última medida o relajación
+    pub updated_at: U256,
+}
+
+impl Calibration {
+    pub fn record(&mut self, estimated: U256, measured: U256, now: U256) {
+        if estimated.is_zero() {
+            return;
+        }
+        let sample = (measured.saturating_mul(U256::from(BPS)) / estimated)
+            .clamp(U256::from(MIN_RATIO_BPS), U256::from(MAX_RATIO_BPS));
+
+        let weight = U256::from(CALIBRATION_WEIGHT);
+        self.ratio_bps = (self.current_ratio() * (weight - U256::from(1)) + sample) / weight;
+        self.samples += U256::from(1);
+        self.updated_at = now;
+    }
+
+    // Una relación alta manda el modelo off-chain, donde ya no se mide. Cada
+    // vez que eso pasa, como mucho una vez por RELAX_INTERVAL, se acerca 1/8
+    // a 1.0, así que tarde o temprano vuelve a correr on-chain y una medida
+    // real la corrige. Devuelve si ha cambiado
+    pub fn relax(&mut self, now: U256) -> bool {
+        let bps = U256::from(BPS);
+        if self.samples.is_zero() || self.ratio_bps <= bps {
+            return false;
+        }
+        if now < self.updated_at.saturating_add(U256::from(RELAX_INTERVAL)) {
+            return false;
+        }
+        let weight = U256::from(CALIBRATION_WEIGHT);
+        self.ratio_bps = (self.ratio_bps * (weight - U256::from(1)) + bps) / weight;
+        self.updated_at = now;
+        true
+    }
+
+    fn current_ratio(&self) -> U256 {
+        if self.samples.is_zero() {
+            U256::from(BPS)
+        } else {
+            self.ratio_bps
+        }
+    }
+
+    pub fn apply(&self, estimated: U256) -> U256 {
+        if self.samples.is_zero() {
+            return estimated;
+        }
+        estimated.saturating_mul(self.ratio_bps) / U256::from(BPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_follows_model_shape() {
+        let small = estimate(&ModelShape::dense(8, 2), 8);
+        let deep = ModelShape {
+            dims: vec![8, 64, 64, 2],
+            ..ModelShape::dense(8, 2)
+        };
+        assert!(estimate(&deep, 8) > small);
+
+        // Mismas capas con pesos int8: menos lecturas y multiplicaciones
+        let quantized = ModelShape {
+            kind: KIND_QUANTIZED,
+            weight_bytes: 1,
+            ..deep.clone()
+        };
+        assert!(quantized.validate().is_ok());
+        assert!(estimate(&quantized, 8) < estimate(&deep, 8));
+
+        let trees = |depth| ModelShape {
+            kind: KIND_TREES,
+            dims: vec![8, 2],
+            num_trees: 50,
+            tree_depth: depth,
+            weight_bytes: 0,
+            pipeline_steps: 0,
+        };
+        assert!(estimate(&trees(8), 8) > estimate(&trees(4), 8));
+
+        // El preprocesado y la entrada también cuentan
+        let preprocessed = |steps| ModelShape { pipeline_steps: steps, ..deep.clone() };
+        assert!(estimate(&preprocessed(3), 8) > estimate(&preprocessed(1), 8));
+        assert!(estimate(&preprocessed(1), 8) > estimate(&deep, 8));
+        assert!(estimate(&ModelShape { pipeline_steps: 3, ..trees(4) }, 8) > estimate(&trees(4), 8));
+        assert!(estimate(&deep, 64) > estimate(&deep, 8));
+    }
+
+    #[test]
+    fn test_validate_and_pipeline_steps() {
+        assert!(ModelShape { dims: vec![8], ..ModelShape::dense(8, 2) }.validate().is_err());
+        assert!(ModelShape::dense(0, 2).validate().is_err());
+        assert!(ModelShape { kind: KIND_QUANTIZED, ..ModelShape::dense(8, 2) }.validate().is_err());
+        assert!(ModelShape { kind: 9, ..ModelShape::dense(8, 2) }.validate().is_err());
+
+        assert_eq!(pipeline_steps(b"BSPP\x01\x03rest"), 3);
+        assert_eq!(pipeline_steps(b"XXXX\x01\x03"), 0);
+        assert_eq!(pipeline_steps(b""), 0);
+    }
+
+    #[test]
+    fn test_calibration_tracks_measured_gas() {
+        let mut calibration = Calibration::default();
+        let estimated = U256::from(100_000);
+        assert_eq!(calibration.apply(estimated), estimated);
+
+        // Cada medida mueve la relación 1/8, también la primera
+        calibration.record(estimated, U256::from(200_000), U256::ZERO);
+        assert_eq!(calibration.ratio_bps, U256::from((10_000 * 7 + 20_000) / 8));
+        assert_eq!(calibration.apply(estimated), U256::from(112_500));
+
+        calibration.record(estimated, U256::from(100_000), U256::ZERO);
+        assert_eq!(calibration.ratio_bps, U256::from((11_250 * 7 + 10_000) / 8));
+
+        // Una medida absurda queda acotada
+        calibration.record(estimated, U256::from(1_000_000_000), U256::ZERO);
+        assert_eq!(calibration.ratio_bps, U256::from((11_093 * 7 + 100_000) / 8));
+        assert_eq!(calibration.samples, U256::from(3));
+    }
+
+    #[test]
+    fn test_calibration_recovers() {
+        let estimated = U256::from(100_000);
+
+        let interval = U256::from(RELAX_INTERVAL);
+        let mut now = U256::from(1_000);
+
+        // Sin medidas no hay nada que relajar
+        let mut calibration = Calibration::default();
+        assert!(!calibration.relax(now + interval));
+        assert_eq!(calibration.apply(estimated), estimated);
+
+        // Varias medidas 10x y después el modelo deja de correr on-chain
+        for _ in 0..20 {
+            calibration.record(estimated, U256::from(1_000_000), now);
+        }
+        assert!(calibration.apply(estimated) > U256::from(800_000));
+
+        // Justo después de medir, o repitiendo en el mismo intervalo, no cambia
+        let ratio = calibration.ratio_bps;
+        assert!(!calibration.relax(now));
+        assert!(!calibration.relax(now + interval - U256::from(1)));
+        assert_eq!(calibration.ratio_bps, ratio);
+        now += interval;
+        assert!(calibration.relax(now));
+        assert!(!calibration.relax(now));
+        assert!(calibration.ratio_bps < ratio);
+
+        for _ in 0..40 {
+            now += interval;
+            calibration.relax(now);
+        }
+        assert!(calibration.apply(estimated) < U256::from(110_000));
+        assert!(calibration.apply(estimated) >= estimated);
+
+        // Una medida real la vuelve a mover en cualquier dirección
+        calibration.record(estimated, U256::from(50_000), now);
+        assert!(calibration.apply(estimated) < U256::from(110_000));
+        let ratio = calibration.ratio_bps;
+        assert!(!calibration.relax(now + interval));
+        assert!(calibration.ratio_bps <= ratio);
+    }
+}
@@ -1,138 +1,766 @@
 use stylus_sdk::{
     alloy_primitives::U256,
+    alloy_sol_types::sol,
+    call::{self, Call},
+    contract,
     prelude::*,
     stylus_proc::stylus_fn,
 };
 
-use alloy_primitives::{Address, Bytes};
+use alloy_primitives::{Address, Bytes, FixedBytes};
 use core::marker::PhantomData;
 use wee_alloc::WeeAlloc;
 
+pub mod engine;
+pub mod errors;
+pub mod linear;
+pub mod marketplace;
+pub mod preprocess;
+pub mod pricing;
+pub mod quant;
+pub mod quotas;
+pub mod requests;
+pub mod results;
+pub mod schema;
+pub mod stats;
+pub mod tree;
+pub mod weights;
+
+use engine::{Model, Network, Prediction};
+use errors::AIError;
+use linear::LinearModel;
+use marketplace::License;
+use preprocess::Pipeline;
+use pricing::{PricingConfig, IERC20, NATIVE_TOKEN};
+use quant::QuantizedNetwork;
+use quotas::{QuotaConfig, QuotaLimit, Usage};
+use requests::{RequestIdAllocator, RequestOrigin};
+use results::ResultIndex;
+use stats::{ProcessingStats, SOURCE_ON_CHAIN};
+use tree::TreeEnsemble;
+
 #[global_allocator]
 static ALLOC: WeeAlloc = WeeAlloc::INIT;
 
+// Mismos identificadores que los contratos Solidity (keccak256("ADMIN_ROLE"), ...)
+pub const ADMIN_ROLE: FixedBytes<32> = FixedBytes(keccak256!("ADMIN_ROLE"));
+pub const MODEL_MANAGER_ROLE: FixedBytes<32> = FixedBytes(keccak256!("MODEL_MANAGER_ROLE"));
+pub const OPERATOR_ROLE: FixedBytes<32> = FixedBytes(keccak256!("OPERATOR_ROLE"));
+pub const PAUSER_ROLE: FixedBytes<32> = FixedBytes(keccak256!("PAUSER_ROLE"));
+pub const PUBLISHER_ROLE: FixedBytes<32> = FixedBytes(keccak256!("PUBLISHER_ROLE"));
+
+const ALL_ROLES: [FixedBytes<32>; 5] =
+    [ADMIN_ROLE, MODEL_MANAGER_ROLE, OPERATOR_ROLE, PAUSER_ROLE, PUBLISHER_ROLE];
+
 #[derive(Debug)]
 pub struct AIProcessor {
     owner: Address,
-    model_configs: StorageMap<U256, ModelConfig>,
+    // Transferencia en dos pasos: el nuevo owner debe aceptarla
+    pending_owner: Address,
+    // Indexado por role_key(role, account)
+    roles: StorageMap<FixedBytes<32>, bool>,
+    // Configuración, pesos y subidas indexados por (model_id, version)
+    model_configs: StorageMap<FixedBytes<32>, ModelConfig>,
+    model_weights: StorageMap<FixedBytes<32>, Bytes>,
+    weight_uploads: StorageMap<FixedBytes<32>, WeightUpload>,
+    latest_versions: StorageMap<U256, U256>,
+    active_versions: StorageMap<U256, U256>,
+    version_history: StorageMap<U256, Vec<U256>>,
     inference_results: StorageMap<U256, InferenceResult>,
+    result_index: ResultIndex,
+    request_ids: RequestIdAllocator,
     stats: StorageMap<U256, ProcessingStats>,
+    // Parada global y circuit breaker por modelo
+    paused: bool,
+    circuit_breakers: StorageMap<U256, CircuitBreaker>,
+    // Cuotas por modelo; el consumo se indexa por model_account_key(model_id, account)
+    quotas: StorageMap<U256, QuotaConfig>,
+    usage: StorageMap<FixedBytes<32>, Usage>,
+    // Contratos de confianza (p. ej. AIOracle) que no consumen cuota
+    quota_exempt: StorageMap<Address, bool>,
+    // Cobro por inferencia; lo acumulado se indexa por fee_key(account, token)
+    fee_token: Address,
+    model_owners: StorageMap<U256, Address>,
+    pricing: StorageMap<U256, PricingConfig>,
+    accrued_fees: StorageMap<FixedBytes<32>, U256>,
+    // Marketplace: parte de cada tarifa para la plataforma y licencias de
+    // los editores; las aceptaciones se indexan por model_account_key
+    platform_fee_bps: U256,
+    platform_treasury: Address,
+    licenses: StorageMap<U256, License>,
+    license_acceptances: StorageMap<FixedBytes<32>, FixedBytes<32>>,
     _phantom: PhantomData<()>,
 }
 
 #[derive(Debug, Clone, Storage)]
 pub struct ModelConfig {
     model_id: U256,
+    version: U256,
+    model_kind: ModelKind,
     input_size: U256,
     output_size: U256,
     batch_size: U256,
     compute_units: U256,
+    weights_root: FixedBytes<32>,
+    // Tipos ABI de entrada y campos de salida; vacío = bytes sin esquema
+    input_schema: Bytes,
+    output_schema: Bytes,
+    // Pipeline de preprocesado (formato BSPP); vacío = features sin transformar
+    preprocessing: Bytes,
     is_active: bool,
 }
 
+#[derive(Debug, Clone, Storage)]
+pub enum ModelKind {
+    NeuralNetwork,
+    TreeEnsemble,
+    LinearModel,
+    QuantizedNetwork,
+}
+
+#[derive(Debug, Clone, Storage)]
+pub struct WeightUpload {
+    model_id: U256,
+    version: U256,
+    committed_root: FixedBytes<32>,
+    total_size: U256,
+    chunk_count: U256,
+    received_chunks: U256,
+    data: Bytes,
+    leaves: Vec<FixedBytes<32>>,
+}
+
 #[derive(Debug, Clone, Storage)]
 pub struct InferenceResult {
     request_id: U256,
+    model_id: U256,
+    model_version: U256,
     input_hash: Bytes,
     output: Bytes,
+    predicted_class: U256,
+    // Porcentaje (0-100) y probabilidad calibrada en puntos básicos
     confidence: U256,
+    confidence_bps: U256,
     timestamp: U256,
     gas_used: U256,
+    // false si el modelo falló; la salida queda vacía
+    success: bool,
+}
+
+#[derive(Debug, Clone, Storage)]
+pub struct ResultPage {
+    results: Vec<InferenceResult>,
+    // Se pasa tal cual en la siguiente llamada
+    next_cursor: U256,
+    has_more: bool,
+}
+
+// Lo que queda al llamador teniendo en cuenta también el límite del modelo
+#[derive(Debug, Clone, Storage)]
+pub struct RemainingQuota {
+    // U256::MAX si no hay límite
+    requests: U256,
+    compute_units: U256,
+    // Bloque en que se reinicia cada contador (0 si no hay ventana)
+    window_resets_at: U256,
+    epoch_resets_at: U256,
 }
 
 #[derive(Debug, Clone, Storage)]
-pub struct ProcessingStats {
-    total_requests: U256,
-    total_gas_used: U256,
-    avg_processing_time: U256,
-    success_rate: U256,
+pub struct CircuitBreaker {
+    max_error_rate_bps: U256,
+    min_avg_confidence_bps: U256,
+    max_avg_confidence_bps: U256,
+    // Muestras necesarias antes de evaluar y tamaño de cada tramo de la ventana
+    min_samples: U256,
+    // La ventana son el tramo en curso y el anterior completo: se evalúan
+    // entre min_samples y 2 * min_samples - 1 peticiones recientes, así que
+    // un historial largo sano no diluye una racha de fallos
+    window_requests: U256,
+    window_failures: U256,
+    window_confidence_bps: U256,
+    previous_requests: U256,
+    previous_failures: U256,
+    previous_confidence_bps: U256,
+    tripped: bool,
 }
 
 #[stylus_fn]
 impl AIProcessor {
     pub fn new() -> Self {
-        Self {
-            owner: msg::sender(),
+        let owner = msg::sender();
+        let mut processor = Self {
+            owner,
+            pending_owner: Address::ZERO,
+            roles: StorageMap::new(),
             model_configs: StorageMap::new(),
+            model_weights: StorageMap::new(),
+            weight_uploads: StorageMap::new(),
+            latest_versions: StorageMap::new(),
+            active_versions: StorageMap::new(),
+            version_history: StorageMap::new(),
             inference_results: StorageMap::new(),
+            result_index: ResultIndex::new(),
+            request_ids: RequestIdAllocator::new(requests::DOMAIN_AI_PROCESSOR),
             stats: StorageMap::new(),
+            paused: false,
+            circuit_breakers: StorageMap::new(),
+            quotas: StorageMap::new(),
+            usage: StorageMap::new(),
+            quota_exempt: StorageMap::new(),
+            fee_token: Address::ZERO,
+            model_owners: StorageMap::new(),
+            pricing: StorageMap::new(),
+            accrued_fees: StorageMap::new(),
+            platform_fee_bps: U256::ZERO,
+            platform_treasury: owner,
+            licenses: StorageMap::new(),
+            license_acceptances: StorageMap::new(),
             _phantom: PhantomData,
+        };
+
+        // El desplegador arranca con todos los roles
+        for role in [ADMIN_ROLE, MODEL_MANAGER_ROLE, OPERATOR_ROLE, PAUSER_ROLE] {
+            processor.set_role(role, owner, true);
+        }
+        processor
+    }
+
+    #[stylus_fn(name = "hasRole")]
+    pub fn has_role(&self, role: FixedBytes<32>, account: Address) -> bool {
+        self.roles.get(&role_key(role, account)).unwrap_or(false)
+    }
+
+    #[stylus_fn(name = "grantRole")]
+    pub fn grant_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        Ok(self.set_role(role, account, true))
+    }
+
+    #[stylus_fn(name = "revokeRole")]
+    pub fn revoke_role(&mut self, role: FixedBytes<32>, account: Address) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        self.ensure_not_owner_admin(role, account)?;
+        Ok(self.set_role(role, account, false))
+    }
+
+    #[stylus_fn(name = "renounceRole")]
+    pub fn renounce_role(&mut self, role: FixedBytes<32>) -> Result<bool, AIError> {
+        let caller = msg::sender();
+        self.ensure_not_owner_admin(role, caller)?;
+        Ok(self.set_role(role, caller, false))
+    }
+
+    #[stylus_fn(name = "owner")]
+    pub fn owner(&self) -> Address {
+        self.owner
+    }
+
+    #[stylus_fn(name = "pendingOwner")]
+    pub fn pending_owner(&self) -> Address {
+        self.pending_owner
+    }
+
+    // Address::ZERO cancela una transferencia pendiente
+    #[stylus_fn(name = "transferOwnership")]
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<bool, AIError> {
+        self.ensure_owner()?;
+        self.pending_owner = new_owner;
+
+        evm::log(OwnershipTransferStarted {
+            previous_owner: self.owner,
+            new_owner,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "acceptOwnership")]
+    pub fn accept_ownership(&mut self) -> Result<bool, AIError> {
+        let caller = msg::sender();
+        if caller != self.pending_owner || caller == Address::ZERO {
+            return Err(AIError::NotPendingOwner { caller });
+        }
+
+        // Todos los roles del owner anterior pasan al nuevo: si se rota una
+        // clave comprometida, la vieja no debe poder seguir gestionando
+        // modelos ni pausar. ADMIN_ROLE se concede siempre.
+        let previous = self.owner;
+        self.owner = caller;
+        self.pending_owner = Address::ZERO;
+        for role in ALL_ROLES {
+            if self.set_role(role, previous, false) || role == ADMIN_ROLE {
+                self.set_role(role, caller, true);
+            }
         }
+
+        evm::log(OwnershipTransferred {
+            previous_owner: previous,
+            new_owner: caller,
+        });
+
+        Ok(true)
     }
 
     #[stylus_fn(name = "registerModel")]
     pub fn register_model(
         &mut self,
         model_id: U256,
+        model_kind: ModelKind,
         input_size: U256,
         output_size: U256,
         batch_size: U256,
         compute_units: U256,
-    ) -> Result<bool, Vec<u8>> {
-        self.ensure_owner()?;
-        
+    ) -> Result<U256, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        // Cada registro crea una versión nueva; nunca se sobrescribe una existente
+        let version = self.latest_versions.get(&model_id).unwrap_or(U256::ZERO) + U256::from(1);
+
         let config = ModelConfig {
             model_id,
+            version,
+            model_kind,
             input_size,
             output_size,
             batch_size,
             compute_units,
-            is_active: true,
+            weights_root: FixedBytes::ZERO,
+            input_schema: Bytes::new(),
+            output_schema: Bytes::new(),
+            preprocessing: Bytes::new(),
+            // Se activa al confirmar la subida de pesos
+            is_active: false,
         };
         
-        self.model_configs.insert(model_id, config);
+        self.model_configs.insert(version_key(model_id, version), config);
+        self.latest_versions.insert(model_id, version);
+
+        evm::log(ModelVersionRegistered {
+            model_id,
+            version,
+        });
+        // La primera versión da de alta el modelo; las siguientes lo actualizan
+        if version == U256::from(1) {
+            // Quien registra el modelo recibe lo que se cobre por usarlo
+            self.model_owners.insert(model_id, msg::sender());
+            evm::log(ModelRegistered {
+                model_id,
+                input_size,
+                output_size,
+            });
+        } else {
+            evm::log(ModelUpdated {
+                model_id,
+                version,
+            });
+        }
+
+        Ok(version)
+    }
+
+    #[stylus_fn(name = "setModelSchema")]
+    pub fn set_model_schema(
+        &mut self,
+        model_id: U256,
+        version: U256,
+        input_schema: Bytes,
+        output_schema: Bytes,
+    ) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        let mut config = self.model_configs.get(&key)
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+
+        // El esquema forma parte de la versión y queda fijo al confirmar los pesos
+        if config.weights_root != FixedBytes::ZERO {
+            return Err(AIError::WeightsAlreadyCommitted { model_id, version });
+        }
+
+        if !input_schema.is_empty() {
+            let types = schema::parse_input_schema(&input_schema)?;
+            if let Some(count) = schema::static_feature_count(&types) {
+                if count > config.input_size.as_usize() {
+                    return Err(AIError::FeatureCountExceeded {
+                        max: config.input_size,
+                        actual: U256::from(count),
+                    });
+                }
+            }
+        }
+        if !output_schema.is_empty() {
+            schema::parse_output_schema(&output_schema)?;
+        }
+
+        config.input_schema = input_schema;
+        config.output_schema = output_schema;
+        self.model_configs.insert(key, config);
+
+        evm::log(ModelSchemaSet {
+            model_id,
+            version,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "setModelPreprocessing")]
+    pub fn set_model_preprocessing(
+        &mut self,
+        model_id: U256,
+        version: U256,
+        pipeline: Bytes,
+    ) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        let mut config = self.model_configs.get(&key)
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+
+        // Igual que el esquema: el preprocesado es parte de la versión
+        if config.weights_root != FixedBytes::ZERO {
+            return Err(AIError::WeightsAlreadyCommitted { model_id, version });
+        }
+
+        if !pipeline.is_empty() {
+            let parsed = Pipeline::decode(&pipeline).map_err(AIError::pipeline)?;
+            if parsed.output_dim() > config.input_size.as_usize() {
+                return Err(AIError::FeatureCountExceeded {
+                    max: config.input_size,
+                    actual: U256::from(parsed.output_dim()),
+                });
+            }
+        }
+
+        config.preprocessing = pipeline;
+        self.model_configs.insert(key, config);
+
+        evm::log(ModelPreprocessingSet {
+            model_id,
+            version,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "beginWeightUpload")]
+    pub fn begin_weight_upload(
+        &mut self,
+        model_id: U256,
+        version: U256,
+        committed_root: FixedBytes<32>,
+        total_size: U256,
+        chunk_count: U256,
+    ) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        let config = self.model_configs.get(&key)
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+
+        // Los pesos de una versión confirmada son inmutables
+        if config.weights_root != FixedBytes::ZERO {
+            return Err(AIError::WeightsAlreadyCommitted { model_id, version });
+        }
+        // chunk_count viene del llamador: se acota antes de multiplicar y de
+        // pasarlo a u32
+        let max_chunks = U256::from(weights::MAX_CHUNK_COUNT);
+        let max_size = chunk_count.min(max_chunks)
+            .checked_mul(U256::from(weights::MAX_CHUNK_SIZE))
+            .unwrap_or(U256::MAX);
+        if chunk_count == U256::ZERO || chunk_count > max_chunks || total_size > max_size {
+            return Err(AIError::UploadTooLarge { total_size, max_size });
+        }
+
+        let upload = WeightUpload {
+            model_id,
+            version,
+            committed_root,
+            total_size,
+            chunk_count,
+            received_chunks: U256::ZERO,
+            data: Bytes::new(),
+            leaves: Vec::new(),
+        };
+        self.weight_uploads.insert(key, upload);
+
+        evm::log(WeightUploadStarted {
+            model_id,
+            version,
+            committed_root: committed_root.0,
+            chunk_count: chunk_count.as_u32(),
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "uploadWeightChunk")]
+    pub fn upload_weight_chunk(
+        &mut self,
+        model_id: U256,
+        version: U256,
+        chunk_index: U256,
+        chunk: Bytes,
+    ) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        let mut upload = self.weight_uploads.get(&key)
+            .ok_or(AIError::UploadNotStarted { model_id, version })?;
+
+        // Los trozos se aceptan estrictamente en orden
+        if chunk_index != upload.received_chunks || chunk_index >= upload.chunk_count {
+            return Err(AIError::UnexpectedChunk {
+                expected: upload.received_chunks,
+                actual: chunk_index,
+            });
+        }
+        if chunk.is_empty() || chunk.len() > weights::MAX_CHUNK_SIZE {
+            return Err(AIError::InvalidChunkSize { size: U256::from(chunk.len()) });
+        }
+        let received_size = U256::from(upload.data.len() + chunk.len());
+        if received_size > upload.total_size {
+            return Err(AIError::UploadSizeMismatch {
+                expected: upload.total_size,
+                actual: received_size,
+            });
+        }
+
+        let leaf = weights::chunk_leaf(chunk_index.as_u32(), &chunk);
+        let mut data = upload.data.to_vec();
+        data.extend_from_slice(&chunk);
+
+        upload.data = data.into();
+        upload.leaves.push(leaf);
+        upload.received_chunks += U256::from(1);
+        self.weight_uploads.insert(key, upload);
+
+        evm::log(WeightChunkUploaded {
+            model_id,
+            version,
+            chunk_index: chunk_index.as_u32(),
+            leaf: leaf.0,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "finalizeWeightUpload")]
+    pub fn finalize_weight_upload(&mut self, model_id: U256, version: U256) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        let upload = self.weight_uploads.get(&key)
+            .ok_or(AIError::UploadNotStarted { model_id, version })?;
+        let mut config = self.model_configs.get(&key)
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+
+        if upload.received_chunks != upload.chunk_count {
+            return Err(AIError::UploadIncomplete {
+                received: upload.received_chunks,
+                expected: upload.chunk_count,
+            });
+        }
+        if U256::from(upload.data.len()) != upload.total_size {
+            return Err(AIError::UploadSizeMismatch {
+                expected: upload.total_size,
+                actual: U256::from(upload.data.len()),
+            });
+        }
+
+        let root = weights::merkle_root(&upload.leaves);
+        if root != upload.committed_root {
+            return Err(AIError::WeightsRootMismatch {
+                committed: upload.committed_root,
+                computed: root,
+            });
+        }
+
+        // El modelo debe coincidir con el tipo y las dimensiones registradas
+        decode_model(&config, &upload.data)?;
+
+        self.model_weights.insert(key, upload.data);
+        self.weight_uploads.remove(&key);
+
+        config.weights_root = root;
+        config.is_active = true;
+        self.model_configs.insert(key, config);
+
+        evm::log(ModelWeightsCommitted {
+            model_id,
+            version,
+            weights_root: root.0,
+        });
+
+        // La primera versión confirmada pasa a servir automáticamente
+        if self.active_version(model_id) == U256::ZERO {
+            self.set_active_version(model_id, version);
+        }
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "cancelWeightUpload")]
+    pub fn cancel_weight_upload(&mut self, model_id: U256, version: U256) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let key = version_key(model_id, version);
+        if self.weight_uploads.get(&key).is_none() {
+            return Err(AIError::UploadNotStarted { model_id, version });
+        }
+        self.weight_uploads.remove(&key);
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "activateModelVersion")]
+    pub fn activate_model_version(&mut self, model_id: U256, version: U256) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let config = self.model_configs.get(&version_key(model_id, version))
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+        if !config.is_active {
+            return Err(AIError::ModelInactive { model_id, version });
+        }
+
+        let current = self.active_version(model_id);
+        if current == version {
+            return Err(AIError::VersionAlreadyActive { model_id, version });
+        }
+
+        // Guardar la versión actual para poder volver a ella
+        if current != U256::ZERO {
+            let mut history = self.version_history.get(&model_id).unwrap_or_default();
+            history.push(current);
+            self.version_history.insert(model_id, history);
+        }
+
+        self.set_active_version(model_id, version);
         Ok(true)
     }
 
+    #[stylus_fn(name = "deactivateModelVersion")]
+    pub fn deactivate_model_version(&mut self, model_id: U256, version: U256) -> Result<bool, AIError> {
+        // La plataforma puede retirar cualquier modelo, también los de editores
+        if !self.has_role(MODEL_MANAGER_ROLE, msg::sender()) {
+            self.ensure_model_manager(model_id)?;
+        }
+
+        let key = version_key(model_id, version);
+        let mut config = self.model_configs.get(&key)
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })?;
+        if !config.is_active {
+            return Err(AIError::ModelInactive { model_id, version });
+        }
+
+        config.is_active = false;
+        self.model_configs.insert(key, config);
+
+        // Si era la versión en servicio, el modelo queda sin versión activa
+        if self.active_version(model_id) == version {
+            self.set_active_version(model_id, U256::ZERO);
+        }
+
+        evm::log(ModelVersionDeactivated {
+            model_id,
+            version,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "rollbackModel")]
+    pub fn rollback_model(&mut self, model_id: U256) -> Result<U256, AIError> {
+        self.ensure_model_manager(model_id)?;
+
+        let mut history = self.version_history.get(&model_id).unwrap_or_default();
+        let current = self.active_version(model_id);
+
+        // Saltar versiones anteriores que se hayan desactivado desde entonces
+        while let Some(previous) = history.pop() {
+            let usable = self.model_configs.get(&version_key(model_id, previous))
+                .map(|config| config.is_active)
+                .unwrap_or(false);
+            if usable && previous != current {
+                self.version_history.insert(model_id, history);
+                self.set_active_version(model_id, previous);
+
+                evm::log(ModelRolledBack {
+                    model_id,
+                    from_version: current,
+                    to_version: previous,
+                });
+
+                return Ok(previous);
+            }
+        }
+
+        self.version_history.insert(model_id, history);
+        Err(AIError::NoPreviousVersion { model_id })
+    }
+
     #[stylus_fn(name = "processInference")]
+    #[payable]
     pub fn process_inference(
         &mut self,
         model_id: U256,
         input_data: Bytes,
-    ) -> Result<InferenceResult, Vec<u8>> {
-        let config = self.model_configs.get(&model_id)
-            .ok_or("Model not found")?;
-        
-        require!(config.is_active, "Model not active");
-        require!(input_data.len() <= config.input_size.as_usize(), "Input too large");
-
-        let start_gas = evm::gas_left();
-        let start_time = evm::block_timestamp();
+    ) -> Result<InferenceResult, AIError> {
+        self.ensure_not_paused()?;
+        let config = self.active_config(model_id)?;
 
-        // Procesar inferencia
-        let result = self.run_inference(model_id, &input_data)?;
-        
-        // Calcular estadísticas
-        let gas_used = start_gas - evm::gas_left();
-        let processing_time = evm::block_timestamp() - start_time;
-
-        // Actualizar estadísticas
-        self.update_stats(model_id, gas_used.into(), processing_time.into())?;
+        if !config.is_active {
+            return Err(AIError::ModelInactive { model_id, version: config.version });
+        }
+        self.ensure_breaker_closed(model_id)?;
+        self.ensure_license_accepted(model_id)?;
+        self.meter_usage(&config, 1)?;
+        let pricing = self.charge_fees(&config, [input_data.len()])?;
 
+        let result = self.infer(&config, &input_data)?;
+        if let Some(pricing) = pricing {
+            self.settle_fees(&config, &pricing, [(input_data.len(), result.success)]);
+        }
         Ok(result)
     }
 
     #[stylus_fn(name = "batchProcess")]
+    #[payable]
     pub fn batch_process(
         &mut self,
         model_id: U256,
         inputs: Vec<Bytes>,
-    ) -> Result<Vec<InferenceResult>, Vec<u8>> {
-        let config = self.model_configs.get(&model_id)
-            .ok_or("Model not found")?;
-        
-        require!(inputs.len() <= config.batch_size.as_usize(), "Batch too large");
+    ) -> Result<Vec<InferenceResult>, AIError> {
+        self.ensure_not_paused()?;
+        let config = self.active_config(model_id)?;
+        self.ensure_breaker_closed(model_id)?;
+
+        if inputs.len() > config.batch_size.as_usize() {
+            return Err(AIError::BatchTooLarge {
+                max: config.batch_size,
+                actual: U256::from(inputs.len()),
+            });
+        }
+        self.ensure_license_accepted(model_id)?;
+        self.meter_usage(&config, inputs.len())?;
+        let pricing = self.charge_fees(&config, inputs.iter().map(|input| input.len()))?;
 
         let mut results = Vec::with_capacity(inputs.len());
         let start_gas = evm::gas_left();
 
-        for input in inputs {
-            let result = self.process_inference(model_id, input)?;
+        // El breaker se comprueba solo al inicio: si salta a mitad del lote,
+        // el lote termina y el disparo queda persistido
+        for input in &inputs {
+            let result = self.infer(&config, input)?;
             results.push(result);
         }
+        if let Some(pricing) = pricing {
+            let outcomes = inputs.iter().zip(&results).map(|(input, result)| (input.len(), result.success));
+            self.settle_fees(&config, &pricing, outcomes);
+        }
 
         let total_gas = start_gas - evm::gas_left();
-        emit!(BatchProcessed {
+        evm::log(BatchProcessed {
             model_id,
             count: inputs.len() as u32,
             gas_used: total_gas
@@ -141,159 +769,2410 @@ impl AIProcessor {
         Ok(results)
     }
 
+    // Devuelve la versión en servicio o, si no hay ninguna, la última registrada
     #[stylus_fn(name = "getModelConfig")]
-    pub fn get_model_config(&self, model_id: U256) -> Result<ModelConfig, Vec<u8>> {
-        self.model_configs.get(&model_id)
-            .ok_or_else(|| "Model not found".into())
+    pub fn get_model_config(&self, model_id: U256) -> Result<ModelConfig, AIError> {
+        let mut version = self.active_version(model_id);
+        if version == U256::ZERO {
+            version = self.latest_versions.get(&model_id).unwrap_or(U256::ZERO);
+        }
+        self.model_configs.get(&version_key(model_id, version))
+            .ok_or(AIError::ModelNotFound { model_id })
+    }
+
+    #[stylus_fn(name = "getModelVersion")]
+    pub fn get_model_version(&self, model_id: U256, version: U256) -> Result<ModelConfig, AIError> {
+        self.model_configs.get(&version_key(model_id, version))
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })
+    }
+
+    #[stylus_fn(name = "getActiveVersion")]
+    pub fn get_active_version(&self, model_id: U256) -> Result<U256, AIError> {
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+        Ok(self.active_version(model_id))
     }
 
     #[stylus_fn(name = "getProcessingStats")]
-    pub fn get_processing_stats(&self, model_id: U256) -> Result<ProcessingStats, Vec<u8>> {
+    pub fn get_processing_stats(&self, model_id: U256) -> Result<ProcessingStats, AIError> {
         self.stats.get(&model_id)
-            .ok_or_else(|| "Stats not found".into())
+            .ok_or(AIError::StatsNotFound { model_id })
     }
 
-    fn run_inference(&mut self, model_id: U256, input: &Bytes) -> Result<InferenceResult, Vec<u8>> {
-        let request_id = self.get_next_request_id();
-        let input_hash = evm::keccak256(input);
+    #[stylus_fn(name = "getRequestOrigin")]
+    pub fn get_request_origin(&self, request_id: U256) -> Result<RequestOrigin, AIError> {
+        self.request_ids.origin(request_id)
+            .ok_or(AIError::RequestNotFound { request_id })
+    }
 
-        // Simular procesamiento de IA
-        let output = self.simulate_inference(input)?;
-        let confidence = self.calculate_confidence(&output);
+    #[stylus_fn(name = "getInferenceResult")]
+    pub fn get_inference_result(&self, request_id: U256) -> Result<InferenceResult, AIError> {
+        self.inference_results.get(&request_id)
+            .ok_or(AIError::RequestNotFound { request_id })
+    }
 
-        let result = InferenceResult {
-            request_id,
-            input_hash: input_hash.into(),
-            output: output.into(),
-            confidence,
-            timestamp: evm::block_timestamp().into(),
-            gas_used: evm::gas_left().into(),
-        };
+    #[stylus_fn(name = "getResultsByModel")]
+    pub fn get_results_by_model(
+        &self,
+        model_id: U256,
+        cursor: U256,
+        limit: U256,
+    ) -> Result<ResultPage, AIError> {
+        self.list_page(results::model_list(model_id), cursor, limit)
+    }
 
-        self.inference_results.insert(request_id, result.clone());
-        Ok(result)
+    #[stylus_fn(name = "getResultsByRequester")]
+    pub fn get_results_by_requester(
+        &self,
+        requester: Address,
+        cursor: U256,
+        limit: U256,
+    ) -> Result<ResultPage, AIError> {
+        self.list_page(results::requester_list(requester), cursor, limit)
     }
 
-    fn simulate_inference(&self, input: &Bytes) -> Result<Vec<u8>, Vec<u8>> {
-        // Implementar lógica real de inferencia aquí
-        let mut output = Vec::with_capacity(input.len());
-        for byte in input.iter() {
-            output.push(byte.rotate_left(2));
+    // El cursor es una posición en la lista global; un cursor anterior a
+    // from_time se adelanta al primer resultado del rango
+    #[stylus_fn(name = "getResultsByTimeRange")]
+    pub fn get_results_by_time_range(
+        &self,
+        from_time: U256,
+        to_time: U256,
+        cursor: U256,
+        limit: U256,
+    ) -> Result<ResultPage, AIError> {
+        let limit = page_size(limit)?;
+        if from_time > to_time {
+            return Err(AIError::InvalidTimeRange { from_time, to_time });
         }
-        Ok(output)
-    }
 
-    fn calculate_confidence(&self, output: &[u8]) -> U256 {
-        // Implementar cálculo real de confianza aquí
-        let sum: u32 = output.iter().map(|&x| x as u32).sum();
-        U256::from(sum.saturating_mul(100) / (output.len() as u32 * 255))
-    }
+        let list = results::global_list();
+        let timestamp = |id: U256| {
+            self.inference_results.get(&id)
+                .map(|result| result.timestamp)
+                .unwrap_or(U256::ZERO)
+        };
+        let first = self.result_index.partition_point(list, |id| timestamp(id) < from_time);
+        let end = self.result_index.partition_point(list, |id| timestamp(id) <= to_time);
 
-    fn update_stats(
-        &mut self,
-        model_id: U256,
-        gas_used: U256,
-        processing_time: U256,
-    ) -> Result<(), Vec<u8>> {
-        let mut stats = self.stats.get(&model_id)
-            .unwrap_or_else(|| ProcessingStats {
-                total_requests: U256::ZERO,
-                total_gas_used: U256::ZERO,
-                avg_processing_time: U256::ZERO,
-                success_rate: U256::from(100),
-            });
+        let start = first.max(cursor.saturating_to::<usize>());
+        let count = end.saturating_sub(start).min(limit);
+        let (ids, next) = self.result_index.page(list, start, count);
 
-        stats.total_requests += U256::from(1);
-        stats.total_gas_used += gas_used;
-        stats.avg_processing_time = (
-            stats.avg_processing_time * (stats.total_requests - U256::from(1)) + processing_time
-        ) / stats.total_requests;
+        Ok(ResultPage {
+            results: self.load_results(&ids),
+            next_cursor: U256::from(next),
+            has_more: next < end,
+        })
+    }
 
-        self.stats.insert(model_id, stats);
-        Ok(())
+    #[stylus_fn(name = "countResults")]
+    pub fn count_results(&self) -> U256 {
+        U256::from(self.result_index.len(results::global_list()))
     }
 
-    fn get_next_request_id(&self) -> U256 {
-        // Implementar generación segura de IDs
-        let block_number = evm::block_number();
-        let timestamp = evm::block_timestamp();
-        let sender = msg::sender();
-        
-        let mut data = Vec::with_capacity(32 * 3);
-        data.extend_from_slice(&block_number.to_be_bytes());
-        data.extend_from_slice(&timestamp.to_be_bytes());
-        data.extend_from_slice(sender.as_bytes());
-        
-        U256::from_be_bytes(evm::keccak256(&data))
+    #[stylus_fn(name = "countResultsByModel")]
+    pub fn count_results_by_model(&self, model_id: U256) -> U256 {
+        U256::from(self.result_index.len(results::model_list(model_id)))
     }
 
-    fn ensure_owner(&self) -> Result<(), Vec<u8>> {
-        require!(msg::sender() == self.owner, "Not owner");
-        Ok(())
+    #[stylus_fn(name = "countResultsByRequester")]
+    pub fn count_results_by_requester(&self, requester: Address) -> U256 {
+        U256::from(self.result_index.len(results::requester_list(requester)))
     }
-}
 
-#[derive(Debug)]
-pub struct BatchProcessed {
-    model_id: U256,
-    count: u32,
-    gas_used: u64,
-}
+    #[stylus_fn(name = "pause")]
+    pub fn pause(&mut self) -> Result<bool, AIError> {
+        self.ensure_role(PAUSER_ROLE)?;
+        self.ensure_not_paused()?;
+        self.paused = true;
 
-impl Event for BatchProcessed {
-    const SIGNATURE: [u8; 32] = keccak256!("BatchProcessed(uint256,uint32,uint64)");
-}
+        evm::log(Paused {
+            account: msg::sender(),
+        });
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        Ok(true)
+    }
 
-    #[test]
-    fn test_register_model() {
-        let mut processor = AIProcessor::new();
-        let result = processor.register_model(
-            U256::from(1),
-            U256::from(1024),
-            U256::from(128),
-            U256::from(32),
-            U256::from(100),
-        );
-        assert!(result.is_ok());
+    #[stylus_fn(name = "unpause")]
+    pub fn unpause(&mut self) -> Result<bool, AIError> {
+        self.ensure_role(PAUSER_ROLE)?;
+        if !self.paused {
+            return Err(AIError::NotPaused);
+        }
+        self.paused = false;
+
+        evm::log(Unpaused {
+            account: msg::sender(),
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "paused")]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Reconfigurar el breaker reinicia su ventana pero no lo rearma si ya saltó
+    #[stylus_fn(name = "setCircuitBreaker")]
+    pub fn set_circuit_breaker(
+        &mut self,
+        model_id: U256,
+        max_error_rate_bps: U256,
+        min_avg_confidence_bps: U256,
+        max_avg_confidence_bps: U256,
+        min_samples: U256,
+    ) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+
+        let bps = U256::from(engine::BPS);
+        if max_error_rate_bps > bps
+            || min_avg_confidence_bps > max_avg_confidence_bps
+            || max_avg_confidence_bps > bps
+            || min_samples == U256::ZERO
+        {
+            return Err(AIError::InvalidBreakerConfig);
+        }
+
+        let tripped = self.circuit_breakers.get(&model_id)
+            .map(|breaker| breaker.tripped)
+            .unwrap_or(false);
+        self.circuit_breakers.insert(model_id, CircuitBreaker {
+            max_error_rate_bps,
+            min_avg_confidence_bps,
+            max_avg_confidence_bps,
+            min_samples,
+            window_requests: U256::ZERO,
+            window_failures: U256::ZERO,
+            window_confidence_bps: U256::ZERO,
+            previous_requests: U256::ZERO,
+            previous_failures: U256::ZERO,
+            previous_confidence_bps: U256::ZERO,
+            tripped,
+        });
+
+        evm::log(CircuitBreakerConfigured {
+            model_id,
+            max_error_rate_bps,
+            min_avg_confidence_bps,
+            max_avg_confidence_bps,
+            min_samples,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "resetCircuitBreaker")]
+    pub fn reset_circuit_breaker(&mut self, model_id: U256) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        let mut breaker = self.circuit_breakers.get(&model_id)
+            .ok_or(AIError::BreakerNotConfigured { model_id })?;
+
+        breaker.window_requests = U256::ZERO;
+        breaker.window_failures = U256::ZERO;
+        breaker.window_confidence_bps = U256::ZERO;
+        breaker.previous_requests = U256::ZERO;
+        breaker.previous_failures = U256::ZERO;
+        breaker.previous_confidence_bps = U256::ZERO;
+        breaker.tripped = false;
+        self.circuit_breakers.insert(model_id, breaker);
+
+        evm::log(CircuitBreakerReset {
+            model_id,
+            account: msg::sender(),
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "getCircuitBreaker")]
+    pub fn get_circuit_breaker(&self, model_id: U256) -> Result<CircuitBreaker, AIError> {
+        self.circuit_breakers.get(&model_id)
+            .ok_or(AIError::BreakerNotConfigured { model_id })
+    }
+
+    #[stylus_fn(name = "setQuota")]
+    pub fn set_quota(&mut self, model_id: U256, quota: QuotaConfig) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+        quota.validate().map_err(AIError::quota)?;
+
+        evm::log(QuotaConfigured {
+            model_id,
+            window_blocks: quota.window_blocks,
+            epoch_blocks: quota.epoch_blocks,
+            caller_requests_per_window: quota.caller_requests_per_window,
+            caller_compute_units_per_epoch: quota.caller_compute_units_per_epoch,
+            model_requests_per_window: quota.model_requests_per_window,
+            model_compute_units_per_epoch: quota.model_compute_units_per_epoch,
+        });
+
+        // El consumo ya anotado se conserva y se reinicia con su ventana
+        self.quotas.insert(model_id, quota);
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "getQuota")]
+    pub fn get_quota(&self, model_id: U256) -> Option<QuotaConfig> {
+        self.quotas.get(&model_id)
+    }
+
+    #[stylus_fn(name = "setQuotaExempt")]
+    pub fn set_quota_exempt(&mut self, account: Address, exempt: bool) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        self.quota_exempt.insert(account, exempt);
+
+        evm::log(QuotaExemptionSet {
+            account,
+            exempt,
+            sender: msg::sender(),
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "isQuotaExempt")]
+    pub fn is_quota_exempt(&self, account: Address) -> bool {
+        self.quota_exempt.get(&account).unwrap_or(false)
+    }
+
+    #[stylus_fn(name = "getRemainingQuota")]
+    pub fn get_remaining_quota(&self, model_id: U256, account: Address) -> RemainingQuota {
+        let unlimited = RemainingQuota {
+            requests: U256::MAX,
+            compute_units: U256::MAX,
+            window_resets_at: U256::ZERO,
+            epoch_resets_at: U256::ZERO,
+        };
+        let Some(quota) = self.quotas.get(&model_id) else {
+            return unlimited;
+        };
+        if self.is_quota_exempt(account) {
+            return unlimited;
+        }
+
+        let block: U256 = evm::block_number().into();
+        let remaining = |account: Address, request_limit: U256, unit_limit: U256| {
+            let mut usage = self.usage.get(&model_account_key(model_id, account)).unwrap_or_default();
+            usage.roll(&quota, block);
+            usage.remaining(request_limit, unit_limit)
+        };
+        let (caller_requests, caller_units) = remaining(
+            account,
+            quota.caller_requests_per_window,
+            quota.caller_compute_units_per_epoch,
+        );
+        let (model_requests, model_units) = remaining(
+            Address::ZERO,
+            quota.model_requests_per_window,
+            quota.model_compute_units_per_epoch,
+        );
+
+        RemainingQuota {
+            requests: caller_requests.min(model_requests),
+            compute_units: caller_units.min(model_units),
+            window_resets_at: quotas::next_reset(block, quota.window_blocks),
+            epoch_resets_at: quotas::next_reset(block, quota.epoch_blocks),
+        }
+    }
+
+    #[stylus_fn(name = "setFeeToken")]
+    pub fn set_fee_token(&mut self, token: Address) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        self.fee_token = token;
+
+        evm::log(FeeTokenSet {
+            token,
+            sender: msg::sender(),
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "feeToken")]
+    pub fn fee_token(&self) -> Address {
+        self.fee_token
+    }
+
+    #[stylus_fn(name = "getModelOwner")]
+    pub fn get_model_owner(&self, model_id: U256) -> Result<Address, AIError> {
+        self.model_owners.get(&model_id)
+            .ok_or(AIError::ModelNotFound { model_id })
+    }
+
+    // Una tarifa a cero en todos sus términos deja el modelo gratis
+    #[stylus_fn(name = "setModelPricing")]
+    pub fn set_model_pricing(&mut self, model_id: U256, pricing: PricingConfig) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+        // Solo ETH nativo o el token configurado
+        if !pricing.is_native() && pricing.token != self.fee_token {
+            return Err(AIError::UnsupportedFeeToken { token: pricing.token });
+        }
+
+        evm::log(ModelPricingSet {
+            model_id,
+            token: pricing.token,
+            base_fee: pricing.base_fee,
+            fee_per_compute_unit: pricing.fee_per_compute_unit,
+            fee_per_input_byte: pricing.fee_per_input_byte,
+        });
+
+        self.pricing.insert(model_id, pricing);
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "getModelPricing")]
+    pub fn get_model_pricing(&self, model_id: U256) -> Option<PricingConfig> {
+        self.pricing.get(&model_id)
+    }
+
+    // Lo que costaría ahora una inferencia de `input_size` bytes
+    #[stylus_fn(name = "quoteFee")]
+    pub fn quote_fee(&self, model_id: U256, input_size: U256) -> Result<U256, AIError> {
+        let config = self.active_config(model_id)?;
+        Ok(self.pricing.get(&model_id)
+            .map(|pricing| pricing.fee(config.compute_units, input_size.as_usize()))
+            .unwrap_or(U256::ZERO))
+    }
+
+    #[stylus_fn(name = "accruedFees")]
+    pub fn accrued_fees(&self, account: Address, token: Address) -> U256 {
+        self.accrued_fees.get(&fee_key(account, token)).unwrap_or(U256::ZERO)
+    }
+
+    // Retira todo lo acumulado por el llamador en `token` (Address::ZERO = ETH)
+    #[stylus_fn(name = "withdrawFees")]
+    pub fn withdraw_fees(&mut self, token: Address) -> Result<U256, AIError> {
+        let account = msg::sender();
+        let key = fee_key(account, token);
+        let amount = self.accrued_fees.get(&key).unwrap_or(U256::ZERO);
+        if amount.is_zero() {
+            return Err(AIError::NothingToWithdraw { account, token });
+        }
+
+        // Se pone a cero antes de transferir para que una reentrada no cobre dos veces
+        self.accrued_fees.insert(key, U256::ZERO);
+        let sent = if token == NATIVE_TOKEN {
+            call::transfer_eth(account, amount).is_ok()
+        } else {
+            matches!(IERC20::new(token).transfer(Call::new_in(self), account, amount), Ok(true))
+        };
+        if !sent {
+            return Err(AIError::FeeTransferFailed { token, amount });
+        }
+
+        evm::log(FeesWithdrawn {
+            account,
+            token,
+            amount,
+        });
+
+        Ok(amount)
+    }
+
+    // Id del modelo `local_id` dentro del espacio de nombres de `publisher`
+    #[stylus_fn(name = "publisherModelId")]
+    pub fn publisher_model_id(&self, publisher: Address, local_id: U256) -> Result<U256, AIError> {
+        marketplace::namespaced_model_id(publisher, local_id)
+            .ok_or(AIError::LocalIdTooLarge { local_id })
+    }
+
+    #[stylus_fn(name = "setPlatformFee")]
+    pub fn set_platform_fee(&mut self, fee_bps: U256, treasury: Address) -> Result<bool, AIError> {
+        self.ensure_role(ADMIN_ROLE)?;
+        let max = U256::from(marketplace::MAX_PLATFORM_FEE_BPS);
+        if fee_bps > max {
+            return Err(AIError::InvalidPlatformFee { fee_bps, max });
+        }
+        self.platform_fee_bps = fee_bps;
+        self.platform_treasury = treasury;
+
+        evm::log(PlatformFeeSet {
+            fee_bps,
+            treasury,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "platformFee")]
+    pub fn platform_fee(&self) -> (U256, Address) {
+        (self.platform_fee_bps, self.platform_treasury)
+    }
+
+    #[stylus_fn(name = "setModelLicense")]
+    pub fn set_model_license(&mut self, model_id: U256, license: License) -> Result<bool, AIError> {
+        self.ensure_model_manager(model_id)?;
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+
+        evm::log(ModelLicenseSet {
+            model_id,
+            terms_hash: license.terms_hash.0,
+            commercial_use: license.commercial_use,
+            requires_acceptance: license.requires_acceptance,
+        });
+
+        // Cambiar los términos invalida las aceptaciones anteriores, que
+        // quedan guardadas con el hash viejo
+        self.licenses.insert(model_id, license);
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "getModelLicense")]
+    pub fn get_model_license(&self, model_id: U256) -> Option<License> {
+        self.licenses.get(&model_id)
+    }
+
+    // Se acepta un hash concreto para no aceptar sin saberlo unos términos
+    // que el editor haya cambiado justo antes
+    #[stylus_fn(name = "acceptModelLicense")]
+    pub fn accept_model_license(&mut self, model_id: U256, terms_hash: FixedBytes<32>) -> Result<bool, AIError> {
+        let license = self.licenses.get(&model_id)
+            .ok_or(AIError::ModelNotFound { model_id })?;
+        if license.terms_hash != terms_hash {
+            return Err(AIError::LicenseTermsMismatch {
+                model_id,
+                expected: license.terms_hash,
+                actual: terms_hash,
+            });
+        }
+
+        let account = msg::sender();
+        self.license_acceptances.insert(model_account_key(model_id, account), terms_hash);
+
+        evm::log(ModelLicenseAccepted {
+            model_id,
+            account,
+            terms_hash: terms_hash.0,
+        });
+
+        Ok(true)
+    }
+
+    #[stylus_fn(name = "hasAcceptedLicense")]
+    pub fn has_accepted_license(&self, model_id: U256, account: Address) -> bool {
+        let Some(license) = self.licenses.get(&model_id) else {
+            return false;
+        };
+        self.license_acceptances.get(&model_account_key(model_id, account)) == Some(license.terms_hash)
+    }
+
+    // Los errores de entrada son culpa del llamador y revierten. Los fallos
+    // del modelo se devuelven como resultado con success = false para que
+    // cuenten en las estadísticas y en el breaker (un revert los borraría).
+    fn infer(&mut self, config: &ModelConfig, input: &Bytes) -> Result<InferenceResult, AIError> {
+        // Validar la entrada contra el esquema antes de inferir
+        let features = input_features(config, input)?;
+
+        let start_gas = evm::gas_left();
+
+        // Procesar inferencia
+        let result = self.run_inference(config, input, &features);
+
+        // Actualizar estadísticas
+        let gas_used = start_gas - evm::gas_left();
+        self.update_stats(config.model_id, &result, input.len(), gas_used.into());
+        self.update_circuit_breaker(config.model_id, &result);
+
+        Ok(result)
+    }
+
+    fn run_inference(
+        &mut self,
+        config: &ModelConfig,
+        input: &Bytes,
+        features: &[i32],
+    ) -> InferenceResult {
+        let request_id = self.request_ids.allocate(msg::sender(), config.model_id);
+        let input_hash = evm::keccak256(input);
+
+        let mut result = InferenceResult {
+            request_id,
+            model_id: config.model_id,
+            model_version: config.version,
+            input_hash: input_hash.into(),
+            output: Bytes::new(),
+            predicted_class: U256::ZERO,
+            confidence: U256::ZERO,
+            confidence_bps: U256::ZERO,
+            timestamp: evm::block_timestamp().into(),
+            gas_used: evm::gas_left().into(),
+            success: false,
+        };
+
+        match self.evaluate(config, features) {
+            Ok((prediction, output)) => {
+                result.output = output.into();
+                result.predicted_class = U256::from(prediction.class);
+                result.confidence = U256::from(prediction.confidence_percent());
+                result.confidence_bps = U256::from(prediction.confidence_bps);
+                result.success = true;
+
+                evm::log(InferenceCompleted {
+                    request_id,
+                    model_id: config.model_id,
+                    input_hash: input_hash.into(),
+                    output_hash: evm::keccak256(&result.output).into(),
+                    confidence: result.confidence_bps,
+                    source: SOURCE_ON_CHAIN,
+                });
+            }
+            Err(error) => {
+                evm::log(InferenceFailed {
+                    request_id,
+                    model_id: config.model_id,
+                    reason: error.encode(),
+                });
+            }
+        }
+
+        self.inference_results.insert(request_id, result.clone());
+        self.result_index.record(request_id, config.model_id, msg::sender());
+        result
+    }
+
+    fn evaluate(&self, config: &ModelConfig, features: &[i32]) -> Result<(Prediction, Vec<u8>), AIError> {
+        let weights = self.model_weights.get(&version_key(config.model_id, config.version))
+            .ok_or(AIError::WeightsNotSet {
+                model_id: config.model_id,
+                version: config.version,
+            })?;
+        let model = decode_model(config, &weights)?;
+
+        // Evaluar el modelo en punto fijo
+        let prediction = model.predict_features(features).map_err(AIError::inference)?;
+        let output = if config.output_schema.is_empty() {
+            engine::encode_scores(&prediction.scores)
+        } else {
+            let fields = schema::parse_output_schema(&config.output_schema)?;
+            schema::encode_output(&fields, &prediction)
+        };
+        Ok((prediction, output))
+    }
+
+    fn list_page(
+        &self,
+        list: FixedBytes<32>,
+        cursor: U256,
+        limit: U256,
+    ) -> Result<ResultPage, AIError> {
+        let limit = page_size(limit)?;
+        let (ids, next) = self.result_index.page(list, cursor.saturating_to::<usize>(), limit);
+
+        Ok(ResultPage {
+            results: self.load_results(&ids),
+            next_cursor: U256::from(next),
+            has_more: next < self.result_index.len(list),
+        })
+    }
+
+    fn load_results(&self, ids: &[U256]) -> Vec<InferenceResult> {
+        ids.iter()
+            .filter_map(|id| self.inference_results.get(id))
+            .collect()
+    }
+
+    fn active_config(&self, model_id: U256) -> Result<ModelConfig, AIError> {
+        if self.latest_versions.get(&model_id).is_none() {
+            return Err(AIError::ModelNotFound { model_id });
+        }
+
+        let version = self.active_version(model_id);
+        if version == U256::ZERO {
+            return Err(AIError::ModelInactive { model_id, version });
+        }
+
+        self.model_configs.get(&version_key(model_id, version))
+            .ok_or(AIError::ModelVersionNotFound { model_id, version })
+    }
+
+    fn active_version(&self, model_id: U256) -> U256 {
+        self.active_versions.get(&model_id).unwrap_or(U256::ZERO)
+    }
+
+    fn set_active_version(&mut self, model_id: U256, version: U256) {
+        let previous = self.active_version(model_id);
+        self.active_versions.insert(model_id, version);
+
+        evm::log(ModelVersionActivated {
+            model_id,
+            previous_version: previous,
+            version,
+        });
+    }
+
+    fn update_stats(
+        &mut self,
+        model_id: U256,
+        result: &InferenceResult,
+        input_size: usize,
+        gas_used: U256,
+    ) {
+        let mut stats = self.stats.get(&model_id).unwrap_or_default();
+        stats.record(
+            SOURCE_ON_CHAIN,
+            input_size,
+            gas_used,
+            result.success,
+            result.confidence_bps,
+        );
+
+        evm::log(StatsUpdated {
+            model_id,
+            total_requests: stats.total_requests,
+            total_gas_used: stats.total_gas_used,
+            success_rate: stats.success_rate,
+        });
+
+        self.stats.insert(model_id, stats);
+    }
+
+    fn update_circuit_breaker(&mut self, model_id: U256, result: &InferenceResult) {
+        let Some(mut breaker) = self.circuit_breakers.get(&model_id) else {
+            return;
+        };
+        if breaker.tripped {
+            return;
+        }
+
+        // Tramo lleno: pasa a ser el anterior y se descarta el más viejo
+        if breaker.window_requests >= breaker.min_samples {
+            breaker.previous_requests = breaker.window_requests;
+            breaker.previous_failures = breaker.window_failures;
+            breaker.previous_confidence_bps = breaker.window_confidence_bps;
+            breaker.window_requests = U256::ZERO;
+            breaker.window_failures = U256::ZERO;
+            breaker.window_confidence_bps = U256::ZERO;
+        }
+
+        breaker.window_requests += U256::from(1);
+        if result.success {
+            breaker.window_confidence_bps += result.confidence_bps;
+        } else {
+            breaker.window_failures += U256::from(1);
+        }
+
+        let requests = breaker.previous_requests + breaker.window_requests;
+        if requests >= breaker.min_samples {
+            let failures = breaker.previous_failures + breaker.window_failures;
+            let error_rate_bps = failures * U256::from(engine::BPS) / requests;
+            let successes = requests - failures;
+            let avg_confidence_bps = if successes == U256::ZERO {
+                U256::ZERO
+            } else {
+                (breaker.previous_confidence_bps + breaker.window_confidence_bps) / successes
+            };
+            let confidence_out_of_bounds = successes > U256::ZERO
+                && (avg_confidence_bps < breaker.min_avg_confidence_bps
+                    || avg_confidence_bps > breaker.max_avg_confidence_bps);
+
+            if error_rate_bps > breaker.max_error_rate_bps || confidence_out_of_bounds {
+                breaker.tripped = true;
+                evm::log(CircuitBreakerTripped {
+                    model_id,
+                    error_rate_bps,
+                    avg_confidence_bps,
+                });
+            }
+        }
+
+        self.circuit_breakers.insert(model_id, breaker);
+    }
+
+    fn ensure_owner(&self) -> Result<(), AIError> {
+        let caller = msg::sender();
+        if caller != self.owner {
+            return Err(AIError::NotOwner { caller });
+        }
+        Ok(())
+    }
+
+    fn ensure_not_paused(&self) -> Result<(), AIError> {
+        if self.paused {
+            return Err(AIError::Paused);
+        }
+        Ok(())
+    }
+
+    // Cobra por adelantado la tarifa de las entradas dadas. En ETH msg.value
+    // debe ser exacto; en ERC-20 se usa transferFrom y el llamador debe haber
+    // aprobado el importe antes. Lo cobrado se reparte en settle_fees una vez
+    // se sabe qué inferencias salieron bien; None si el modelo es gratis.
+    fn charge_fees(
+        &mut self,
+        config: &ModelConfig,
+        input_sizes: impl IntoIterator<Item = usize>,
+    ) -> Result<Option<PricingConfig>, AIError> {
+        let paid = msg::value();
+        let pricing = match self.pricing.get(&config.model_id) {
+            Some(pricing) if !pricing.is_free() => pricing,
+            // Un modelo gratis no acepta ETH: se quedaría bloqueado en el contrato
+            _ if !paid.is_zero() => {
+                return Err(AIError::IncorrectPayment { expected: U256::ZERO, actual: paid });
+            }
+            _ => return Ok(None),
+        };
+
+        let fee = pricing.batch_fee(config.compute_units, input_sizes);
+        let payer = msg::sender();
+        if pricing.is_native() {
+            if paid != fee {
+                return Err(AIError::IncorrectPayment { expected: fee, actual: paid });
+            }
+        } else {
+            if !paid.is_zero() {
+                return Err(AIError::IncorrectPayment { expected: U256::ZERO, actual: paid });
+            }
+            let receiver = contract::address();
+            let collected = IERC20::new(pricing.token)
+                .transfer_from(Call::new_in(self), payer, receiver, fee);
+            if !matches!(collected, Ok(true)) {
+                return Err(AIError::FeeTransferFailed { token: pricing.token, amount: fee });
+            }
+        }
+
+        Ok(Some(pricing))
+    }
+
+    // Solo se cobran las inferencias correctas. Un fallo del modelo no
+    // revierte (borraría el fallo de las estadísticas y del breaker), así que
+    // su parte se devuelve al pagador como saldo retirable con withdrawFees.
+    // Lo cobrado se reparte entre la plataforma y el dueño del modelo.
+    fn settle_fees(
+        &mut self,
+        config: &ModelConfig,
+        pricing: &PricingConfig,
+        outcomes: impl IntoIterator<Item = (usize, bool)>,
+    ) {
+        let (mut paid, mut earned) = (U256::ZERO, U256::ZERO);
+        for (input_size, success) in outcomes {
+            let fee = pricing.fee(config.compute_units, input_size);
+            paid = paid.saturating_add(fee);
+            if success {
+                earned = earned.saturating_add(fee);
+            }
+        }
+        let payer = msg::sender();
+        self.accrue_fees(payer, pricing.token, paid - earned);
+        if earned.is_zero() {
+            return;
+        }
+
+        // La plataforma se queda su parte y el resto es para el dueño del modelo
+        let owner = self.model_owners.get(&config.model_id).unwrap_or(self.owner);
+        let (platform_share, owner_share) =
+            marketplace::split_fee(earned, self.platform_fee_bps.as_limbs()[0] as u32);
+        self.accrue_fees(self.platform_treasury, pricing.token, platform_share);
+        self.accrue_fees(owner, pricing.token, owner_share);
+
+        evm::log(FeeCharged {
+            model_id: config.model_id,
+            payer,
+            token: pricing.token,
+            amount: earned,
+        });
+    }
+
+    fn accrue_fees(&mut self, account: Address, token: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let key = fee_key(account, token);
+        let accrued = self.accrued_fees.get(&key).unwrap_or(U256::ZERO);
+        self.accrued_fees.insert(key, accrued + amount);
+    }
+
+    fn ensure_license_accepted(&self, model_id: U256) -> Result<(), AIError> {
+        let Some(license) = self.licenses.get(&model_id) else {
+            return Ok(());
+        };
+        if !license.requires_acceptance {
+            return Ok(());
+        }
+        let account = msg::sender();
+        if !self.has_accepted_license(model_id, account) {
+            return Err(AIError::LicenseNotAccepted { model_id, account });
+        }
+        Ok(())
+    }
+
+    // Los modelos de la plataforma (espacio de Address::ZERO) los gestiona
+    // MODEL_MANAGER_ROLE; los de un editor, solo el propio editor
+    fn ensure_model_manager(&self, model_id: U256) -> Result<(), AIError> {
+        let namespace = marketplace::namespace_of(model_id);
+        if namespace == Address::ZERO {
+            return self.ensure_role(MODEL_MANAGER_ROLE);
+        }
+        let account = msg::sender();
+        if namespace != account || !self.has_role(PUBLISHER_ROLE, account) {
+            return Err(AIError::NotModelPublisher { model_id, account });
+        }
+        Ok(())
+    }
+
+    // Anota `count` peticiones a nombre del llamador y del modelo. Se cobra
+    // antes de inferir; si algún límite se supera la llamada revierte entera.
+    fn meter_usage(&mut self, config: &ModelConfig, count: usize) -> Result<(), AIError> {
+        let Some(quota) = self.quotas.get(&config.model_id) else {
+            return Ok(());
+        };
+        let caller = msg::sender();
+        if self.is_quota_exempt(caller) {
+            return Ok(());
+        }
+
+        let block: U256 = evm::block_number().into();
+        let requests = U256::from(count);
+        let compute_units = config.compute_units * requests;
+
+        // El total del modelo se guarda bajo Address::ZERO
+        for (account, request_limit, unit_limit) in [
+            (caller, quota.caller_requests_per_window, quota.caller_compute_units_per_epoch),
+            (Address::ZERO, quota.model_requests_per_window, quota.model_compute_units_per_epoch),
+        ] {
+            let key = model_account_key(config.model_id, account);
+            let mut usage = self.usage.get(&key).unwrap_or_default();
+            usage.roll(&quota, block);
+            usage.consume(requests, compute_units, request_limit, unit_limit)
+                .map_err(|limit| match limit {
+                    QuotaLimit::Requests(limit) => AIError::RequestQuotaExceeded {
+                        model_id: config.model_id,
+                        account,
+                        limit,
+                    },
+                    QuotaLimit::ComputeUnits(limit) => AIError::ComputeQuotaExceeded {
+                        model_id: config.model_id,
+                        account,
+                        limit,
+                    },
+                })?;
+            self.usage.insert(key, usage);
+        }
+
+        Ok(())
+    }
+
+    fn ensure_breaker_closed(&self, model_id: U256) -> Result<(), AIError> {
+        let tripped = self.circuit_breakers.get(&model_id)
+            .map(|breaker| breaker.tripped)
+            .unwrap_or(false);
+        if tripped {
+            return Err(AIError::CircuitBreakerOpen { model_id });
+        }
+        Ok(())
+    }
+
+    fn ensure_role(&self, role: FixedBytes<32>) -> Result<(), AIError> {
+        let account = msg::sender();
+        if !self.has_role(role, account) {
+            return Err(AIError::MissingRole { role, account });
+        }
+        Ok(())
+    }
+
+    // El owner no puede quedarse sin ADMIN_ROLE; para cederlo se transfiere la propiedad
+    fn ensure_not_owner_admin(&self, role: FixedBytes<32>, account: Address) -> Result<(), AIError> {
+        if role == ADMIN_ROLE && account == self.owner {
+            return Err(AIError::OwnerAdminRole { account });
+        }
+        Ok(())
+    }
+
+    // Devuelve si hubo cambio; solo entonces se emite el evento
+    fn set_role(&mut self, role: FixedBytes<32>, account: Address, granted: bool) -> bool {
+        if self.has_role(role, account) == granted {
+            return false;
+        }
+        self.roles.insert(role_key(role, account), granted);
+
+        let sender = msg::sender();
+        if granted {
+            evm::log(RoleGranted { role: role.0, account, sender });
+        } else {
+            evm::log(RoleRevoked { role: role.0, account, sender });
+        }
+        true
+    }
+}
+
+fn decode_model(config: &ModelConfig, data: &[u8]) -> Result<Model, AIError> {
+    let model = match config.model_kind {
+        ModelKind::NeuralNetwork => Network::decode(data).map(Model::Network),
+        ModelKind::TreeEnsemble => TreeEnsemble::decode(data).map(Model::Trees),
+        ModelKind::LinearModel => LinearModel::decode(data).map(Model::Linear),
+        ModelKind::QuantizedNetwork => QuantizedNetwork::decode(data).map(Model::Quantized),
+    }
+    .map_err(AIError::model_data)?;
+    model
+        .check_shape(config.input_size.as_usize(), config.output_size.as_usize())
+        .map_err(AIError::model_data)?;
+    Ok(model)
+}
+
+fn input_features(config: &ModelConfig, input: &[u8]) -> Result<Vec<i32>, AIError> {
+    let input_size = config.input_size.as_usize();
+    if !config.preprocessing.is_empty() {
+        return preprocessed_features(config, input);
+    }
+    if config.input_schema.is_empty() {
+        check_input_size(config, input)?;
+        return engine::bytes_to_features(input, input_size).map_err(AIError::input);
+    }
+
+    let types = schema::parse_input_schema(&config.input_schema)?;
+    let values = schema::decode_input(&types, input)?;
+    let features = schema::raw_features(&values);
+    if features.len() > input_size {
+        return Err(AIError::FeatureCountExceeded {
+            max: config.input_size,
+            actual: U256::from(features.len()),
+        });
+    }
+    Ok(features)
+}
+
+// Con pipeline, input_size es el número de features que produce, no el
+// tamaño de la entrada; sin esquema cada byte es una columna.
+fn preprocessed_features(config: &ModelConfig, input: &[u8]) -> Result<Vec<i32>, AIError> {
+    let pipeline = Pipeline::decode(&config.preprocessing).map_err(AIError::pipeline)?;
+    let columns = if config.input_schema.is_empty() {
+        check_input_size(config, input)?;
+        preprocess::columns_from_bytes(input)
+    } else {
+        let types = schema::parse_input_schema(&config.input_schema)?;
+        preprocess::columns_from_values(&schema::decode_input(&types, input)?)
+    };
+    pipeline.apply(&columns).map_err(AIError::input)
+}
+
+fn check_input_size(config: &ModelConfig, input: &[u8]) -> Result<(), AIError> {
+    if input.len() > config.input_size.as_usize() {
+        return Err(AIError::InputTooLarge {
+            max: config.input_size,
+            actual: U256::from(input.len()),
+        });
+    }
+    Ok(())
+}
+
+fn page_size(limit: U256) -> Result<usize, AIError> {
+    if limit == U256::ZERO || limit > U256::from(results::MAX_PAGE_SIZE) {
+        return Err(AIError::InvalidPageSize {
+            limit,
+            max: U256::from(results::MAX_PAGE_SIZE),
+        });
+    }
+    Ok(limit.as_usize())
+}
+
+fn role_key(role: FixedBytes<32>, account: Address) -> FixedBytes<32> {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(role.as_slice());
+    data.extend_from_slice(account.as_slice());
+    evm::keccak256(&data).into()
+}
+
+fn fee_key(account: Address, token: Address) -> FixedBytes<32> {
+    let mut data = Vec::with_capacity(40);
+    data.extend_from_slice(account.as_slice());
+    data.extend_from_slice(token.as_slice());
+    evm::keccak256(&data).into()
+}
+
+fn model_account_key(model_id: U256, account: Address) -> FixedBytes<32> {
+    let mut data = Vec::with_capacity(52);
+    data.extend_from_slice(&model_id.to_be_bytes::<32>());
+    data.extend_from_slice(account.as_slice());
+    evm::keccak256(&data).into()
+}
+
+fn version_key(model_id: U256, version: U256) -> FixedBytes<32> {
+    let mut data = Vec::with_capacity(64);
+    data.extend_from_slice(&model_id.to_be_bytes::<32>());
+    data.extend_from_slice(&version.to_be_bytes::<32>());
+    evm::keccak256(&data).into()
+}
+
+sol! {
+    event BatchProcessed(uint256 model_id, uint32 count, uint64 gas_used);
+
+    event RoleGranted(bytes32 role, address account, address sender);
+
+    event RoleRevoked(bytes32 role, address account, address sender);
+
+    event OwnershipTransferStarted(address previous_owner, address new_owner);
+
+    event OwnershipTransferred(address previous_owner, address new_owner);
+
+    event Paused(address account);
+
+    event Unpaused(address account);
+
+    event CircuitBreakerConfigured(
+        uint256 model_id,
+        uint256 max_error_rate_bps,
+        uint256 min_avg_confidence_bps,
+        uint256 max_avg_confidence_bps,
+        uint256 min_samples
+    );
+
+    event CircuitBreakerTripped(
+        uint256 model_id,
+        uint256 error_rate_bps,
+        uint256 avg_confidence_bps
+    );
+
+    event CircuitBreakerReset(uint256 model_id, address account);
+
+    event InferenceFailed(
+        uint256 request_id,
+        uint256 model_id,
+        // Error ABI-codificado (selector + parámetros)
+        bytes reason
+    );
+
+    event PlatformFeeSet(uint256 fee_bps, address treasury);
+
+    event ModelLicenseSet(
+        uint256 indexed model_id,
+        bytes32 terms_hash,
+        bool commercial_use,
+        bool requires_acceptance
+    );
+
+    event ModelLicenseAccepted(
+        uint256 indexed model_id,
+        address indexed account,
+        bytes32 terms_hash
+    );
+
+    event FeeTokenSet(address token, address sender);
+
+    event ModelPricingSet(
+        uint256 model_id,
+        address token,
+        uint256 base_fee,
+        uint256 fee_per_compute_unit,
+        uint256 fee_per_input_byte
+    );
+
+    event FeeCharged(
+        uint256 indexed model_id,
+        address indexed payer,
+        address token,
+        uint256 amount
+    );
+
+    event FeesWithdrawn(address indexed account, address token, uint256 amount);
+
+    event QuotaConfigured(
+        uint256 model_id,
+        uint256 window_blocks,
+        uint256 epoch_blocks,
+        uint256 caller_requests_per_window,
+        uint256 caller_compute_units_per_epoch,
+        uint256 model_requests_per_window,
+        uint256 model_compute_units_per_epoch
+    );
+
+    event QuotaExemptionSet(address account, bool exempt, address sender);
+
+    event InferenceCompleted(
+        uint256 indexed request_id,
+        uint256 indexed model_id,
+        bytes32 input_hash,
+        bytes32 output_hash,
+        // En puntos básicos
+        uint256 confidence,
+        uint8 source
+    );
+
+    event StatsUpdated(
+        uint256 indexed model_id,
+        uint256 total_requests,
+        uint256 total_gas_used,
+        uint256 success_rate
+    );
+
+    event ModelRegistered(uint256 indexed model_id, uint256 input_size, uint256 output_size);
+
+    event ModelUpdated(uint256 indexed model_id, uint256 version);
+
+    event ModelVersionRegistered(uint256 model_id, uint256 version);
+
+    event ModelVersionActivated(uint256 model_id, uint256 previous_version, uint256 version);
+
+    event ModelVersionDeactivated(uint256 model_id, uint256 version);
+
+    event ModelRolledBack(uint256 model_id, uint256 from_version, uint256 to_version);
+
+    event ModelSchemaSet(uint256 model_id, uint256 version);
+
+    event ModelPreprocessingSet(uint256 model_id, uint256 version);
+
+    event WeightUploadStarted(
+        uint256 model_id,
+        uint256 version,
+        bytes32 committed_root,
+        uint32 chunk_count
+    );
+
+    event WeightChunkUploaded(uint256 model_id, uint256 version, uint32 chunk_index, bytes32 leaf);
+
+    event ModelWeightsCommitted(uint256 model_id, uint256 version, bytes32 weights_root);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Activation, DenseLayer, ONE};
+    use crate::linear::Link;
+    use crate::preprocess::Step;
+    use crate::quant::encoder::{quantize_network, FloatLayer};
+    use crate::quant::{QuantScheme, QuantWidth};
+    use crate::tree::{Aggregation, Node, Tree};
+    use stylus_sdk::alloy_sol_types::SolEvent;
+
+    fn test_network(input_size: usize, output_size: usize) -> Bytes {
+        Network {
+            layers: vec![DenseLayer {
+                input_dim: input_size,
+                output_dim: output_size,
+                activation: Activation::Relu,
+                weights: (0..input_size * output_size)
+                    .map(|i| ((i % 7) as i32 - 3) * ONE / 8)
+                    .collect(),
+                biases: vec![ONE / 2; output_size],
+            }],
+        }
+        .encode()
+        .into()
+    }
+
+    fn upload_weights(
+        processor: &mut AIProcessor,
+        model_id: U256,
+        version: U256,
+        blob: Bytes,
+    ) -> Result<bool, AIError> {
+        let (root, chunks) = weights::commit_blob(&blob, weights::MAX_CHUNK_SIZE);
+        processor.begin_weight_upload(
+            model_id,
+            version,
+            root,
+            U256::from(blob.len()),
+            U256::from(chunks.len()),
+        )?;
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            processor.upload_weight_chunk(model_id, version, U256::from(i), chunk.into())?;
+        }
+        processor.finalize_weight_upload(model_id, version)
+    }
+
+    #[test]
+    fn test_register_model() {
+        let mut processor = AIProcessor::new();
+        let result = processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(1024),
+            U256::from(128),
+            U256::from(32),
+            U256::from(100),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_process_inference() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(1024),
+            U256::from(128),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(1024, 128)).unwrap();
+
+        let input = vec![1, 2, 3, 4];
+        let result = processor.process_inference(U256::from(1), input.into());
+        assert!(result.is_ok());
+        assert!(result.unwrap().confidence > U256::ZERO);
+    }
+
+    #[test]
+    fn test_batch_process() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(1024),
+            U256::from(128),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(1024, 128)).unwrap();
+
+        let inputs = vec![
+            vec![1, 2, 3].into(),
+            vec![4, 5, 6].into(),
+        ];
+        let result = processor.batch_process(U256::from(1), inputs);
+        assert!(result.is_ok());
+        let results = result.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|inference| inference.confidence > U256::ZERO));
+    }
+
+    #[test]
+    fn test_weights_must_match_model_shape() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+
+        assert!(upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(8, 2)).is_err());
+        assert!(upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 3)).is_err());
+        assert!(upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 2)).is_ok());
+    }
+
+    #[test]
+    fn test_chunked_upload_activates_model() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+
+        let blob = test_network(4, 2);
+        let (root, chunks) = weights::commit_blob(&blob, 16);
+        assert!(chunks.len() > 1);
+
+        processor.begin_weight_upload(
+            U256::from(1),
+            U256::from(1),
+            root,
+            U256::from(blob.len()),
+            U256::from(chunks.len()),
+        ).unwrap();
+
+        // Fuera de orden se rechaza
+        assert!(processor.upload_weight_chunk(U256::from(1), U256::from(1), U256::from(1), chunks[1].clone().into()).is_err());
+
+        for (i, chunk) in chunks.iter().enumerate() {
+            assert!(!processor.get_model_config(U256::from(1)).unwrap().is_active);
+            processor.upload_weight_chunk(U256::from(1), U256::from(1), U256::from(i), chunk.clone().into()).unwrap();
+        }
+        processor.finalize_weight_upload(U256::from(1), U256::from(1)).unwrap();
+
+        let config = processor.get_model_config(U256::from(1)).unwrap();
+        assert!(config.is_active);
+        assert_eq!(config.weights_root, root);
+    }
+
+    #[test]
+    fn test_upload_rejects_wrong_root() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+
+        let blob = test_network(4, 2);
+        let (_, chunks) = weights::commit_blob(&blob, 16);
+        let (other_root, _) = weights::commit_blob(&test_network(4, 2)[1..], 16);
+
+        processor.begin_weight_upload(
+            U256::from(1),
+            U256::from(1),
+            other_root,
+            U256::from(blob.len()),
+            U256::from(chunks.len()),
+        ).unwrap();
+        for (i, chunk) in chunks.iter().enumerate() {
+            processor.upload_weight_chunk(U256::from(1), U256::from(1), U256::from(i), chunk.clone().into()).unwrap();
+        }
+
+        let err = processor.finalize_weight_upload(U256::from(1), U256::from(1)).unwrap_err();
+        assert!(matches!(err, AIError::WeightsRootMismatch { .. }));
+        assert!(!processor.get_model_config(U256::from(1)).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_upload_size_is_bounded() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(2),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        let begin = |processor: &mut AIProcessor, total_size: U256, chunk_count: U256| {
+            processor.begin_weight_upload(
+                U256::from(2),
+                U256::from(1),
+                FixedBytes::repeat_byte(0x01),
+                total_size,
+                chunk_count,
+            )
+        };
+        let max_chunks = U256::from(weights::MAX_CHUNK_COUNT);
+        let max_size = max_chunks * U256::from(weights::MAX_CHUNK_SIZE);
+
+        // Ni desborda la multiplicación ni se trunca a u32
+        for chunk_count in [U256::MAX, U256::from(u32::MAX) + U256::from(1), max_chunks + U256::from(1)] {
+            let err = begin(&mut processor, U256::from(1), chunk_count).unwrap_err();
+            assert_eq!(err, AIError::UploadTooLarge { total_size: U256::from(1), max_size });
+        }
+        let err = begin(&mut processor, max_size + U256::from(1), max_chunks).unwrap_err();
+        assert_eq!(err, AIError::UploadTooLarge { total_size: max_size + U256::from(1), max_size });
+        assert!(matches!(begin(&mut processor, U256::from(1), U256::ZERO), Err(AIError::UploadTooLarge { .. })));
+
+        begin(&mut processor, max_size, max_chunks).unwrap();
+    }
+
+    #[test]
+    fn test_inference_requires_weights() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+
+        let err = processor.process_inference(U256::from(1), vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::ModelInactive { model_id: U256::from(1), version: U256::ZERO });
+    }
+
+    #[test]
+    fn test_errors_are_typed() {
+        let mut processor = AIProcessor::new();
+        let err = processor.process_inference(U256::from(9), vec![1].into()).unwrap_err();
+        assert_eq!(err, AIError::ModelNotFound { model_id: U256::from(9) });
+
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(1),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 2)).unwrap();
+
+        let err = processor.process_inference(U256::from(1), vec![0; 5].into()).unwrap_err();
+        assert_eq!(err, AIError::InputTooLarge { max: U256::from(4), actual: U256::from(5) });
+
+        let err = processor.batch_process(U256::from(1), vec![vec![1].into(), vec![2].into()]).unwrap_err();
+        assert_eq!(err, AIError::BatchTooLarge { max: U256::from(1), actual: U256::from(2) });
+
+        // El revert lleva el selector del error personalizado
+        let encoded = Vec::<u8>::from(err);
+        assert_eq!(&encoded[..4], &evm::keccak256(b"BatchTooLarge(uint256,uint256)")[..4]);
+    }
+
+    #[test]
+    fn test_models_produce_different_outputs() {
+        let mut processor = AIProcessor::new();
+        for model_id in [1u64, 2] {
+            processor.register_model(
+                U256::from(model_id),
+                ModelKind::NeuralNetwork,
+                U256::from(4),
+                U256::from(2),
+                U256::from(32),
+                U256::from(100),
+            ).unwrap();
+        }
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 2)).unwrap();
+        let inverted = Network {
+            layers: vec![DenseLayer {
+                input_dim: 4,
+                output_dim: 2,
+                activation: Activation::Identity,
+                weights: vec![-ONE, -ONE, -ONE, -ONE, ONE, ONE, ONE, ONE],
+                biases: vec![0, 0],
+            }],
+        };
+        upload_weights(&mut processor, U256::from(2), U256::from(1), inverted.encode().into()).unwrap();
+
+        let input: Bytes = vec![10, 20, 30, 40].into();
+        let a = processor.process_inference(U256::from(1), input.clone()).unwrap();
+        let b = processor.process_inference(U256::from(2), input).unwrap();
+        assert_ne!(a.output, b.output);
+        assert_eq!(b.predicted_class, U256::from(1));
+        assert_eq!(a.output.len(), 2 * 4);
+    }
+
+    #[test]
+    fn test_register_creates_new_version() {
+        let mut processor = AIProcessor::new();
+        let v1 = processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(&mut processor, U256::from(1), v1, test_network(4, 2)).unwrap();
+
+        // Re-registrar no sobrescribe la versión en servicio
+        let v2 = processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(16),
+            U256::from(100),
+        ).unwrap();
+        assert_eq!(v1, U256::from(1));
+        assert_eq!(v2, U256::from(2));
+        assert_eq!(processor.get_active_version(U256::from(1)).unwrap(), v1);
+        assert_eq!(processor.get_model_config(U256::from(1)).unwrap().batch_size, U256::from(32));
+
+        // Los pesos de una versión confirmada no se pueden reemplazar
+        assert!(upload_weights(&mut processor, U256::from(1), v1, test_network(4, 2)).is_err());
+    }
+
+    #[test]
+    fn test_activate_and_rollback_versions() {
+        let mut processor = AIProcessor::new();
+        for _ in 0..2 {
+            processor.register_model(
+                U256::from(1),
+                ModelKind::NeuralNetwork,
+                U256::from(4),
+                U256::from(2),
+                U256::from(32),
+                U256::from(100),
+            ).unwrap();
+        }
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 2)).unwrap();
+        upload_weights(&mut processor, U256::from(1), U256::from(2), test_network(4, 2)).unwrap();
+
+        let input: Bytes = vec![1, 2, 3, 4].into();
+        let result = processor.process_inference(U256::from(1), input.clone()).unwrap();
+        assert_eq!(result.model_version, U256::from(1));
+
+        processor.activate_model_version(U256::from(1), U256::from(2)).unwrap();
+        let result = processor.process_inference(U256::from(1), input.clone()).unwrap();
+        assert_eq!(result.model_version, U256::from(2));
+
+        assert_eq!(processor.rollback_model(U256::from(1)).unwrap(), U256::from(1));
+        let result = processor.process_inference(U256::from(1), input).unwrap();
+        assert_eq!(result.model_version, U256::from(1));
+
+        // No queda historial al que volver
+        assert!(processor.rollback_model(U256::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_deactivate_active_version() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(&mut processor, U256::from(1), U256::from(1), test_network(4, 2)).unwrap();
+
+        processor.deactivate_model_version(U256::from(1), U256::from(1)).unwrap();
+        assert_eq!(processor.get_active_version(U256::from(1)).unwrap(), U256::ZERO);
+        assert!(processor.process_inference(U256::from(1), vec![1].into()).is_err());
+        assert!(processor.activate_model_version(U256::from(1), U256::from(1)).is_err());
+    }
+
+    fn test_ensemble() -> Bytes {
+        // Un único árbol: feature 0 <= 0.5 -> clase 0, si no -> clase 1
+        TreeEnsemble {
+            num_features: 4,
+            num_outputs: 2,
+            aggregation: Aggregation::Sum,
+            base_scores: vec![0, 0],
+            trees: vec![Tree {
+                nodes: vec![
+                    Node::Split {
+                        feature: 0,
+                        threshold: ONE / 2,
+                        left: 1,
+                        right: 2,
+                    },
+                    Node::Leaf { values: vec![ONE, 0] },
+                    Node::Leaf { values: vec![0, ONE] },
+                ],
+            }],
+        }
+        .encode()
+        .into()
     }
 
     #[test]
-    fn test_process_inference() {
+    fn test_tree_ensemble_inference() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(7),
+            ModelKind::TreeEnsemble,
+            U256::from(4),
+            U256::from(2),
+            U256::from(8),
+            U256::from(100),
+        ).unwrap();
+
+        // Un blob de red no es válido para un modelo de árboles
+        assert!(upload_weights(&mut processor, U256::from(7), U256::from(1), test_network(4, 2)).is_err());
+        upload_weights(&mut processor, U256::from(7), U256::from(1), test_ensemble()).unwrap();
+
+        let low = processor.process_inference(U256::from(7), vec![10, 0, 0, 0].into()).unwrap();
+        assert_eq!(low.predicted_class, U256::ZERO);
+
+        let results = processor.batch_process(
+            U256::from(7),
+            vec![vec![10].into(), vec![250].into()],
+        ).unwrap();
+        assert_eq!(results[0].predicted_class, U256::ZERO);
+        assert_eq!(results[1].predicted_class, U256::from(1));
+    }
+
+    #[test]
+    fn test_logistic_model_confidence() {
         let mut processor = AIProcessor::new();
         processor.register_model(
+            U256::from(3),
+            ModelKind::LinearModel,
+            U256::from(2),
             U256::from(1),
-            U256::from(1024),
-            U256::from(128),
+            U256::from(64),
+            U256::from(150),
+        ).unwrap();
+
+        let model = LinearModel {
+            num_features: 2,
+            num_outputs: 1,
+            link: Link::Logistic,
+            weights: vec![8 * ONE, 0],
+            intercepts: vec![-2 * ONE],
+            calibration_scale: ONE,
+            calibration_offset: 0,
+        };
+        upload_weights(&mut processor, U256::from(3), U256::from(1), model.encode().into()).unwrap();
+
+        // z = 8 * (255/256) - 2 ≈ 5.97 -> p ≈ 0.9975
+        let suspicious = processor.process_inference(U256::from(3), vec![255, 0].into()).unwrap();
+        assert_eq!(suspicious.predicted_class, U256::from(1));
+        assert!(suspicious.confidence >= U256::from(75));
+        assert!(suspicious.confidence_bps >= U256::from(9900));
+
+        // z = -2 -> p ≈ 0.1192
+        let normal = processor.process_inference(U256::from(3), vec![0, 0].into()).unwrap();
+        assert_eq!(normal.predicted_class, U256::ZERO);
+        assert!(normal.confidence < U256::from(75));
+        assert_eq!(normal.confidence, normal.confidence_bps / U256::from(100));
+    }
+
+    #[test]
+    fn test_quantized_network_inference() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(4),
+            ModelKind::QuantizedNetwork,
+            U256::from(4),
+            U256::from(2),
             U256::from(32),
             U256::from(100),
         ).unwrap();
 
-        let input = vec![1, 2, 3, 4];
-        let result = processor.process_inference(U256::from(1), input.into());
-        assert!(result.is_ok());
+        let layer = FloatLayer {
+            input_dim: 4,
+            output_dim: 2,
+            activation: Activation::Identity,
+            weights: vec![1.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0],
+            biases: vec![0.0, 0.0],
+        };
+        let model = quantize_network(&[layer], &[(0.0, 1.0)], QuantWidth::Int8, QuantScheme::Symmetric);
+        let blob: Bytes = model.encode().into();
+
+        // Un byte por peso en lugar de cuatro
+        assert!(blob.len() < test_network(4, 2).len());
+        upload_weights(&mut processor, U256::from(4), U256::from(1), blob).unwrap();
+
+        let left = processor.process_inference(U256::from(4), vec![200, 200, 0, 0].into()).unwrap();
+        let right = processor.process_inference(U256::from(4), vec![0, 0, 200, 200].into()).unwrap();
+        assert_eq!(left.predicted_class, U256::ZERO);
+        assert_eq!(right.predicted_class, U256::from(1));
+    }
+
+    fn abi_words(words: &[U256]) -> Bytes {
+        words.iter().flat_map(|w| w.to_be_bytes::<32>()).collect::<Vec<u8>>().into()
+    }
+
+    fn register_schema_model(processor: &mut AIProcessor) {
+        processor.register_model(
+            U256::from(5),
+            ModelKind::LinearModel,
+            U256::from(2),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        // Entrada abi.encode(address, uint256); salida abi.encode(uint256[])
+        processor.set_model_schema(
+            U256::from(5),
+            U256::from(1),
+            vec![0x03, 0x01].into(),
+            vec![0x11].into(),
+        ).unwrap();
+
+        let model = LinearModel {
+            num_features: 2,
+            num_outputs: 2,
+            link: Link::Identity,
+            weights: vec![0, ONE, 0, -ONE],
+            intercepts: vec![0, 0],
+            calibration_scale: ONE,
+            calibration_offset: 0,
+        };
+        upload_weights(processor, U256::from(5), U256::from(1), model.encode().into()).unwrap();
     }
 
     #[test]
-    fn test_batch_process() {
+    fn test_schema_validates_and_encodes_output() {
+        let mut processor = AIProcessor::new();
+        register_schema_model(&mut processor);
+
+        let input = abi_words(&[U256::from(0xbeef), U256::from(3)]);
+        let result = processor.process_inference(U256::from(5), input).unwrap();
+
+        // Puntuaciones [3.0, -3.0] -> uint256[] con la negativa saturada a 0
+        assert_eq!(
+            result.output,
+            abi_words(&[U256::from(32), U256::from(2), U256::from(3 * ONE), U256::ZERO])
+        );
+    }
+
+    #[test]
+    fn test_schema_rejects_malformed_input() {
+        let mut processor = AIProcessor::new();
+        register_schema_model(&mut processor);
+
+        // Solo una palabra para una tupla de dos
+        let short = abi_words(&[U256::from(1)]);
+        let err = processor.process_inference(U256::from(5), short).unwrap_err();
+        assert_eq!(err, AIError::InputTooShort { expected: U256::from(64), actual: U256::from(32) });
+
+        // Dirección con bits altos sucios
+        let dirty = abi_words(&[U256::MAX, U256::from(1)]);
+        assert!(processor.process_inference(U256::from(5), dirty).is_err());
+    }
+
+    #[test]
+    fn test_schema_fixed_after_commit() {
         let mut processor = AIProcessor::new();
+        register_schema_model(&mut processor);
+
+        let result = processor.set_model_schema(
+            U256::from(5),
+            U256::from(1),
+            vec![0x01].into(),
+            Bytes::new(),
+        );
+        assert!(result.is_err());
+    }
+
+    fn register_preprocessed_model(processor: &mut AIProcessor) {
         processor.register_model(
+            U256::from(6),
+            ModelKind::LinearModel,
+            U256::from(6),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        // Entrada abi.encode(address, uint256 timestamp)
+        processor.set_model_schema(
+            U256::from(6),
             U256::from(1),
-            U256::from(1024),
-            U256::from(128),
+            vec![0x03, 0x01].into(),
+            Bytes::new(),
+        ).unwrap();
+        let pipeline = Pipeline {
+            steps: vec![
+                Step::HashBucket { column: 0, buckets: 4 },
+                Step::Bucketize { column: 1, boundaries: vec![1_000] },
+            ],
+        };
+        processor.set_model_preprocessing(
+            U256::from(6),
+            U256::from(1),
+            pipeline.encode().into(),
+        ).unwrap();
+
+        // Clase 0 para timestamps antiguos, clase 1 para recientes
+        let mut weights = vec![0; 12];
+        weights[4] = ONE;
+        weights[6 + 5] = ONE;
+        let model = LinearModel {
+            num_features: 6,
+            num_outputs: 2,
+            link: Link::Identity,
+            weights,
+            intercepts: vec![0, 0],
+            calibration_scale: ONE,
+            calibration_offset: 0,
+        };
+        upload_weights(processor, U256::from(6), U256::from(1), model.encode().into()).unwrap();
+    }
+
+    #[test]
+    fn test_preprocessing_runs_before_model() {
+        let mut processor = AIProcessor::new();
+        register_preprocessed_model(&mut processor);
+
+        let early = abi_words(&[U256::from(0xbeef), U256::from(500)]);
+        let result = processor.process_inference(U256::from(6), early).unwrap();
+        assert_eq!(result.predicted_class, U256::ZERO);
+
+        let late = abi_words(&[U256::from(0xbeef), U256::from(5_000)]);
+        let result = processor.process_inference(U256::from(6), late).unwrap();
+        assert_eq!(result.predicted_class, U256::from(1));
+    }
+
+    #[test]
+    fn test_preprocessing_validation() {
+        let mut processor = AIProcessor::new();
+        processor.register_model(
+            U256::from(7),
+            ModelKind::NeuralNetwork,
+            U256::from(3),
+            U256::from(2),
             U256::from(32),
             U256::from(100),
         ).unwrap();
 
-        let inputs = vec![
-            vec![1, 2, 3].into(),
-            vec![4, 5, 6].into(),
-        ];
-        let result = processor.batch_process(U256::from(1), inputs);
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().len(), 2);
+        // Produce 4 features para un modelo de 3
+        let too_wide = Pipeline {
+            steps: vec![Step::OneHot { column: 0, categories: 4 }],
+        };
+        assert!(processor.set_model_preprocessing(
+            U256::from(7),
+            U256::from(1),
+            too_wide.encode().into(),
+        ).is_err());
+        assert!(processor.set_model_preprocessing(
+            U256::from(7),
+            U256::from(1),
+            vec![0x00].into(),
+        ).is_err());
+
+        let mut processor = AIProcessor::new();
+        register_preprocessed_model(&mut processor);
+        assert!(processor.set_model_preprocessing(
+            U256::from(6),
+            U256::from(1),
+            Bytes::new(),
+        ).is_err());
+    }
+
+    #[test]
+    fn test_role_management() {
+        let mut processor = AIProcessor::new();
+        let deployer = msg::sender();
+        let operator = Address::repeat_byte(0x22);
+
+        for role in [ADMIN_ROLE, MODEL_MANAGER_ROLE, OPERATOR_ROLE, PAUSER_ROLE] {
+            assert!(processor.has_role(role, deployer));
+        }
+
+        assert!(processor.grant_role(OPERATOR_ROLE, operator).unwrap());
+        assert!(processor.has_role(OPERATOR_ROLE, operator));
+        // Conceder dos veces no cambia nada
+        assert!(!processor.grant_role(OPERATOR_ROLE, operator).unwrap());
+
+        assert!(processor.revoke_role(OPERATOR_ROLE, operator).unwrap());
+        assert!(!processor.has_role(OPERATOR_ROLE, operator));
+
+        // El owner no puede perder ADMIN_ROLE
+        let err = processor.revoke_role(ADMIN_ROLE, deployer).unwrap_err();
+        assert_eq!(err, AIError::OwnerAdminRole { account: deployer });
+        assert!(processor.renounce_role(ADMIN_ROLE).is_err());
+
+        // Sin MODEL_MANAGER_ROLE no se pueden registrar modelos
+        assert!(processor.renounce_role(MODEL_MANAGER_ROLE).unwrap());
+        let err = processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap_err();
+        assert_eq!(err, AIError::MissingRole { role: MODEL_MANAGER_ROLE, account: deployer });
+    }
+
+    #[test]
+    fn test_ownership_transfer_is_two_step() {
+        let mut processor = AIProcessor::new();
+        let deployer = msg::sender();
+        let new_owner = Address::repeat_byte(0x33);
+
+        processor.transfer_ownership(new_owner).unwrap();
+        assert_eq!(processor.pending_owner(), new_owner);
+        // Hasta que el nuevo owner acepte, nada cambia
+        assert_eq!(processor.owner(), deployer);
+        assert!(processor.has_role(ADMIN_ROLE, deployer));
+
+        let err = processor.accept_ownership().unwrap_err();
+        assert_eq!(err, AIError::NotPendingOwner { caller: deployer });
+
+        processor.transfer_ownership(Address::ZERO).unwrap();
+        assert_eq!(processor.pending_owner(), Address::ZERO);
+        assert!(processor.accept_ownership().is_err());
+    }
+
+    #[test]
+    fn test_accepting_ownership_moves_every_role() {
+        let mut processor = AIProcessor::new();
+        let deployer = msg::sender();
+        let previous = Address::repeat_byte(0x44);
+
+        // Simula una clave anterior que tiene todos los roles de operación y
+        // una transferencia hacia el llamador del test
+        for role in [ADMIN_ROLE, MODEL_MANAGER_ROLE, OPERATOR_ROLE, PAUSER_ROLE] {
+            processor.set_role(role, deployer, false);
+            processor.set_role(role, previous, true);
+        }
+        processor.owner = previous;
+        processor.pending_owner = deployer;
+
+        processor.accept_ownership().unwrap();
+        assert_eq!(processor.owner(), deployer);
+        for role in [ADMIN_ROLE, MODEL_MANAGER_ROLE, OPERATOR_ROLE, PAUSER_ROLE] {
+            assert!(!processor.has_role(role, previous));
+            assert!(processor.has_role(role, deployer));
+        }
+        // Solo se mueve lo que tenía el owner anterior
+        assert!(!processor.has_role(PUBLISHER_ROLE, deployer));
+        assert!(processor.pause().is_ok());
+    }
+
+    fn register_network_model(processor: &mut AIProcessor) {
+        processor.register_model(
+            U256::from(1),
+            ModelKind::NeuralNetwork,
+            U256::from(4),
+            U256::from(2),
+            U256::from(32),
+            U256::from(100),
+        ).unwrap();
+        upload_weights(processor, U256::from(1), U256::from(1), test_network(4, 2)).unwrap();
+    }
+
+    #[test]
+    fn test_pause_blocks_inference() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+
+        processor.pause().unwrap();
+        assert!(processor.is_paused());
+        assert_eq!(processor.pause().unwrap_err(), AIError::Paused);
+
+        let err = processor.process_inference(U256::from(1), vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::Paused);
+        let err = processor.batch_process(U256::from(1), vec![vec![1].into()]).unwrap_err();
+        assert_eq!(err, AIError::Paused);
+
+        processor.unpause().unwrap();
+        assert_eq!(processor.unpause().unwrap_err(), AIError::NotPaused);
+        assert!(processor.process_inference(U256::from(1), vec![1, 2].into()).unwrap().success);
+    }
+
+    #[test]
+    fn test_circuit_breaker_trips_and_resets() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+
+        assert_eq!(
+            processor.set_circuit_breaker(U256::from(1), U256::ZERO, U256::from(9_000), U256::from(8_000), U256::from(1))
+                .unwrap_err(),
+            AIError::InvalidBreakerConfig
+        );
+
+        // Ninguna red de prueba alcanza una confianza media del 100%
+        processor.set_circuit_breaker(
+            U256::from(1),
+            U256::from(1_000),
+            U256::from(10_000),
+            U256::from(10_000),
+            U256::from(2),
+        ).unwrap();
+
+        // La primera muestra no basta para evaluar; la segunda dispara el breaker
+        // pero se sirve igualmente
+        processor.process_inference(U256::from(1), vec![1, 2].into()).unwrap();
+        processor.process_inference(U256::from(1), vec![3, 4].into()).unwrap();
+        assert!(processor.get_circuit_breaker(U256::from(1)).unwrap().tripped);
+
+        let err = processor.process_inference(U256::from(1), vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::CircuitBreakerOpen { model_id: U256::from(1) });
+
+        processor.reset_circuit_breaker(U256::from(1)).unwrap();
+        let breaker = processor.get_circuit_breaker(U256::from(1)).unwrap();
+        assert!(!breaker.tripped);
+        assert_eq!(breaker.window_requests, U256::ZERO);
+        assert_eq!(breaker.previous_requests, U256::ZERO);
+        assert!(processor.process_inference(U256::from(1), vec![1, 2].into()).is_ok());
+
+        let stats = processor.get_processing_stats(U256::from(1)).unwrap();
+        assert_eq!(stats.failed_requests, U256::ZERO);
+        assert_eq!(stats.success_rate, U256::from(100));
+        assert!(stats.avg_confidence_bps > U256::ZERO);
+    }
+
+    #[test]
+    fn test_circuit_breaker_window_forgets_old_samples() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        processor.set_circuit_breaker(
+            U256::from(1),
+            U256::from(5_000),
+            U256::ZERO,
+            U256::from(10_000),
+            U256::from(4),
+        ).unwrap();
+
+        let sample = |success: bool| InferenceResult {
+            request_id: U256::ZERO,
+            model_id: U256::from(1),
+            model_version: U256::from(1),
+            input_hash: Bytes::new(),
+            output: Bytes::new(),
+            predicted_class: U256::ZERO,
+            confidence: U256::from(if success { 90 } else { 0 }),
+            confidence_bps: U256::from(if success { 9_000 } else { 0 }),
+            timestamp: U256::ZERO,
+            gas_used: U256::ZERO,
+            success,
+        };
+
+        // Con una ventana acumulada, 100 aciertos ocultarían cualquier racha
+        for _ in 0..100 {
+            processor.update_circuit_breaker(U256::from(1), &sample(true));
+        }
+        for _ in 0..4 {
+            processor.update_circuit_breaker(U256::from(1), &sample(false));
+        }
+        let breaker = processor.get_circuit_breaker(U256::from(1)).unwrap();
+        assert!(!breaker.tripped);
+        assert_eq!(breaker.previous_requests + breaker.window_requests, U256::from(8));
+
+        // El quinto fallo saca los últimos aciertos de la ventana
+        processor.update_circuit_breaker(U256::from(1), &sample(false));
+        let breaker = processor.get_circuit_breaker(U256::from(1)).unwrap();
+        assert!(breaker.tripped);
+        assert_eq!(breaker.previous_failures, U256::from(4));
+    }
+
+    #[test]
+    fn test_stats_track_gas_and_input_sizes() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+
+        let inputs = vec![vec![1, 2].into(), vec![1, 2, 3, 4].into()];
+        processor.batch_process(U256::from(1), inputs).unwrap();
+
+        let stats = processor.get_processing_stats(U256::from(1)).unwrap();
+        assert_eq!(stats.total_requests, U256::from(2));
+        assert_eq!(stats.successful_requests, U256::from(2));
+        assert_eq!(stats.on_chain_requests, U256::from(2));
+        assert_eq!(stats.cached_requests, U256::ZERO);
+        assert!(stats.min_gas_used > U256::ZERO);
+        assert!(stats.min_gas_used <= stats.ema_gas_used);
+        assert!(stats.ema_gas_used <= stats.max_gas_used);
+        assert_eq!(stats.input_size_histogram[0], U256::from(2));
+    }
+
+    #[test]
+    fn test_quotas_limit_callers() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        let caller = msg::sender();
+        let model_id = U256::from(1);
+
+        let quota = QuotaConfig {
+            window_blocks: U256::from(1_000),
+            epoch_blocks: U256::ZERO,
+            caller_requests_per_window: U256::from(2),
+            caller_compute_units_per_epoch: U256::ZERO,
+            model_requests_per_window: U256::from(10),
+            model_compute_units_per_epoch: U256::ZERO,
+        };
+        processor.set_quota(model_id, quota.clone()).unwrap();
+        assert_eq!(processor.get_remaining_quota(model_id, caller).requests, U256::from(2));
+
+        processor.process_inference(model_id, vec![1, 2].into()).unwrap();
+        processor.process_inference(model_id, vec![1, 2].into()).unwrap();
+        let remaining = processor.get_remaining_quota(model_id, caller);
+        assert_eq!(remaining.requests, U256::ZERO);
+        assert_eq!(remaining.compute_units, U256::MAX);
+
+        let err = processor.process_inference(model_id, vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::RequestQuotaExceeded {
+            model_id,
+            account: caller,
+            limit: U256::from(2),
+        });
+
+        // Los contratos exentos no consumen cuota
+        processor.set_quota_exempt(caller, true).unwrap();
+        assert!(processor.process_inference(model_id, vec![1, 2].into()).is_ok());
+        assert_eq!(processor.get_remaining_quota(model_id, caller).requests, U256::MAX);
+
+        // Un límite de unidades sin época no es válido
+        let invalid = QuotaConfig {
+            caller_compute_units_per_epoch: U256::from(500),
+            ..quota
+        };
+        assert!(matches!(
+            processor.set_quota(model_id, invalid),
+            Err(AIError::InvalidQuotaConfig { .. })
+        ));
+    }
+
+    #[test]
+    fn test_model_pricing() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        let model_id = U256::from(1);
+        let edu = Address::repeat_byte(0xed);
+
+        assert_eq!(processor.get_model_owner(model_id).unwrap(), msg::sender());
+        assert_eq!(processor.quote_fee(model_id, U256::from(4)).unwrap(), U256::ZERO);
+
+        let pricing = PricingConfig {
+            token: edu,
+            base_fee: U256::from(1_000),
+            fee_per_compute_unit: U256::from(2),
+            fee_per_input_byte: U256::from(5),
+        };
+        // Solo se acepta el token configurado
+        let err = processor.set_model_pricing(model_id, pricing.clone()).unwrap_err();
+        assert_eq!(err, AIError::UnsupportedFeeToken { token: edu });
+
+        processor.set_fee_token(edu).unwrap();
+        processor.set_model_pricing(model_id, pricing.clone()).unwrap();
+        let config = processor.get_model_config(model_id).unwrap();
+        assert_eq!(
+            processor.quote_fee(model_id, U256::from(4)).unwrap(),
+            U256::from(1_000 + 20) + config.compute_units * U256::from(2)
+        );
+
+        let err = processor.withdraw_fees(edu).unwrap_err();
+        assert_eq!(err, AIError::NothingToWithdraw { account: msg::sender(), token: edu });
+        assert_eq!(processor.accrued_fees(msg::sender(), edu), U256::ZERO);
+
+        // Una inferencia correcta en EDU: la plataforma se queda el 10%
+        let owner = msg::sender();
+        let treasury = Address::repeat_byte(0x7e);
+        processor.set_platform_fee(U256::from(1_000), treasury).unwrap();
+        let fee = processor.quote_fee(model_id, U256::from(2)).unwrap();
+        assert!(processor.process_inference(model_id, vec![1, 2].into()).unwrap().success);
+        let (platform_share, owner_share) = marketplace::split_fee(fee, 1_000);
+        assert_eq!(platform_share, fee / U256::from(10));
+        assert_eq!(platform_share + owner_share, fee);
+        assert_eq!(processor.accrued_fees(treasury, edu), platform_share);
+        assert_eq!(processor.accrued_fees(owner, edu), owner_share);
+
+        // Retirada en ERC-20
+        assert_eq!(processor.withdraw_fees(edu).unwrap(), owner_share);
+        assert_eq!(processor.accrued_fees(owner, edu), U256::ZERO);
+        assert!(matches!(processor.withdraw_fees(edu), Err(AIError::NothingToWithdraw { .. })));
+
+        // De un lote con un fallo solo se cobra la entrada correcta; la otra
+        // vuelve al pagador, que aquí es también el dueño
+        processor.settle_fees(&config, &pricing, [(2, false), (2, true)]);
+        assert_eq!(processor.accrued_fees(treasury, edu), platform_share * U256::from(2));
+        assert_eq!(processor.accrued_fees(owner, edu), fee + owner_share);
+        processor.settle_fees(&config, &pricing, [(2, false)]);
+        assert_eq!(processor.accrued_fees(treasury, edu), platform_share * U256::from(2));
+
+        // En ETH msg.value tiene que cubrir la tarifa exacta
+        let native = PricingConfig { token: NATIVE_TOKEN, ..pricing };
+        processor.set_model_pricing(model_id, native).unwrap();
+        let err = processor.process_inference(model_id, vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::IncorrectPayment { expected: fee, actual: U256::ZERO });
+        let err = processor.batch_process(model_id, vec![vec![1, 2].into(), vec![3, 4].into()]).unwrap_err();
+        assert_eq!(err, AIError::IncorrectPayment { expected: fee * U256::from(2), actual: U256::ZERO });
+        assert_eq!(processor.accrued_fees(owner, NATIVE_TOKEN), U256::ZERO);
+
+        // Retirada en ETH de lo acumulado
+        processor.accrued_fees.insert(fee_key(owner, NATIVE_TOKEN), U256::from(500));
+        assert_eq!(processor.withdraw_fees(NATIVE_TOKEN).unwrap(), U256::from(500));
+        assert_eq!(processor.accrued_fees(owner, NATIVE_TOKEN), U256::ZERO);
+    }
+
+    // Los topics tienen que coincidir con los `indexed` de IAIProcessor.sol
+    #[test]
+    fn test_events_index_interface_topics() {
+        let word = |value: U256| FixedBytes::<32>::from(value.to_be_bytes::<32>());
+        let completed = InferenceCompleted {
+            request_id: U256::from(7),
+            model_id: U256::from(1),
+            input_hash: [0x11; 32],
+            output_hash: [0x22; 32],
+            confidence: U256::from(9_000),
+            source: SOURCE_ON_CHAIN,
+        };
+        let topics = completed.encode_topics();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(
+            topics[0].0,
+            evm::keccak256(b"InferenceCompleted(uint256,uint256,bytes32,bytes32,uint256,uint8)")
+        );
+        assert_eq!(topics[1].0, word(U256::from(7)));
+        assert_eq!(topics[2].0, word(U256::from(1)));
+
+        let registered = ModelRegistered {
+            model_id: U256::from(3),
+            input_size: U256::from(4),
+            output_size: U256::from(2),
+        };
+        let topics = registered.encode_topics();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].0, evm::keccak256(b"ModelRegistered(uint256,uint256,uint256)"));
+        assert_eq!(topics[1].0, word(U256::from(3)));
+
+        let updated = ModelUpdated { model_id: U256::from(3), version: U256::from(2) };
+        assert_eq!(updated.encode_topics()[1].0, word(U256::from(3)));
+        let stats = StatsUpdated {
+            model_id: U256::from(3),
+            total_requests: U256::from(1),
+            total_gas_used: U256::from(100),
+            success_rate: U256::from(100),
+        };
+        assert_eq!(stats.encode_topics().len(), 2);
+
+        let charged = FeeCharged {
+            model_id: U256::from(3),
+            payer: Address::repeat_byte(0xaa),
+            token: Address::ZERO,
+            amount: U256::from(5),
+        };
+        let topics = charged.encode_topics();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(topics[2].0, Address::repeat_byte(0xaa).into_word());
+    }
+
+    #[test]
+    fn test_publishers_own_their_namespace() {
+        let mut processor = AIProcessor::new();
+        let publisher = msg::sender();
+        let model_id = processor.publisher_model_id(publisher, U256::from(1)).unwrap();
+        let register = |processor: &mut AIProcessor, model_id: U256| {
+            processor.register_model(
+                model_id,
+                ModelKind::NeuralNetwork,
+                U256::from(4),
+                U256::from(2),
+                U256::from(32),
+                U256::from(100),
+            )
+        };
+
+        // Sin PUBLISHER_ROLE no se puede publicar ni en el propio espacio
+        let err = register(&mut processor, model_id).unwrap_err();
+        assert_eq!(err, AIError::NotModelPublisher { model_id, account: publisher });
+
+        processor.grant_role(PUBLISHER_ROLE, publisher).unwrap();
+        register(&mut processor, model_id).unwrap();
+        upload_weights(&mut processor, model_id, U256::from(1), test_network(4, 2)).unwrap();
+        assert_eq!(processor.get_model_owner(model_id).unwrap(), publisher);
+
+        // Ni en el de otro editor
+        let foreign = processor.publisher_model_id(Address::repeat_byte(0x33), U256::from(1)).unwrap();
+        let err = register(&mut processor, foreign).unwrap_err();
+        assert_eq!(err, AIError::NotModelPublisher { model_id: foreign, account: publisher });
+
+        // El breaker y las cuotas de un modelo publicado los gestiona su
+        // editor, aunque no tenga MODEL_MANAGER_ROLE
+        processor.revoke_role(MODEL_MANAGER_ROLE, publisher).unwrap();
+        let bps = U256::from(engine::BPS);
+        processor.set_circuit_breaker(model_id, bps, U256::ZERO, bps, U256::from(10)).unwrap();
+        let quota = QuotaConfig {
+            window_blocks: U256::from(1_000),
+            epoch_blocks: U256::ZERO,
+            caller_requests_per_window: U256::from(2),
+            caller_compute_units_per_epoch: U256::ZERO,
+            model_requests_per_window: U256::from(10),
+            model_compute_units_per_epoch: U256::ZERO,
+        };
+        processor.set_quota(model_id, quota.clone()).unwrap();
+
+        let err = processor.set_circuit_breaker(foreign, bps, U256::ZERO, bps, U256::from(10)).unwrap_err();
+        assert_eq!(err, AIError::NotModelPublisher { model_id: foreign, account: publisher });
+        let err = processor.set_quota(foreign, quota.clone()).unwrap_err();
+        assert_eq!(err, AIError::NotModelPublisher { model_id: foreign, account: publisher });
+        // Ni los de la plataforma
+        let err = processor.set_quota(U256::from(1), quota).unwrap_err();
+        assert_eq!(err, AIError::MissingRole { role: MODEL_MANAGER_ROLE, account: publisher });
+
+        let err = processor.set_platform_fee(U256::from(6_000), publisher).unwrap_err();
+        assert_eq!(err, AIError::InvalidPlatformFee {
+            fee_bps: U256::from(6_000),
+            max: U256::from(marketplace::MAX_PLATFORM_FEE_BPS),
+        });
+        processor.set_platform_fee(U256::from(1_000), publisher).unwrap();
+        assert_eq!(processor.platform_fee(), (U256::from(1_000), publisher));
+    }
+
+    #[test]
+    fn test_license_acceptance_gates_inference() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        let model_id = U256::from(1);
+        let caller = msg::sender();
+        let terms_hash = FixedBytes::repeat_byte(0x11);
+
+        processor.set_model_license(model_id, License {
+            terms_uri: b"ipfs://terms-v1".to_vec().into(),
+            terms_hash,
+            commercial_use: false,
+            requires_acceptance: true,
+        }).unwrap();
+
+        let err = processor.process_inference(model_id, vec![1, 2].into()).unwrap_err();
+        assert_eq!(err, AIError::LicenseNotAccepted { model_id, account: caller });
+
+        let stale = FixedBytes::repeat_byte(0x22);
+        let err = processor.accept_model_license(model_id, stale).unwrap_err();
+        assert_eq!(err, AIError::LicenseTermsMismatch { model_id, expected: terms_hash, actual: stale });
+
+        processor.accept_model_license(model_id, terms_hash).unwrap();
+        assert!(processor.has_accepted_license(model_id, caller));
+        assert!(processor.process_inference(model_id, vec![1, 2].into()).is_ok());
+
+        // Nuevos términos exigen aceptar de nuevo
+        processor.set_model_license(model_id, License {
+            terms_uri: b"ipfs://terms-v2".to_vec().into(),
+            terms_hash: stale,
+            commercial_use: true,
+            requires_acceptance: true,
+        }).unwrap();
+        assert!(!processor.has_accepted_license(model_id, caller));
+    }
+
+    #[test]
+    fn test_batch_items_get_distinct_request_ids() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+
+        let inputs = vec![vec![1, 2].into(), vec![1, 2].into(), vec![1, 2].into()];
+        let results = processor.batch_process(U256::from(1), inputs).unwrap();
+
+        let ids: Vec<U256> = results.iter().map(|r| r.request_id).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        for id in &ids {
+            assert!(processor.inference_results.get(id).is_some());
+            let origin = processor.get_request_origin(*id).unwrap();
+            assert_eq!(origin.sender, msg::sender());
+            assert_eq!(origin.model_id, U256::from(1));
+        }
+
+        let err = processor.get_request_origin(U256::from(12345)).unwrap_err();
+        assert_eq!(err, AIError::RequestNotFound { request_id: U256::from(12345) });
+    }
+
+    #[test]
+    fn test_result_queries_paginate() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        let inputs = (0..5u8).map(|i| vec![i].into()).collect();
+        let ids: Vec<U256> = processor.batch_process(U256::from(1), inputs).unwrap()
+            .iter()
+            .map(|r| r.request_id)
+            .collect();
+
+        assert_eq!(processor.get_inference_result(ids[3]).unwrap().request_id, ids[3]);
+        assert_eq!(processor.count_results(), U256::from(5));
+        assert_eq!(processor.count_results_by_model(U256::from(1)), U256::from(5));
+        assert_eq!(processor.count_results_by_model(U256::from(2)), U256::ZERO);
+        assert_eq!(processor.count_results_by_requester(msg::sender()), U256::from(5));
+
+        let mut cursor = U256::ZERO;
+        let mut seen = Vec::new();
+        loop {
+            let page = processor.get_results_by_model(U256::from(1), cursor, U256::from(2)).unwrap();
+            assert!(page.results.len() <= 2);
+            seen.extend(page.results.iter().map(|r| r.request_id));
+            cursor = page.next_cursor;
+            if !page.has_more {
+                break;
+            }
+        }
+        assert_eq!(seen, ids);
+
+        let page = processor.get_results_by_requester(msg::sender(), U256::from(4), U256::from(10)).unwrap();
+        assert_eq!(page.results.len(), 1);
+        assert!(!page.has_more);
+
+        assert!(processor.get_results_by_model(U256::from(1), U256::ZERO, U256::ZERO).is_err());
+        assert!(processor.get_results_by_model(U256::from(1), U256::ZERO, U256::from(101)).is_err());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_results_by_time_range() {
+        let mut processor = AIProcessor::new();
+        register_network_model(&mut processor);
+        let inputs = (0..3u8).map(|i| vec![i].into()).collect();
+        processor.batch_process(U256::from(1), inputs).unwrap();
+        let now = U256::from(evm::block_timestamp());
+
+        let page = processor.get_results_by_time_range(now, now, U256::ZERO, U256::from(2)).unwrap();
+        assert_eq!(page.results.len(), 2);
+        assert!(page.has_more);
+        let page = processor.get_results_by_time_range(now, now, page.next_cursor, U256::from(2)).unwrap();
+        assert_eq!(page.results.len(), 1);
+        assert!(!page.has_more);
+
+        let later = now + U256::from(1);
+        let page = processor.get_results_by_time_range(later, later + U256::from(10), U256::ZERO, U256::from(10)).unwrap();
+        assert!(page.results.is_empty());
+
+        let err = processor.get_results_by_time_range(later, now, U256::ZERO, U256::from(10)).unwrap_err();
+        assert_eq!(err, AIError::InvalidTimeRange { from_time: later, to_time: now });
+    }
+}
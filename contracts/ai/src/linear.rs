@@ -0,0 +1,218 @@
+// Modelos lineales generalizados (regresión lineal y logística) con
+// confianza calibrada en puntos básicos.
+
+use crate::engine::{argmax, sigmoid, to_bps, Prediction, Reader, FRAC_BITS, ONE};
+
+const LINEAR_MAGIC: &[u8; 4] = b"BSLM";
+const LINEAR_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Link {
+    Identity,
+    Logistic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearModel {
+    pub num_features: usize,
+    pub num_outputs: usize,
+    pub link: Link,
+    // z = W·x + b; weights[o * num_features + i]
+    pub weights: Vec<i32>,
+    pub intercepts: Vec<i32>,
+    // Escalado de Platt: p = sigmoid(scale * z + offset)
+    pub calibration_scale: i32,
+    pub calibration_offset: i32,
+}
+
+impl LinearModel {
+    // Formato: "BSLM" | version u8 | link u8 | num_features u16 |
+    // num_outputs u16 | calibration_scale i32 | calibration_offset i32 |
+    // weights i32[num_outputs * num_features] | intercepts i32[num_outputs]
+    pub fn decode(data: &[u8]) -> Result<Self, Vec<u8>> {
+        let mut reader = Reader::new(data);
+        if reader.take(4)? != LINEAR_MAGIC {
+            return Err("Invalid linear model magic".into());
+        }
+        if reader.u8()? != LINEAR_VERSION {
+            return Err("Unsupported linear model version".into());
+        }
+
+        let link = match reader.u8()? {
+            0 => Link::Identity,
+            1 => Link::Logistic,
+            _ => return Err("Unknown link function".into()),
+        };
+        let num_features = reader.u16()? as usize;
+        let num_outputs = reader.u16()? as usize;
+        if num_features == 0 || num_outputs == 0 {
+            return Err("Empty linear model".into());
+        }
+
+        let calibration_scale = reader.i32()?;
+        let calibration_offset = reader.i32()?;
+        if calibration_scale <= 0 {
+            return Err("Calibration scale must be positive".into());
+        }
+
+        let weights = reader.i32_vec(num_features * num_outputs)?;
+        let intercepts = reader.i32_vec(num_outputs)?;
+
+        if !reader.is_empty() {
+            return Err("Trailing linear model data".into());
+        }
+        Ok(Self {
+            num_features,
+            num_outputs,
+            link,
+            weights,
+            intercepts,
+            calibration_scale,
+            calibration_offset,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(LINEAR_MAGIC);
+        out.push(LINEAR_VERSION);
+        out.push(match self.link {
+            Link::Identity => 0,
+            Link::Logistic => 1,
+        });
+        out.extend_from_slice(&(self.num_features as u16).to_be_bytes());
+        out.extend_from_slice(&(self.num_outputs as u16).to_be_bytes());
+        out.extend_from_slice(&self.calibration_scale.to_be_bytes());
+        out.extend_from_slice(&self.calibration_offset.to_be_bytes());
+        for value in self.weights.iter().chain(self.intercepts.iter()) {
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    pub fn linear_scores(&self, features: &[i32]) -> Result<Vec<i32>, Vec<u8>> {
+        if features.len() != self.num_features {
+            return Err("Feature count mismatch".into());
+        }
+
+        let mut scores = Vec::with_capacity(self.num_outputs);
+        for o in 0..self.num_outputs {
+            let row = &self.weights[o * self.num_features..(o + 1) * self.num_features];
+            let mut acc: i64 = 0;
+            for (w, x) in row.iter().zip(features.iter()) {
+                acc = acc.saturating_add(*w as i64 * *x as i64);
+            }
+            scores.push(clamp_i32((acc >> FRAC_BITS).saturating_add(self.intercepts[o] as i64)));
+        }
+        Ok(scores)
+    }
+
+    pub fn probability(&self, z: i32) -> i32 {
+        let scaled = (z as i64 * self.calibration_scale as i64) >> FRAC_BITS;
+        sigmoid(clamp_i32(scaled.saturating_add(self.calibration_offset as i64)))
+    }
+
+    // Con una salida la clase es p >= 0.5 y la confianza es p (probabilidad
+    // de la clase positiva); con varias salidas (one-vs-rest) se elige la de
+    // mayor z y la confianza es su probabilidad calibrada.
+    pub fn predict_features(&self, features: &[i32]) -> Result<Prediction, Vec<u8>> {
+        let z = self.linear_scores(features)?;
+        let probabilities: Vec<i32> = z.iter().map(|v| self.probability(*v)).collect();
+
+        let (class, probability) = if self.num_outputs == 1 {
+            ((probabilities[0] >= ONE / 2) as usize, probabilities[0])
+        } else {
+            let class = argmax(&z);
+            (class, probabilities[class])
+        };
+
+        let scores = match self.link {
+            Link::Identity => z,
+            Link::Logistic => probabilities,
+        };
+        Ok(Prediction {
+            scores,
+            class,
+            confidence_bps: to_bps(probability),
+        })
+    }
+}
+
+fn clamp_i32(value: i64) -> i32 {
+    value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::BPS;
+
+    fn fraud_model() -> LinearModel {
+        LinearModel {
+            num_features: 2,
+            num_outputs: 1,
+            link: Link::Logistic,
+            weights: vec![4 * ONE, -2 * ONE],
+            intercepts: vec![-ONE],
+            calibration_scale: ONE,
+            calibration_offset: 0,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let model = fraud_model();
+        assert_eq!(LinearModel::decode(&model.encode()).unwrap(), model);
+    }
+
+    #[test]
+    fn test_logistic_probability_in_bps() {
+        let model = fraud_model();
+
+        // z = 4 * 1.0 - 2 * 0 - 1 = 3 -> p = 0.9526
+        let high = model.predict_features(&[ONE, 0]).unwrap();
+        assert_eq!(high.class, 1);
+        assert!((high.confidence_bps as i32 - 9526).abs() <= 2);
+        assert_eq!(high.scores, vec![sigmoid(3 * ONE)]);
+
+        // z = -1 -> p = 0.2689; la confianza sigue siendo P(clase positiva)
+        let low = model.predict_features(&[0, 0]).unwrap();
+        assert_eq!(low.class, 0);
+        assert!((low.confidence_bps as i32 - 2689).abs() <= 2);
+    }
+
+    #[test]
+    fn test_platt_calibration() {
+        let mut model = fraud_model();
+        model.calibration_scale = ONE / 2;
+        model.calibration_offset = ONE;
+        // z = -1 -> 0.5 * -1 + 1 = 0.5 -> p = 0.6225
+        let prediction = model.predict_features(&[0, 0]).unwrap();
+        assert!((prediction.confidence_bps as i32 - 6225).abs() <= 2);
+        assert_eq!(prediction.class, 1);
+    }
+
+    #[test]
+    fn test_linear_regression_keeps_raw_scores() {
+        let model = LinearModel {
+            num_features: 1,
+            num_outputs: 2,
+            link: Link::Identity,
+            weights: vec![2 * ONE, -ONE],
+            intercepts: vec![0, ONE],
+            calibration_scale: ONE,
+            calibration_offset: 0,
+        };
+        let prediction = model.predict_features(&[ONE]).unwrap();
+        assert_eq!(prediction.scores, vec![2 * ONE, 0]);
+        assert_eq!(prediction.class, 0);
+        assert!(prediction.confidence_bps > BPS / 2);
+    }
+
+    #[test]
+    fn test_rejects_non_positive_scale() {
+        let mut model = fraud_model();
+        model.calibration_scale = 0;
+        assert!(LinearModel::decode(&model.encode()).is_err());
+    }
+}
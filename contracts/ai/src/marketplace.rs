@@ -0,0 +1,68 @@
+// Marketplace de modelos. Cada editor externo publica bajo su propio espacio
+// de nombres: los 160 bits altos del model_id son su dirección y los 96 bajos
+// un id local que elige él. Los modelos de la plataforma usan el espacio de
+// Address::ZERO, es decir, ids por debajo de 2^96. De cada tarifa cobrada la
+// plataforma se queda un porcentaje configurable y el resto va al editor.
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+use alloy_primitives::{Address, Bytes, FixedBytes};
+
+const LOCAL_ID_BITS: usize = 96;
+
+// Máximo que se puede quedar la plataforma: 50%
+pub const MAX_PLATFORM_FEE_BPS: u32 = 5_000;
+
+// Términos de licencia publicados por el editor. Si requires_acceptance está
+// activo, cada llamador debe aceptar terms_hash antes de usar el modelo.
+#[derive(Debug, Clone, Storage)]
+pub struct License {
+    pub terms_uri: Bytes,
+    pub terms_hash: FixedBytes<32>,
+    pub commercial_use: bool,
+    pub requires_acceptance: bool,
+}
+
+// None si local_id no cabe en 96 bits
+pub fn namespaced_model_id(publisher: Address, local_id: U256) -> Option<U256> {
+    if local_id >> LOCAL_ID_BITS != U256::ZERO {
+        return None;
+    }
+    Some((U256::from_be_slice(publisher.as_slice()) << LOCAL_ID_BITS) | local_id)
+}
+
+pub fn namespace_of(model_id: U256) -> Address {
+    let bytes = (model_id >> LOCAL_ID_BITS).to_be_bytes::<32>();
+    Address::from_slice(&bytes[12..])
+}
+
+// (parte de la plataforma, parte del editor)
+pub fn split_fee(fee: U256, platform_fee_bps: u32) -> (U256, U256) {
+    let platform = fee * U256::from(platform_fee_bps) / U256::from(10_000u32);
+    (platform, fee - platform)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespaces_round_trip() {
+        let publisher = Address::repeat_byte(0xab);
+        let model_id = namespaced_model_id(publisher, U256::from(7)).unwrap();
+        assert_eq!(namespace_of(model_id), publisher);
+        assert_eq!(model_id & ((U256::from(1) << 96) - U256::from(1)), U256::from(7));
+
+        // Los ids de la plataforma caen en el espacio de Address::ZERO
+        assert_eq!(namespace_of(U256::from(42)), Address::ZERO);
+        assert!(namespaced_model_id(publisher, U256::from(1) << 96).is_none());
+    }
+
+    #[test]
+    fn test_split_fee() {
+        assert_eq!(split_fee(U256::from(1_000), 1_500), (U256::from(150), U256::from(850)));
+        assert_eq!(split_fee(U256::from(999), 0), (U256::ZERO, U256::from(999)));
+        // Redondeo a favor del editor
+        assert_eq!(split_fee(U256::from(3), 5_000), (U256::from(1), U256::from(2)));
+    }
+}
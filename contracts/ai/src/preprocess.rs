@@ -0,0 +1,455 @@
+// Pipeline declarativo de preprocesado que se guarda junto a cada versión
+// de modelo y se ejecuta antes de la inferencia, para que la ingeniería de
+// features sea idéntica en entrenamiento y on-chain.
+//
+// La entrada se ve como una lista de columnas (un valor escalar por
+// columna: cada campo ABI o cada byte si el modelo no tiene esquema). Cada
+// paso lee una columna y emite una o más features Q16.16, en orden.
+
+use alloy_primitives::{keccak256, FixedBytes, U256};
+
+use crate::engine::{Reader, FRAC_BITS, ONE};
+use crate::schema::{int_to_i64, AbiValue};
+
+const PIPELINE_MAGIC: &[u8; 4] = b"BSPP";
+const PIPELINE_VERSION: u8 = 1;
+const MAX_STEPS: usize = 64;
+const MAX_CATEGORIES: u16 = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Column {
+    Unsigned(U256),
+    Signed(i64),
+    Bytes(FixedBytes<32>),
+}
+
+impl Column {
+    fn numeric(&self) -> Result<i64, Vec<u8>> {
+        match self {
+            Column::Unsigned(v) => Ok(if *v > U256::from(i64::MAX as u64) {
+                i64::MAX
+            } else {
+                v.as_limbs()[0] as i64
+            }),
+            Column::Signed(v) => Ok(*v),
+            Column::Bytes(_) => Err("Non-numeric column".into()),
+        }
+    }
+
+    fn word(&self) -> [u8; 32] {
+        match self {
+            Column::Unsigned(v) => v.to_be_bytes::<32>(),
+            Column::Signed(v) => {
+                let fill = if *v < 0 { 0xff } else { 0x00 };
+                let mut word = [fill; 32];
+                word[24..].copy_from_slice(&v.to_be_bytes());
+                word
+            }
+            Column::Bytes(b) => b.0,
+        }
+    }
+}
+
+pub fn columns_from_bytes(input: &[u8]) -> Vec<Column> {
+    input
+        .iter()
+        .map(|b| Column::Unsigned(U256::from(*b)))
+        .collect()
+}
+
+pub fn columns_from_values(values: &[AbiValue]) -> Vec<Column> {
+    let mut columns = Vec::new();
+    for value in values {
+        match value {
+            AbiValue::Uint(v) => columns.push(Column::Unsigned(*v)),
+            AbiValue::Int(v) => columns.push(Column::Signed(int_to_i64(*v))),
+            AbiValue::Bool(b) => columns.push(Column::Unsigned(U256::from(*b as u8))),
+            AbiValue::Address(a) => columns.push(Column::Bytes(a.into_word())),
+            AbiValue::Bytes32(b) => columns.push(Column::Bytes(*b)),
+            AbiValue::UintArray(items) => {
+                columns.extend(items.iter().map(|v| Column::Unsigned(*v)))
+            }
+            AbiValue::IntArray(items) => {
+                columns.extend(items.iter().map(|v| Column::Signed(int_to_i64(*v))))
+            }
+        }
+    }
+    columns
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    // Valor entero tal cual en Q16.16 (saturado)
+    Passthrough { column: u16 },
+    // (v - min) / (max - min), recortado a [0, 1]
+    MinMax { column: u16, min: i64, max: i64 },
+    // (v - mean) / std; mean y std en Q16.16 sobre las unidades de la columna
+    ZScore { column: u16, mean: i64, std: i64 },
+    // 1.0 en la posición v si v < categories; todo ceros si no
+    OneHot { column: u16, categories: u16 },
+    // One-hot sobre boundaries.len() + 1 intervalos; v < boundaries[0] cae en el 0
+    Bucketize { column: u16, boundaries: Vec<i64> },
+    // Hashing trick: keccak256(palabra) mod buckets, en one-hot
+    HashBucket { column: u16, buckets: u16 },
+}
+
+impl Step {
+    fn column(&self) -> usize {
+        match self {
+            Step::Passthrough { column }
+            | Step::MinMax { column, .. }
+            | Step::ZScore { column, .. }
+            | Step::OneHot { column, .. }
+            | Step::Bucketize { column, .. }
+            | Step::HashBucket { column, .. } => *column as usize,
+        }
+    }
+
+    pub fn output_dim(&self) -> usize {
+        match self {
+            Step::Passthrough { .. } | Step::MinMax { .. } | Step::ZScore { .. } => 1,
+            Step::OneHot { categories, .. } => *categories as usize,
+            Step::Bucketize { boundaries, .. } => boundaries.len() + 1,
+            Step::HashBucket { buckets, .. } => *buckets as usize,
+        }
+    }
+
+    fn apply(&self, column: &Column, out: &mut Vec<i32>) -> Result<(), Vec<u8>> {
+        match self {
+            Step::Passthrough { .. } => {
+                let v = column.numeric()?.clamp(
+                    (i32::MIN >> FRAC_BITS) as i64,
+                    (i32::MAX >> FRAC_BITS) as i64,
+                );
+                out.push((v as i32) << FRAC_BITS);
+            }
+            Step::MinMax { min, max, .. } => {
+                let v = column.numeric()?.clamp(*min, *max);
+                let span = (*max as i128) - (*min as i128);
+                out.push((((v as i128 - *min as i128) << FRAC_BITS) / span) as i32);
+            }
+            Step::ZScore { mean, std, .. } => {
+                let v = (column.numeric()? as i128) << FRAC_BITS;
+                let z = ((v - *mean as i128) << FRAC_BITS) / *std as i128;
+                out.push(z.clamp(i32::MIN as i128, i32::MAX as i128) as i32);
+            }
+            Step::OneHot { categories, .. } => {
+                let v = column.numeric()?;
+                push_one_hot(out, *categories as usize, usize::try_from(v).ok());
+            }
+            Step::Bucketize { boundaries, .. } => {
+                let v = column.numeric()?;
+                let bucket = boundaries.iter().take_while(|b| v >= **b).count();
+                push_one_hot(out, boundaries.len() + 1, Some(bucket));
+            }
+            Step::HashBucket { buckets, .. } => {
+                let hash = U256::from_be_bytes(keccak256(column.word()).0);
+                let bucket = (hash % U256::from(*buckets)).as_limbs()[0] as usize;
+                push_one_hot(out, *buckets as usize, Some(bucket));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn push_one_hot(out: &mut Vec<i32>, size: usize, index: Option<usize>) {
+    let start = out.len();
+    out.resize(start + size, 0);
+    if let Some(i) = index.filter(|i| *i < size) {
+        out[start + i] = ONE;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    pub steps: Vec<Step>,
+}
+
+impl Pipeline {
+    // Formato: "BSPP" | version u8 | num_steps u8 | pasos
+    // Paso: tag u8 | column u16 | parámetros
+    //   0 Passthrough: -
+    //   1 MinMax: min i64 | max i64
+    //   2 ZScore: mean i64 | std i64
+    //   3 OneHot: categories u16
+    //   4 Bucketize: count u8 | boundaries i64[count] (estrictamente crecientes)
+    //   5 HashBucket: buckets u16
+    pub fn decode(data: &[u8]) -> Result<Self, Vec<u8>> {
+        let mut reader = Reader::new(data);
+        if reader.take(4)? != PIPELINE_MAGIC {
+            return Err("Invalid pipeline magic".into());
+        }
+        if reader.u8()? != PIPELINE_VERSION {
+            return Err("Unsupported pipeline version".into());
+        }
+
+        let num_steps = reader.u8()? as usize;
+        if num_steps == 0 || num_steps > MAX_STEPS {
+            return Err("Invalid pipeline length".into());
+        }
+
+        let mut steps = Vec::with_capacity(num_steps);
+        for _ in 0..num_steps {
+            let tag = reader.u8()?;
+            let column = reader.u16()?;
+            let step = match tag {
+                0 => Step::Passthrough { column },
+                1 => {
+                    let min = reader.i64()?;
+                    let max = reader.i64()?;
+                    if max <= min {
+                        return Err("Invalid min-max range".into());
+                    }
+                    Step::MinMax { column, min, max }
+                }
+                2 => {
+                    let mean = reader.i64()?;
+                    let std = reader.i64()?;
+                    if std <= 0 {
+                        return Err("Standard deviation must be positive".into());
+                    }
+                    Step::ZScore { column, mean, std }
+                }
+                3 => Step::OneHot {
+                    column,
+                    categories: read_category_count(&mut reader)?,
+                },
+                4 => {
+                    let count = reader.u8()? as usize;
+                    let mut boundaries = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        boundaries.push(reader.i64()?);
+                    }
+                    if count == 0 || boundaries.windows(2).any(|w| w[0] >= w[1]) {
+                        return Err("Invalid bucket boundaries".into());
+                    }
+                    Step::Bucketize { column, boundaries }
+                }
+                5 => Step::HashBucket {
+                    column,
+                    buckets: read_category_count(&mut reader)?,
+                },
+                _ => return Err("Unknown preprocessing step".into()),
+            };
+            steps.push(step);
+        }
+
+        if !reader.is_empty() {
+            return Err("Trailing pipeline data".into());
+        }
+        Ok(Self { steps })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(PIPELINE_MAGIC);
+        out.push(PIPELINE_VERSION);
+        out.push(self.steps.len() as u8);
+        for step in &self.steps {
+            let (tag, column) = match step {
+                Step::Passthrough { column } => (0, column),
+                Step::MinMax { column, .. } => (1, column),
+                Step::ZScore { column, .. } => (2, column),
+                Step::OneHot { column, .. } => (3, column),
+                Step::Bucketize { column, .. } => (4, column),
+                Step::HashBucket { column, .. } => (5, column),
+            };
+            out.push(tag);
+            out.extend_from_slice(&column.to_be_bytes());
+            match step {
+                Step::Passthrough { .. } => {}
+                Step::MinMax { min: a, max: b, .. }
+                | Step::ZScore {
+                    mean: a, std: b, ..
+                } => {
+                    out.extend_from_slice(&a.to_be_bytes());
+                    out.extend_from_slice(&b.to_be_bytes());
+                }
+                Step::OneHot { categories: n, .. } | Step::HashBucket { buckets: n, .. } => {
+                    out.extend_from_slice(&n.to_be_bytes());
+                }
+                Step::Bucketize { boundaries, .. } => {
+                    out.push(boundaries.len() as u8);
+                    for boundary in boundaries {
+                        out.extend_from_slice(&boundary.to_be_bytes());
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn output_dim(&self) -> usize {
+        self.steps.iter().map(|s| s.output_dim()).sum()
+    }
+
+    pub fn apply(&self, columns: &[Column]) -> Result<Vec<i32>, Vec<u8>> {
+        let mut features = Vec::with_capacity(self.output_dim());
+        for step in &self.steps {
+            let column = columns.get(step.column()).ok_or("Missing input column")?;
+            step.apply(column, &mut features)?;
+        }
+        Ok(features)
+    }
+}
+
+fn read_category_count(reader: &mut Reader) -> Result<u16, Vec<u8>> {
+    let count = reader.u16()?;
+    if count == 0 || count > MAX_CATEGORIES {
+        return Err("Invalid category count".into());
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::Address;
+
+    fn unsigned(v: u64) -> Column {
+        Column::Unsigned(U256::from(v))
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let pipeline = Pipeline {
+            steps: vec![
+                Step::Passthrough { column: 0 },
+                Step::MinMax {
+                    column: 1,
+                    min: 0,
+                    max: 100,
+                },
+                Step::ZScore {
+                    column: 2,
+                    mean: -3 * ONE as i64,
+                    std: 2 * ONE as i64,
+                },
+                Step::OneHot {
+                    column: 3,
+                    categories: 4,
+                },
+                Step::Bucketize {
+                    column: 4,
+                    boundaries: vec![10, 20, 30],
+                },
+                Step::HashBucket {
+                    column: 5,
+                    buckets: 8,
+                },
+            ],
+        };
+        assert_eq!(Pipeline::decode(&pipeline.encode()).unwrap(), pipeline);
+        assert_eq!(pipeline.output_dim(), 1 + 1 + 1 + 4 + 4 + 8);
+    }
+
+    #[test]
+    fn test_normalization() {
+        let pipeline = Pipeline {
+            steps: vec![
+                Step::MinMax {
+                    column: 0,
+                    min: 0,
+                    max: 100,
+                },
+                Step::MinMax {
+                    column: 1,
+                    min: 0,
+                    max: 100,
+                },
+                Step::ZScore {
+                    column: 0,
+                    mean: 50 * ONE as i64,
+                    std: 10 * ONE as i64,
+                },
+            ],
+        };
+        let features = pipeline.apply(&[unsigned(75), unsigned(500)]).unwrap();
+        assert_eq!(features, vec![3 * ONE / 4, ONE, 5 * ONE / 2]);
+    }
+
+    #[test]
+    fn test_timestamp_bucketization() {
+        let pipeline = Pipeline {
+            steps: vec![Step::Bucketize {
+                column: 0,
+                boundaries: vec![1_700_000_000, 1_800_000_000],
+            }],
+        };
+        assert_eq!(
+            pipeline.apply(&[unsigned(1_600_000_000)]).unwrap(),
+            vec![ONE, 0, 0]
+        );
+        assert_eq!(
+            pipeline.apply(&[unsigned(1_700_000_000)]).unwrap(),
+            vec![0, ONE, 0]
+        );
+        assert_eq!(
+            pipeline.apply(&[unsigned(1_900_000_000)]).unwrap(),
+            vec![0, 0, ONE]
+        );
+    }
+
+    #[test]
+    fn test_one_hot_unknown_category() {
+        let pipeline = Pipeline {
+            steps: vec![Step::OneHot {
+                column: 0,
+                categories: 3,
+            }],
+        };
+        assert_eq!(pipeline.apply(&[unsigned(1)]).unwrap(), vec![0, ONE, 0]);
+        assert_eq!(pipeline.apply(&[unsigned(9)]).unwrap(), vec![0, 0, 0]);
+        assert_eq!(
+            pipeline.apply(&[Column::Signed(-1)]).unwrap(),
+            vec![0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn test_address_hashing_is_stable() {
+        let pipeline = Pipeline {
+            steps: vec![Step::HashBucket {
+                column: 0,
+                buckets: 16,
+            }],
+        };
+        let values = [AbiValue::Address(Address::repeat_byte(0x11))];
+        let columns = columns_from_values(&values);
+
+        let first = pipeline.apply(&columns).unwrap();
+        assert_eq!(first, pipeline.apply(&columns).unwrap());
+        assert_eq!(first.iter().filter(|f| **f == ONE).count(), 1);
+
+        // Las columnas de bytes no admiten pasos numéricos
+        let numeric = Pipeline {
+            steps: vec![Step::Passthrough { column: 0 }],
+        };
+        assert!(numeric.apply(&columns).is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_pipelines() {
+        let bad_range = Pipeline {
+            steps: vec![Step::MinMax {
+                column: 0,
+                min: 5,
+                max: 5,
+            }],
+        };
+        assert!(Pipeline::decode(&bad_range.encode()).is_err());
+
+        let unsorted = Pipeline {
+            steps: vec![Step::Bucketize {
+                column: 0,
+                boundaries: vec![3, 1],
+            }],
+        };
+        assert!(Pipeline::decode(&unsorted.encode()).is_err());
+
+        let missing = Pipeline {
+            steps: vec![Step::Passthrough { column: 2 }],
+        };
+        assert!(missing.apply(&[unsigned(1)]).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Tarifas de pago por inferencia. Cada modelo fija una tarifa base más un
+// precio por unidad de cómputo y por byte de entrada, cobrada en ETH nativo
+// o en el token ERC-20 configurado (EDU). Lo cobrado se acumula a nombre del
+// dueño del modelo hasta que lo retira.
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+use alloy_primitives::Address;
+
+// Un modelo que cobra en NATIVE_TOKEN se paga con msg.value
+pub const NATIVE_TOKEN: Address = Address::ZERO;
+
+sol_interface! {
+    interface IERC20 {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    }
+}
+
+#[derive(Debug, Clone, Storage)]
+pub struct PricingConfig {
+    pub token: Address,
+    pub base_fee: U256,
+    pub fee_per_compute_unit: U256,
+    pub fee_per_input_byte: U256,
+}
+
+impl PricingConfig {
+    pub fn is_native(&self) -> bool {
+        self.token == NATIVE_TOKEN
+    }
+
+    pub fn is_free(&self) -> bool {
+        self.base_fee.is_zero()
+            && self.fee_per_compute_unit.is_zero()
+            && self.fee_per_input_byte.is_zero()
+    }
+
+    // Tarifa de una inferencia; satura en vez de desbordar
+    pub fn fee(&self, compute_units: U256, input_size: usize) -> U256 {
+        self.base_fee
+            .saturating_add(self.fee_per_compute_unit.saturating_mul(compute_units))
+            .saturating_add(self.fee_per_input_byte.saturating_mul(U256::from(input_size)))
+    }
+
+    // Tarifa de un lote: la suma de la de cada entrada
+    pub fn batch_fee(&self, compute_units: U256, input_sizes: impl IntoIterator<Item = usize>) -> U256 {
+        input_sizes
+            .into_iter()
+            .fold(U256::ZERO, |total, size| total.saturating_add(self.fee(compute_units, size)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_combines_compute_units_and_input_size() {
+        let pricing = PricingConfig {
+            token: NATIVE_TOKEN,
+            base_fee: U256::from(1_000),
+            fee_per_compute_unit: U256::from(10),
+            fee_per_input_byte: U256::from(2),
+        };
+        assert!(pricing.is_native());
+        assert!(!pricing.is_free());
+
+        assert_eq!(pricing.fee(U256::from(100), 64), U256::from(1_000 + 1_000 + 128));
+        assert_eq!(
+            pricing.batch_fee(U256::from(100), [64, 0]),
+            U256::from(2_128 + 2_000)
+        );
+
+        let huge = PricingConfig {
+            fee_per_compute_unit: U256::MAX,
+            ..pricing
+        };
+        assert_eq!(huge.fee(U256::from(2), 1), U256::MAX);
+    }
+}
@@ -0,0 +1,476 @@
+// Redes cuantizadas int8/int16 con escala y punto cero por capa.
+// Valor real = scale * (q - zero_point). Las activaciones de entrada de
+// cada capa se cuantizan a int8 y el producto se acumula en i32; solo el
+// reescalado final a Q16.16 usa i128.
+
+use crate::engine::{Activation, Reader, FRAC_BITS};
+
+const QUANTIZED_MAGIC: &[u8; 4] = b"BSQN";
+const QUANTIZED_VERSION: u8 = 1;
+
+// Las escalas se guardan en Q8.24 para no perder precisión con valores pequeños.
+pub const SCALE_BITS: u32 = 24;
+
+const ACTIVATION_MIN: i32 = i8::MIN as i32;
+const ACTIVATION_MAX: i32 = i8::MAX as i32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantWidth {
+    Int8,
+    Int16,
+}
+
+impl QuantWidth {
+    fn bytes(&self) -> usize {
+        match self {
+            QuantWidth::Int8 => 1,
+            QuantWidth::Int16 => 2,
+        }
+    }
+
+    pub fn range(&self) -> (i32, i32) {
+        match self {
+            QuantWidth::Int8 => (i8::MIN as i32, i8::MAX as i32),
+            QuantWidth::Int16 => (i16::MIN as i32, i16::MAX as i32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantScheme {
+    Symmetric,
+    Asymmetric,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedTensor {
+    pub width: QuantWidth,
+    pub scheme: QuantScheme,
+    pub scale: i32,
+    pub zero_point: i32,
+    pub values: Vec<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedLayer {
+    pub input_dim: usize,
+    pub output_dim: usize,
+    pub activation: Activation,
+    pub weights: QuantizedTensor,
+    // Cuantización int8 asimétrica de la entrada de la capa
+    pub input_scale: i32,
+    pub input_zero_point: i32,
+    // Biases en Q16.16
+    pub biases: Vec<i32>,
+}
+
+impl QuantizedLayer {
+    fn quantize_input(&self, x: i32) -> i32 {
+        // x / s en Q16.16 / Q8.24 -> x * 2^8 / s
+        let q = div_round((x as i64) << (SCALE_BITS - FRAC_BITS), self.input_scale as i64);
+        (q.clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+            .saturating_add(self.input_zero_point)
+            .clamp(ACTIVATION_MIN, ACTIVATION_MAX)
+    }
+
+    fn forward(&self, input: &[i32]) -> Vec<i32> {
+        let quantized: Vec<i32> = input
+            .iter()
+            .map(|x| self.quantize_input(*x) - self.input_zero_point)
+            .collect();
+
+        // Reescalado Q8.24 * Q8.24 -> Q16.16
+        let shift = 2 * SCALE_BITS - FRAC_BITS;
+        let combined_scale = self.weights.scale as i128 * self.input_scale as i128;
+
+        let mut output = Vec::with_capacity(self.output_dim);
+        for o in 0..self.output_dim {
+            let row = &self.weights.values[o * self.input_dim..(o + 1) * self.input_dim];
+            let mut acc: i32 = 0;
+            for (w, x) in row.iter().zip(quantized.iter()) {
+                // check_accumulator garantiza que no hay desbordamiento
+                acc += (*w - self.weights.zero_point) * *x;
+            }
+            let value = ((acc as i128 * combined_scale) >> shift) + self.biases[o] as i128;
+            let value = value.clamp(i32::MIN as i128, i32::MAX as i128) as i32;
+            output.push(self.activation.apply(value));
+        }
+        output
+    }
+
+    // Cota del peor caso del acumulador: si cabe en i32 la suma nunca desborda.
+    fn check_accumulator(&self) -> Result<(), Vec<u8>> {
+        let (min, max) = self.weights.width.range();
+        let zero_point = self.weights.zero_point as i64;
+        let max_weight = (max as i64 - zero_point).abs().max((min as i64 - zero_point).abs());
+        let input_zero_point = self.input_zero_point as i64;
+        let max_input = (ACTIVATION_MAX as i64 - input_zero_point)
+            .abs()
+            .max((ACTIVATION_MIN as i64 - input_zero_point).abs());
+
+        let bound = max_weight * max_input * self.input_dim as i64;
+        if bound > i32::MAX as i64 {
+            return Err("Quantized layer may overflow accumulator".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizedNetwork {
+    pub layers: Vec<QuantizedLayer>,
+}
+
+impl QuantizedNetwork {
+    // Formato: "BSQN" | version u8 | num_layers u8 |
+    // por capa: input_dim u16 | output_dim u16 | activation u8 | width u8 |
+    // scheme u8 | weight_scale i32 | weight_zero_point i32 | input_scale i32 |
+    // input_zero_point i32 | pesos (1 o 2 bytes con signo)[output_dim * input_dim] |
+    // biases i32[output_dim]
+    pub fn decode(data: &[u8]) -> Result<Self, Vec<u8>> {
+        let mut reader = Reader::new(data);
+        if reader.take(4)? != QUANTIZED_MAGIC {
+            return Err("Invalid quantized network magic".into());
+        }
+        if reader.u8()? != QUANTIZED_VERSION {
+            return Err("Unsupported quantized network version".into());
+        }
+
+        let num_layers = reader.u8()? as usize;
+        if num_layers == 0 {
+            return Err("Network has no layers".into());
+        }
+
+        let mut layers: Vec<QuantizedLayer> = Vec::with_capacity(num_layers);
+        for _ in 0..num_layers {
+            let input_dim = reader.u16()? as usize;
+            let output_dim = reader.u16()? as usize;
+            let activation = Activation::from_u8(reader.u8()?)?;
+            let width = match reader.u8()? {
+                1 => QuantWidth::Int8,
+                2 => QuantWidth::Int16,
+                _ => return Err("Unknown quantization width".into()),
+            };
+            let scheme = match reader.u8()? {
+                0 => QuantScheme::Symmetric,
+                1 => QuantScheme::Asymmetric,
+                _ => return Err("Unknown quantization scheme".into()),
+            };
+            let weight_scale = reader.i32()?;
+            let weight_zero_point = reader.i32()?;
+            let input_scale = reader.i32()?;
+            let input_zero_point = reader.i32()?;
+
+            if input_dim == 0 || output_dim == 0 {
+                return Err("Empty layer".into());
+            }
+            if let Some(prev) = layers.last() {
+                if prev.output_dim != input_dim {
+                    return Err("Layer shape mismatch".into());
+                }
+            }
+            if weight_scale <= 0 || input_scale <= 0 {
+                return Err("Quantization scale must be positive".into());
+            }
+            let (min, max) = width.range();
+            if !(min..=max).contains(&weight_zero_point) {
+                return Err("Weight zero point out of range".into());
+            }
+            if scheme == QuantScheme::Symmetric && weight_zero_point != 0 {
+                return Err("Symmetric quantization requires zero point 0".into());
+            }
+            if !(ACTIVATION_MIN..=ACTIVATION_MAX).contains(&input_zero_point) {
+                return Err("Input zero point out of range".into());
+            }
+
+            let raw = reader.take(input_dim * output_dim * width.bytes())?;
+            let values = match width {
+                QuantWidth::Int8 => raw.iter().map(|b| *b as i8 as i32).collect(),
+                QuantWidth::Int16 => raw
+                    .chunks_exact(2)
+                    .map(|c| i16::from_be_bytes([c[0], c[1]]) as i32)
+                    .collect(),
+            };
+            let biases = reader.i32_vec(output_dim)?;
+
+            let layer = QuantizedLayer {
+                input_dim,
+                output_dim,
+                activation,
+                weights: QuantizedTensor {
+                    width,
+                    scheme,
+                    scale: weight_scale,
+                    zero_point: weight_zero_point,
+                    values,
+                },
+                input_scale,
+                input_zero_point,
+                biases,
+            };
+            layer.check_accumulator()?;
+            layers.push(layer);
+        }
+
+        if !reader.is_empty() {
+            return Err("Trailing network data".into());
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(QUANTIZED_MAGIC);
+        out.push(QUANTIZED_VERSION);
+        out.push(self.layers.len() as u8);
+        for layer in &self.layers {
+            let weights = &layer.weights;
+            out.extend_from_slice(&(layer.input_dim as u16).to_be_bytes());
+            out.extend_from_slice(&(layer.output_dim as u16).to_be_bytes());
+            out.push(layer.activation.to_u8());
+            out.push(weights.width.bytes() as u8);
+            out.push(match weights.scheme {
+                QuantScheme::Symmetric => 0,
+                QuantScheme::Asymmetric => 1,
+            });
+            out.extend_from_slice(&weights.scale.to_be_bytes());
+            out.extend_from_slice(&weights.zero_point.to_be_bytes());
+            out.extend_from_slice(&layer.input_scale.to_be_bytes());
+            out.extend_from_slice(&layer.input_zero_point.to_be_bytes());
+            for value in &weights.values {
+                match weights.width {
+                    QuantWidth::Int8 => out.push(*value as i8 as u8),
+                    QuantWidth::Int16 => out.extend_from_slice(&(*value as i16).to_be_bytes()),
+                }
+            }
+            for bias in &layer.biases {
+                out.extend_from_slice(&bias.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn input_dim(&self) -> usize {
+        self.layers[0].input_dim
+    }
+
+    pub fn output_dim(&self) -> usize {
+        self.layers[self.layers.len() - 1].output_dim
+    }
+
+    pub fn evaluate(&self, features: &[i32]) -> Result<Vec<i32>, Vec<u8>> {
+        if features.len() != self.input_dim() {
+            return Err("Feature count mismatch".into());
+        }
+        let mut activations = features.to_vec();
+        for layer in &self.layers {
+            activations = layer.forward(&activations);
+        }
+        Ok(activations)
+    }
+}
+
+// Redondeo a la mitad alejándose de cero; `d` siempre es positivo.
+fn div_round(n: i64, d: i64) -> i64 {
+    if n >= 0 {
+        (n + d / 2) / d
+    } else {
+        (n - d / 2) / d
+    }
+}
+
+// Codificador para el lado host: convierte modelos en coma flotante al
+// formato cuantizado. No se compila en el contrato.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod encoder {
+    use super::*;
+
+    fn to_fixed(value: f64, bits: u32) -> i32 {
+        (value * (1u64 << bits) as f64)
+            .round()
+            .clamp(i32::MIN as f64, i32::MAX as f64) as i32
+    }
+
+    // Escala y punto cero que cubren [min, max] (siempre incluyendo 0).
+    fn scale_and_zero_point(min: f64, max: f64, range: (i32, i32), scheme: QuantScheme) -> (i32, i32) {
+        let (q_min, q_max) = (range.0 as f64, range.1 as f64);
+        let (min, max) = (min.min(0.0), max.max(0.0));
+        match scheme {
+            QuantScheme::Symmetric => {
+                let bound = min.abs().max(max.abs());
+                let scale = if bound == 0.0 { 1.0 } else { bound / q_max };
+                (to_fixed(scale, SCALE_BITS).max(1), 0)
+            }
+            QuantScheme::Asymmetric => {
+                let span = max - min;
+                let scale = if span == 0.0 { 1.0 } else { span / (q_max - q_min) };
+                let zero_point = (q_min - min / scale).round().clamp(q_min, q_max) as i32;
+                (to_fixed(scale, SCALE_BITS).max(1), zero_point)
+            }
+        }
+    }
+
+    pub fn quantize_tensor(values: &[f64], width: QuantWidth, scheme: QuantScheme) -> QuantizedTensor {
+        let min = values.iter().cloned().fold(0.0, f64::min);
+        let max = values.iter().cloned().fold(0.0, f64::max);
+        let (q_min, q_max) = width.range();
+        let (scale, zero_point) = scale_and_zero_point(min, max, (q_min, q_max), scheme);
+        let real_scale = scale as f64 / (1u64 << SCALE_BITS) as f64;
+
+        QuantizedTensor {
+            width,
+            scheme,
+            scale,
+            zero_point,
+            values: values
+                .iter()
+                .map(|v| ((v / real_scale).round() as i64 + zero_point as i64).clamp(q_min as i64, q_max as i64) as i32)
+                .collect(),
+        }
+    }
+
+    // Capa densa en coma flotante; `weights` va fila por neurona de salida.
+    pub struct FloatLayer {
+        pub input_dim: usize,
+        pub output_dim: usize,
+        pub activation: Activation,
+        pub weights: Vec<f64>,
+        pub biases: Vec<f64>,
+    }
+
+    // `input_range` es el rango esperado de las activaciones de entrada,
+    // medido durante la calibración.
+    pub fn quantize_layer(
+        layer: &FloatLayer,
+        input_range: (f64, f64),
+        width: QuantWidth,
+        scheme: QuantScheme,
+    ) -> QuantizedLayer {
+        assert_eq!(layer.weights.len(), layer.input_dim * layer.output_dim);
+        assert_eq!(layer.biases.len(), layer.output_dim);
+
+        let (input_scale, input_zero_point) = scale_and_zero_point(
+            input_range.0,
+            input_range.1,
+            (ACTIVATION_MIN, ACTIVATION_MAX),
+            QuantScheme::Asymmetric,
+        );
+
+        QuantizedLayer {
+            input_dim: layer.input_dim,
+            output_dim: layer.output_dim,
+            activation: layer.activation,
+            weights: quantize_tensor(&layer.weights, width, scheme),
+            input_scale,
+            input_zero_point,
+            biases: layer.biases.iter().map(|b| to_fixed(*b, FRAC_BITS)).collect(),
+        }
+    }
+
+    pub fn quantize_network(
+        layers: &[FloatLayer],
+        input_ranges: &[(f64, f64)],
+        width: QuantWidth,
+        scheme: QuantScheme,
+    ) -> QuantizedNetwork {
+        assert_eq!(layers.len(), input_ranges.len());
+        QuantizedNetwork {
+            layers: layers
+                .iter()
+                .zip(input_ranges.iter())
+                .map(|(layer, range)| quantize_layer(layer, *range, width, scheme))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encoder::*;
+    use super::*;
+    use crate::engine::ONE;
+
+    fn float_layer() -> FloatLayer {
+        FloatLayer {
+            input_dim: 3,
+            output_dim: 2,
+            activation: Activation::Identity,
+            weights: vec![0.5, -0.25, 0.125, 1.0, 0.75, -0.5],
+            biases: vec![0.1, -0.2],
+        }
+    }
+
+    fn float_forward(weights: &[f64], biases: &[f64], input: &[f64]) -> Vec<f64> {
+        biases
+            .iter()
+            .enumerate()
+            .map(|(o, b)| b + (0..input.len()).map(|i| weights[o * input.len() + i] * input[i]).sum::<f64>())
+            .collect()
+    }
+
+    fn network(width: QuantWidth, scheme: QuantScheme) -> QuantizedNetwork {
+        quantize_network(&[float_layer()], &[(0.0, 1.0)], width, scheme)
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        for width in [QuantWidth::Int8, QuantWidth::Int16] {
+            for scheme in [QuantScheme::Symmetric, QuantScheme::Asymmetric] {
+                let model = network(width, scheme);
+                assert_eq!(QuantizedNetwork::decode(&model.encode()).unwrap(), model);
+            }
+        }
+    }
+
+    #[test]
+    fn test_int8_weights_are_one_byte() {
+        let int8 = network(QuantWidth::Int8, QuantScheme::Symmetric).encode();
+        let int16 = network(QuantWidth::Int16, QuantScheme::Symmetric).encode();
+        assert_eq!(int16.len() - int8.len(), 6);
+    }
+
+    #[test]
+    fn test_quantized_matches_float() {
+        let layer = float_layer();
+        let input = [0.25, 0.5, 1.0];
+        let expected = float_forward(&layer.weights, &layer.biases, &input);
+        let features: Vec<i32> = input.iter().map(|x| (x * ONE as f64) as i32).collect();
+
+        for (width, tolerance) in [(QuantWidth::Int8, 0.02), (QuantWidth::Int16, 0.01)] {
+            for scheme in [QuantScheme::Symmetric, QuantScheme::Asymmetric] {
+                let output = network(width, scheme).evaluate(&features).unwrap();
+                for (got, want) in output.iter().zip(expected.iter()) {
+                    let got = *got as f64 / ONE as f64;
+                    assert!((got - want).abs() < tolerance, "{:?} {:?}: {} vs {}", width, scheme, got, want);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoder_is_reproducible() {
+        let a = network(QuantWidth::Int8, QuantScheme::Asymmetric).encode();
+        let b = network(QuantWidth::Int8, QuantScheme::Asymmetric).encode();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_rejects_symmetric_with_zero_point() {
+        let mut model = network(QuantWidth::Int8, QuantScheme::Symmetric);
+        model.layers[0].weights.zero_point = 3;
+        assert!(QuantizedNetwork::decode(&model.encode()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_possible_overflow() {
+        let layer = FloatLayer {
+            input_dim: 600,
+            output_dim: 1,
+            activation: Activation::Identity,
+            weights: vec![1.0; 600],
+            biases: vec![0.0],
+        };
+        let model = quantize_network(&[layer], &[(-1.0, 1.0)], QuantWidth::Int16, QuantScheme::Symmetric);
+        assert!(QuantizedNetwork::decode(&model.encode()).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+// Cuotas de uso por modelo. Cada modelo puede limitar, por llamador y en
+// total, las peticiones por ventana de bloques y las unidades de cómputo por
+// época. Las ventanas están alineadas a múltiplos de su longitud en bloques,
+// así todos los contadores de un modelo se reinician a la vez. Un límite a 0
+// significa sin límite.
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+#[derive(Debug, Clone, Storage)]
+pub struct QuotaConfig {
+    pub window_blocks: U256,
+    pub epoch_blocks: U256,
+    pub caller_requests_per_window: U256,
+    pub caller_compute_units_per_epoch: U256,
+    pub model_requests_per_window: U256,
+    pub model_compute_units_per_epoch: U256,
+}
+
+impl QuotaConfig {
+    pub fn validate(&self) -> Result<(), Vec<u8>> {
+        let limits_requests = !self.caller_requests_per_window.is_zero()
+            || !self.model_requests_per_window.is_zero();
+        if limits_requests && self.window_blocks.is_zero() {
+            return Err(b"Request limits need a block window".to_vec());
+        }
+        let limits_units = !self.caller_compute_units_per_epoch.is_zero()
+            || !self.model_compute_units_per_epoch.is_zero();
+        if limits_units && self.epoch_blocks.is_zero() {
+            return Err(b"Compute unit limits need an epoch".to_vec());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuotaLimit {
+    Requests(U256),
+    ComputeUnits(U256),
+}
+
+// Consumo de un llamador (o del modelo entero) en la ventana y época actuales
+#[derive(Debug, Clone, Default, Storage)]
+pub struct Usage {
+    pub window: U256,
+    pub requests: U256,
+    pub epoch: U256,
+    pub compute_units: U256,
+}
+
+impl Usage {
+    // Descarta el consumo de ventanas y épocas ya cerradas
+    pub fn roll(&mut self, config: &QuotaConfig, block: U256) {
+        let window = period(block, config.window_blocks);
+        if window != self.window {
+            self.window = window;
+            self.requests = U256::ZERO;
+        }
+        let epoch = period(block, config.epoch_blocks);
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.compute_units = U256::ZERO;
+        }
+    }
+
+    // Comprueba los límites antes de anotar nada: si uno falla no se consume
+    pub fn consume(
+        &mut self,
+        requests: U256,
+        compute_units: U256,
+        request_limit: U256,
+        unit_limit: U256,
+    ) -> Result<(), QuotaLimit> {
+        if exceeds(self.requests, requests, request_limit) {
+            return Err(QuotaLimit::Requests(request_limit));
+        }
+        if exceeds(self.compute_units, compute_units, unit_limit) {
+            return Err(QuotaLimit::ComputeUnits(unit_limit));
+        }
+        self.requests += requests;
+        self.compute_units += compute_units;
+        Ok(())
+    }
+
+    // (peticiones, unidades) que quedan; U256::MAX si no hay límite
+    pub fn remaining(&self, request_limit: U256, unit_limit: U256) -> (U256, U256) {
+        (left(self.requests, request_limit), left(self.compute_units, unit_limit))
+    }
+}
+
+// Primer bloque de la siguiente ventana de `length` bloques
+pub fn next_reset(block: U256, length: U256) -> U256 {
+    if length.is_zero() {
+        return U256::ZERO;
+    }
+    (period(block, length) + U256::from(1)) * length
+}
+
+fn period(block: U256, length: U256) -> U256 {
+    if length.is_zero() {
+        return U256::ZERO;
+    }
+    block / length
+}
+
+fn exceeds(used: U256, amount: U256, limit: U256) -> bool {
+    !limit.is_zero() && used.saturating_add(amount) > limit
+}
+
+fn left(used: U256, limit: U256) -> U256 {
+    if limit.is_zero() {
+        return U256::MAX;
+    }
+    limit.saturating_sub(used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> QuotaConfig {
+        QuotaConfig {
+            window_blocks: U256::from(10),
+            epoch_blocks: U256::from(100),
+            caller_requests_per_window: U256::from(2),
+            caller_compute_units_per_epoch: U256::from(500),
+            model_requests_per_window: U256::ZERO,
+            model_compute_units_per_epoch: U256::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_limits_reset_with_window_and_epoch() {
+        let config = config();
+        let (requests, units) = (config.caller_requests_per_window, config.caller_compute_units_per_epoch);
+        let mut usage = Usage::default();
+
+        usage.roll(&config, U256::from(11));
+        usage.consume(U256::from(1), U256::from(200), requests, units).unwrap();
+        usage.consume(U256::from(1), U256::from(200), requests, units).unwrap();
+        assert_eq!(
+            usage.consume(U256::from(1), U256::from(50), requests, units),
+            Err(QuotaLimit::Requests(requests))
+        );
+        assert_eq!(usage.remaining(requests, units), (U256::ZERO, U256::from(100)));
+
+        // Nueva ventana, misma época: vuelven las peticiones pero no las unidades
+        usage.roll(&config, U256::from(20));
+        assert_eq!(
+            usage.consume(U256::from(1), U256::from(200), requests, units),
+            Err(QuotaLimit::ComputeUnits(units))
+        );
+        usage.consume(U256::from(1), U256::from(100), requests, units).unwrap();
+
+        usage.roll(&config, U256::from(100));
+        assert_eq!(usage.remaining(requests, units), (requests, units));
+        assert_eq!(next_reset(U256::from(100), config.epoch_blocks), U256::from(200));
+    }
+
+    #[test]
+    fn test_zero_limit_is_unlimited() {
+        let mut config = config();
+        config.caller_requests_per_window = U256::ZERO;
+        config.caller_compute_units_per_epoch = U256::ZERO;
+        config.window_blocks = U256::ZERO;
+        config.epoch_blocks = U256::ZERO;
+        assert!(config.validate().is_ok());
+
+        let mut usage = Usage::default();
+        usage.roll(&config, U256::from(5));
+        usage.consume(U256::from(1_000), U256::from(1_000_000), U256::ZERO, U256::ZERO).unwrap();
+        assert_eq!(usage.remaining(U256::ZERO, U256::ZERO), (U256::MAX, U256::MAX));
+
+        config.model_requests_per_window = U256::from(3);
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,101 @@
+// Asignador de ids de petición compartido por AIProcessor y OffChainCompute.
+// Cada id es un nonce persistente y monótono con el dominio del contrato en
+// los bits altos, así dos peticiones del mismo bloque (o de un mismo lote)
+// nunca comparten id y los ids de un contrato no chocan con los del otro
+// cuando viajan entre ambos. Los contratos standalone lo incluyen con
+// #[path = "src/requests.rs"].
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+use alloy_primitives::Address;
+
+pub const DOMAIN_AI_PROCESSOR: u8 = 1;
+pub const DOMAIN_OFF_CHAIN_COMPUTE: u8 = 2;
+pub const DOMAIN_AI_PROCESSOR_STYLUS: u8 = 3;
+
+const DOMAIN_SHIFT: usize = 128;
+
+#[derive(Debug, Clone, Storage)]
+pub struct RequestOrigin {
+    pub sender: Address,
+    pub model_id: U256,
+    pub block_number: U256,
+    pub timestamp: U256,
+}
+
+#[derive(Debug)]
+pub struct RequestIdAllocator {
+    domain: u8,
+    // Último nonce emitido; el primero es 1, así 0 nunca es un id válido
+    last_nonce: U256,
+    origins: StorageMap<U256, RequestOrigin>,
+}
+
+impl RequestIdAllocator {
+    pub fn new(domain: u8) -> Self {
+        Self {
+            domain,
+            last_nonce: U256::ZERO,
+            origins: StorageMap::new(),
+        }
+    }
+
+    pub fn allocate(&mut self, sender: Address, model_id: U256) -> U256 {
+        self.last_nonce += U256::from(1);
+        let request_id = (U256::from(self.domain) << DOMAIN_SHIFT) | self.last_nonce;
+
+        self.origins.insert(request_id, RequestOrigin {
+            sender,
+            model_id,
+            block_number: evm::block_number().into(),
+            timestamp: evm::block_timestamp().into(),
+        });
+
+        request_id
+    }
+
+    pub fn origin(&self, request_id: U256) -> Option<RequestOrigin> {
+        self.origins.get(&request_id)
+    }
+
+    pub fn issued(&self) -> U256 {
+        self.last_nonce
+    }
+}
+
+pub fn domain_of(request_id: U256) -> u8 {
+    (request_id >> DOMAIN_SHIFT).as_limbs()[0] as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_are_unique_and_monotonic() {
+        let mut allocator = RequestIdAllocator::new(DOMAIN_AI_PROCESSOR);
+        let sender = Address::repeat_byte(0x01);
+
+        let first = allocator.allocate(sender, U256::from(7));
+        let second = allocator.allocate(sender, U256::from(7));
+        assert!(second > first);
+        assert_eq!(allocator.issued(), U256::from(2));
+
+        let origin = allocator.origin(second).unwrap();
+        assert_eq!(origin.sender, sender);
+        assert_eq!(origin.model_id, U256::from(7));
+        assert!(allocator.origin(U256::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_domains_do_not_collide() {
+        let mut processor = RequestIdAllocator::new(DOMAIN_AI_PROCESSOR);
+        let mut compute = RequestIdAllocator::new(DOMAIN_OFF_CHAIN_COMPUTE);
+
+        let a = processor.allocate(Address::ZERO, U256::from(1));
+        let b = compute.allocate(Address::ZERO, U256::from(1));
+        assert_ne!(a, b);
+        assert_eq!(domain_of(a), DOMAIN_AI_PROCESSOR);
+        assert_eq!(domain_of(b), DOMAIN_OFF_CHAIN_COMPUTE);
+    }
+}
@@ -0,0 +1,98 @@
+// Índices de solo-añadir sobre los resultados de inferencia para poder
+// consultarlos sin reprocesar eventos. Cada lista (global, por modelo y por
+// solicitante) se guarda como posición -> request_id más un contador, así
+// añadir cuesta lo mismo tenga la lista 10 o 10 millones de entradas.
+// Las listas conservan el orden de inserción, que es también el orden de
+// timestamp porque los bloques no retroceden.
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+use alloy_primitives::{keccak256, Address, FixedBytes};
+
+pub const MAX_PAGE_SIZE: usize = 100;
+
+const GLOBAL_LIST: u8 = 0;
+const MODEL_LIST: u8 = 1;
+const REQUESTER_LIST: u8 = 2;
+
+#[derive(Debug)]
+pub struct ResultIndex {
+    entries: StorageMap<FixedBytes<32>, U256>,
+    lengths: StorageMap<FixedBytes<32>, U256>,
+}
+
+impl ResultIndex {
+    pub fn new() -> Self {
+        Self {
+            entries: StorageMap::new(),
+            lengths: StorageMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, request_id: U256, model_id: U256, requester: Address) {
+        self.push(global_list(), request_id);
+        self.push(model_list(model_id), request_id);
+        self.push(requester_list(requester), request_id);
+    }
+
+    pub fn len(&self, list: FixedBytes<32>) -> usize {
+        self.lengths.get(&list).unwrap_or(U256::ZERO).as_usize()
+    }
+
+    pub fn get(&self, list: FixedBytes<32>, position: usize) -> Option<U256> {
+        self.entries.get(&entry_key(list, position))
+    }
+
+    // Ids en [cursor, cursor + limit) y el cursor de la página siguiente
+    pub fn page(&self, list: FixedBytes<32>, cursor: usize, limit: usize) -> (Vec<U256>, usize) {
+        let end = self.len(list).min(cursor.saturating_add(limit));
+        let ids = (cursor..end).filter_map(|position| self.get(list, position)).collect();
+        (ids, end.max(cursor))
+    }
+
+    // Primera posición para la que `before` es falso; `before` debe ser
+    // monótono sobre la lista (p. ej. timestamp < desde)
+    pub fn partition_point(&self, list: FixedBytes<32>, before: impl Fn(U256) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.len(list));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.get(list, mid) {
+                Some(id) if before(id) => low = mid + 1,
+                _ => high = mid,
+            }
+        }
+        low
+    }
+
+    fn push(&mut self, list: FixedBytes<32>, request_id: U256) {
+        let position = self.len(list);
+        self.entries.insert(entry_key(list, position), request_id);
+        self.lengths.insert(list, U256::from(position + 1));
+    }
+}
+
+pub fn global_list() -> FixedBytes<32> {
+    list_key(GLOBAL_LIST, &[])
+}
+
+pub fn model_list(model_id: U256) -> FixedBytes<32> {
+    list_key(MODEL_LIST, &model_id.to_be_bytes::<32>())
+}
+
+pub fn requester_list(requester: Address) -> FixedBytes<32> {
+    list_key(REQUESTER_LIST, requester.as_slice())
+}
+
+fn list_key(tag: u8, data: &[u8]) -> FixedBytes<32> {
+    let mut buf = Vec::with_capacity(1 + data.len());
+    buf.push(tag);
+    buf.extend_from_slice(data);
+    keccak256(&buf)
+}
+
+fn entry_key(list: FixedBytes<32>, position: usize) -> FixedBytes<32> {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(list.as_slice());
+    buf[32..].copy_from_slice(&U256::from(position).to_be_bytes::<32>());
+    keccak256(buf)
+}
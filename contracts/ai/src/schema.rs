@@ -0,0 +1,439 @@
+// Esquemas ABI de entrada y salida por modelo.
+// Un esquema de entrada es una lista de tipos (un byte por tipo) que
+// describe la tupla que el llamador codifica con abi.encode(...). Un
+// esquema de salida es una lista de campos que se rellenan a partir de la
+// predicción y se devuelven codificados como tupla ABI.
+
+use alloy_primitives::{Address, FixedBytes, U256};
+
+use crate::engine::{Prediction, ONE};
+
+const WORD: usize = 32;
+const MAX_FIELDS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiType {
+    Uint256,
+    Int256,
+    Address,
+    Bool,
+    Bytes32,
+    Uint256Array,
+    Int256Array,
+}
+
+impl AbiType {
+    pub fn from_u8(tag: u8) -> Result<Self, SchemaError> {
+        match tag {
+            0x01 => Ok(AbiType::Uint256),
+            0x02 => Ok(AbiType::Int256),
+            0x03 => Ok(AbiType::Address),
+            0x04 => Ok(AbiType::Bool),
+            0x05 => Ok(AbiType::Bytes32),
+            0x11 => Ok(AbiType::Uint256Array),
+            0x12 => Ok(AbiType::Int256Array),
+            _ => Err(SchemaError::UnknownType { tag }),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            AbiType::Uint256 => 0x01,
+            AbiType::Int256 => 0x02,
+            AbiType::Address => 0x03,
+            AbiType::Bool => 0x04,
+            AbiType::Bytes32 => 0x05,
+            AbiType::Uint256Array => 0x11,
+            AbiType::Int256Array => 0x12,
+        }
+    }
+
+    fn is_dynamic(self) -> bool {
+        matches!(self, AbiType::Uint256Array | AbiType::Int256Array)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputField {
+    // Clase predicha como uint256
+    Class,
+    // Confianza calibrada en puntos básicos como uint256
+    ConfidenceBps,
+    // Puntuaciones Q16.16; en uint256[] las negativas se saturan a 0
+    Scores,
+    SignedScores,
+}
+
+impl OutputField {
+    pub fn from_u8(tag: u8) -> Result<Self, SchemaError> {
+        match tag {
+            0x01 => Ok(OutputField::Class),
+            0x02 => Ok(OutputField::ConfidenceBps),
+            0x11 => Ok(OutputField::Scores),
+            0x12 => Ok(OutputField::SignedScores),
+            _ => Err(SchemaError::UnknownType { tag }),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            OutputField::Class => 0x01,
+            OutputField::ConfidenceBps => 0x02,
+            OutputField::Scores => 0x11,
+            OutputField::SignedScores => 0x12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    Uint(U256),
+    // Palabra en complemento a dos
+    Int(U256),
+    Address(Address),
+    Bool(bool),
+    Bytes32(FixedBytes<32>),
+    UintArray(Vec<U256>),
+    IntArray(Vec<U256>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    EmptySchema,
+    TooManyFields { count: usize },
+    UnknownType { tag: u8 },
+    InputTooShort { expected: usize, actual: usize },
+    InvalidOffset { field: usize },
+    InvalidArrayLength { field: usize },
+    InvalidAddress { field: usize },
+    InvalidBool { field: usize },
+    TrailingData { expected: usize, actual: usize },
+    TooManyFeatures { expected: usize, actual: usize },
+}
+
+impl SchemaError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            SchemaError::EmptySchema => "Empty schema",
+            SchemaError::TooManyFields { .. } => "Too many schema fields",
+            SchemaError::UnknownType { .. } => "Unknown schema type",
+            SchemaError::InputTooShort { .. } => "Input shorter than schema",
+            SchemaError::InvalidOffset { .. } => "Invalid ABI offset",
+            SchemaError::InvalidArrayLength { .. } => "Invalid ABI array length",
+            SchemaError::InvalidAddress { .. } => "Invalid ABI address",
+            SchemaError::InvalidBool { .. } => "Invalid ABI bool",
+            SchemaError::TrailingData { .. } => "Trailing ABI data",
+            SchemaError::TooManyFeatures { .. } => "Too many input features",
+        }
+    }
+}
+
+pub fn parse_input_schema(schema: &[u8]) -> Result<Vec<AbiType>, SchemaError> {
+    check_field_count(schema)?;
+    schema.iter().map(|tag| AbiType::from_u8(*tag)).collect()
+}
+
+pub fn parse_output_schema(schema: &[u8]) -> Result<Vec<OutputField>, SchemaError> {
+    check_field_count(schema)?;
+    schema.iter().map(|tag| OutputField::from_u8(*tag)).collect()
+}
+
+fn check_field_count(schema: &[u8]) -> Result<(), SchemaError> {
+    if schema.is_empty() {
+        return Err(SchemaError::EmptySchema);
+    }
+    if schema.len() > MAX_FIELDS {
+        return Err(SchemaError::TooManyFields { count: schema.len() });
+    }
+    Ok(())
+}
+
+// Número de features que produce un esquema sin arrays; None si tiene
+// arrays y depende de la entrada.
+pub fn static_feature_count(types: &[AbiType]) -> Option<usize> {
+    if types.iter().any(|t| t.is_dynamic()) {
+        None
+    } else {
+        Some(types.len())
+    }
+}
+
+// Decodifica y valida `data` como abi.encode(...) de la tupla `types`.
+pub fn decode_input(types: &[AbiType], data: &[u8]) -> Result<Vec<AbiValue>, SchemaError> {
+    let head_size = types.len() * WORD;
+    if data.len() < head_size {
+        return Err(SchemaError::InputTooShort {
+            expected: head_size,
+            actual: data.len(),
+        });
+    }
+
+    // Los arrays deben ir en orden y sin huecos tras las cabeceras
+    let mut expected_tail = head_size;
+    let mut values = Vec::with_capacity(types.len());
+    for (field, abi_type) in types.iter().enumerate() {
+        let head = word(data, field * WORD);
+        let value = match abi_type {
+            AbiType::Uint256 => AbiValue::Uint(head),
+            AbiType::Int256 => AbiValue::Int(head),
+            AbiType::Address => {
+                if head >> 160 != U256::ZERO {
+                    return Err(SchemaError::InvalidAddress { field });
+                }
+                AbiValue::Address(Address::from_slice(&data[field * WORD + 12..(field + 1) * WORD]))
+            }
+            AbiType::Bool => {
+                if head > U256::from(1) {
+                    return Err(SchemaError::InvalidBool { field });
+                }
+                AbiValue::Bool(head == U256::from(1))
+            }
+            AbiType::Bytes32 => {
+                AbiValue::Bytes32(FixedBytes::from_slice(&data[field * WORD..(field + 1) * WORD]))
+            }
+            AbiType::Uint256Array | AbiType::Int256Array => {
+                if head != U256::from(expected_tail) {
+                    return Err(SchemaError::InvalidOffset { field });
+                }
+                let offset = expected_tail;
+                if data.len() < offset + WORD {
+                    return Err(SchemaError::InputTooShort {
+                        expected: offset + WORD,
+                        actual: data.len(),
+                    });
+                }
+                let length = word(data, offset);
+                let max_length = U256::from((data.len() - offset - WORD) / WORD);
+                if length > max_length {
+                    return Err(SchemaError::InvalidArrayLength { field });
+                }
+                let length = length.as_limbs()[0] as usize;
+                let items: Vec<U256> = (0..length)
+                    .map(|i| word(data, offset + WORD + i * WORD))
+                    .collect();
+                expected_tail = offset + WORD + length * WORD;
+                if *abi_type == AbiType::Uint256Array {
+                    AbiValue::UintArray(items)
+                } else {
+                    AbiValue::IntArray(items)
+                }
+            }
+        };
+        values.push(value);
+    }
+
+    if data.len() != expected_tail {
+        return Err(SchemaError::TrailingData {
+            expected: expected_tail,
+            actual: data.len(),
+        });
+    }
+    Ok(values)
+}
+
+// Conversión directa a Q16.16: enteros saturados, bool a 0/1 y
+// direcciones/bytes32 a la fracción de sus 16 bits bajos.
+pub fn raw_features(values: &[AbiValue]) -> Vec<i32> {
+    let mut features = Vec::new();
+    for value in values {
+        match value {
+            AbiValue::Uint(v) => features.push(uint_feature(*v)),
+            AbiValue::Int(v) => features.push(int_feature(*v)),
+            AbiValue::Bool(b) => features.push(if *b { ONE } else { 0 }),
+            AbiValue::Address(a) => features.push(low_bits_fraction(a.as_slice())),
+            AbiValue::Bytes32(b) => features.push(low_bits_fraction(b.as_slice())),
+            AbiValue::UintArray(items) => features.extend(items.iter().map(|v| uint_feature(*v))),
+            AbiValue::IntArray(items) => features.extend(items.iter().map(|v| int_feature(*v))),
+        }
+    }
+    features
+}
+
+fn uint_feature(value: U256) -> i32 {
+    let max = (i32::MAX >> 16) as u64;
+    if value > U256::from(max) {
+        i32::MAX
+    } else {
+        (value.as_limbs()[0] as i32) << 16
+    }
+}
+
+pub fn int_to_i64(value: U256) -> i64 {
+    if value.bit(255) {
+        let magnitude = value.wrapping_neg();
+        if magnitude > U256::from(i64::MAX as u64) {
+            i64::MIN
+        } else {
+            -(magnitude.as_limbs()[0] as i64)
+        }
+    } else if value > U256::from(i64::MAX as u64) {
+        i64::MAX
+    } else {
+        value.as_limbs()[0] as i64
+    }
+}
+
+fn int_feature(value: U256) -> i32 {
+    let v = int_to_i64(value).clamp((i32::MIN >> 16) as i64, (i32::MAX >> 16) as i64);
+    (v as i32) << 16
+}
+
+fn low_bits_fraction(bytes: &[u8]) -> i32 {
+    let n = bytes.len();
+    u16::from_be_bytes([bytes[n - 2], bytes[n - 1]]) as i32
+}
+
+pub fn encode_output(fields: &[OutputField], prediction: &Prediction) -> Vec<u8> {
+    let head_size = fields.len() * WORD;
+    let mut head = Vec::with_capacity(head_size);
+    let mut tail = Vec::new();
+
+    for field in fields {
+        match field {
+            OutputField::Class => push_uint(&mut head, prediction.class as u64),
+            OutputField::ConfidenceBps => push_uint(&mut head, prediction.confidence_bps as u64),
+            OutputField::Scores | OutputField::SignedScores => {
+                push_uint(&mut head, (head_size + tail.len()) as u64);
+                push_uint(&mut tail, prediction.scores.len() as u64);
+                for score in &prediction.scores {
+                    if *field == OutputField::SignedScores {
+                        push_int(&mut tail, *score as i64);
+                    } else {
+                        push_uint(&mut tail, (*score).max(0) as u64);
+                    }
+                }
+            }
+        }
+    }
+
+    head.extend_from_slice(&tail);
+    head
+}
+
+fn word(data: &[u8], offset: usize) -> U256 {
+    U256::from_be_slice(&data[offset..offset + WORD])
+}
+
+fn push_uint(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&U256::from(value).to_be_bytes::<32>());
+}
+
+fn push_int(out: &mut Vec<u8>, value: i64) {
+    let fill = if value < 0 { 0xff } else { 0x00 };
+    out.extend_from_slice(&[fill; 24]);
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_words(words: &[U256]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_be_bytes::<32>()).collect()
+    }
+
+    #[test]
+    fn test_decode_student_and_course() {
+        // abi.encode(address student, uint256 courseId)
+        let student = U256::from(0xabcdu64);
+        let data = encode_words(&[student, U256::from(42)]);
+        let types = parse_input_schema(&[0x03, 0x01]).unwrap();
+
+        let values = decode_input(&types, &data).unwrap();
+        assert_eq!(values[1], AbiValue::Uint(U256::from(42)));
+        assert_eq!(raw_features(&values), vec![0xabcd, 42 << 16]);
+    }
+
+    #[test]
+    fn test_decode_dynamic_array() {
+        // abi.encode(bool, uint256[])
+        let data = encode_words(&[
+            U256::from(1),
+            U256::from(64),
+            U256::from(2),
+            U256::from(7),
+            U256::from(9),
+        ]);
+        let types = parse_input_schema(&[0x04, 0x11]).unwrap();
+        let values = decode_input(&types, &data).unwrap();
+        assert_eq!(values[1], AbiValue::UintArray(vec![U256::from(7), U256::from(9)]));
+        assert_eq!(static_feature_count(&types), None);
+    }
+
+    #[test]
+    fn test_structured_errors() {
+        let types = parse_input_schema(&[0x03, 0x04]).unwrap();
+
+        let short = encode_words(&[U256::from(1)]);
+        assert_eq!(
+            decode_input(&types, &short),
+            Err(SchemaError::InputTooShort { expected: 64, actual: 32 })
+        );
+
+        let dirty_address = encode_words(&[U256::MAX, U256::from(1)]);
+        assert_eq!(decode_input(&types, &dirty_address), Err(SchemaError::InvalidAddress { field: 0 }));
+
+        let bad_bool = encode_words(&[U256::from(1), U256::from(2)]);
+        assert_eq!(decode_input(&types, &bad_bool), Err(SchemaError::InvalidBool { field: 1 }));
+
+        let mut trailing = encode_words(&[U256::from(1), U256::from(1)]);
+        trailing.push(0);
+        assert_eq!(
+            decode_input(&types, &trailing),
+            Err(SchemaError::TrailingData { expected: 64, actual: 65 })
+        );
+
+        let array = parse_input_schema(&[0x11]).unwrap();
+        let huge = encode_words(&[U256::from(32), U256::from(1000)]);
+        assert_eq!(decode_input(&array, &huge), Err(SchemaError::InvalidArrayLength { field: 0 }));
+
+        assert_eq!(parse_input_schema(&[0x42]), Err(SchemaError::UnknownType { tag: 0x42 }));
+        assert_eq!(parse_output_schema(&[]), Err(SchemaError::EmptySchema));
+    }
+
+    #[test]
+    fn test_signed_values() {
+        let minus_two = U256::from(2).wrapping_neg();
+        assert_eq!(int_to_i64(minus_two), -2);
+        assert_eq!(raw_features(&[AbiValue::Int(minus_two)]), vec![-2 << 16]);
+    }
+
+    #[test]
+    fn test_encode_scores_as_uint_array() {
+        let prediction = Prediction {
+            scores: vec![ONE, -ONE, 3],
+            class: 0,
+            confidence_bps: 8000,
+        };
+        let fields = parse_output_schema(&[0x11]).unwrap();
+        let encoded = encode_output(&fields, &prediction);
+
+        // abi.decode(output, (uint256[]))
+        assert_eq!(
+            encoded,
+            encode_words(&[U256::from(32), U256::from(3), U256::from(ONE), U256::ZERO, U256::from(3)])
+        );
+    }
+
+    #[test]
+    fn test_encode_mixed_output() {
+        let prediction = Prediction {
+            scores: vec![-ONE],
+            class: 1,
+            confidence_bps: 7500,
+        };
+        let fields = parse_output_schema(&[0x01, 0x12, 0x02]).unwrap();
+        let encoded = encode_output(&fields, &prediction);
+
+        assert_eq!(
+            encoded,
+            encode_words(&[
+                U256::from(1),
+                U256::from(96),
+                U256::from(7500),
+                U256::from(1),
+                U256::from(ONE).wrapping_neg(),
+            ])
+        );
+    }
+}
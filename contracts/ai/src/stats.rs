@@ -0,0 +1,243 @@
+// Estadísticas de procesamiento por modelo, compartidas por AIProcessor y
+// AIProcessorStylus (que lo incluye con #[path = "src/stats.rs"]).
+// El tiempo de proceso no se mide: dentro de una llamada el timestamp del
+// bloque no cambia, así que el gas es la única medida de coste fiable.
+
+use stylus_sdk::{alloy_primitives::U256, prelude::*};
+
+// Origen del resultado, mismo código que se emite en InferenceCompleted
+pub const SOURCE_ON_CHAIN: u8 = 0;
+pub const SOURCE_OFF_CHAIN: u8 = 1;
+pub const SOURCE_CACHED: u8 = 2;
+
+// Límites superiores (en bytes) de los cubos del histograma de tamaño de
+// entrada; el último cubo recoge todo lo que supere el mayor límite
+pub const INPUT_SIZE_BOUNDS: [usize; 7] = [32, 64, 128, 256, 512, 1024, 4096];
+pub const INPUT_SIZE_BUCKETS: usize = INPUT_SIZE_BOUNDS.len() + 1;
+
+// La media móvil da un peso de 1/8 a cada muestra nueva
+const EMA_WEIGHT: u64 = 8;
+
+#[derive(Debug, Clone, Storage)]
+pub struct ProcessingStats {
+    pub total_requests: U256,
+    pub successful_requests: U256,
+    pub failed_requests: U256,
+    // Porcentaje (0-100) de peticiones correctas
+    pub success_rate: U256,
+    pub total_gas_used: U256,
+    pub min_gas_used: U256,
+    pub max_gas_used: U256,
+    pub ema_gas_used: U256,
+    // Media sobre las inferencias correctas
+    pub avg_confidence_bps: U256,
+    pub on_chain_requests: U256,
+    pub off_chain_requests: U256,
+    pub cached_requests: U256,
+    // Un contador por cubo de INPUT_SIZE_BOUNDS
+    pub input_size_histogram: Vec<U256>,
+    // Cotas superiores de la mediana y del percentil 95 del tamaño de
+    // entrada, con la resolución de los cubos; U256::MAX si caen en el último
+    pub input_size_p50: U256,
+    pub input_size_p95: U256,
+}
+
+impl ProcessingStats {
+    pub fn new() -> Self {
+        Self {
+            total_requests: U256::ZERO,
+            successful_requests: U256::ZERO,
+            failed_requests: U256::ZERO,
+            success_rate: U256::ZERO,
+            total_gas_used: U256::ZERO,
+            min_gas_used: U256::ZERO,
+            max_gas_used: U256::ZERO,
+            ema_gas_used: U256::ZERO,
+            avg_confidence_bps: U256::ZERO,
+            on_chain_requests: U256::ZERO,
+            off_chain_requests: U256::ZERO,
+            cached_requests: U256::ZERO,
+            input_size_histogram: vec![U256::ZERO; INPUT_SIZE_BUCKETS],
+            input_size_p50: U256::ZERO,
+            input_size_p95: U256::ZERO,
+        }
+    }
+
+    // `confidence_bps` solo se tiene en cuenta si la petición fue correcta
+    pub fn record(
+        &mut self,
+        source: u8,
+        input_size: usize,
+        gas_used: U256,
+        success: bool,
+        confidence_bps: U256,
+    ) {
+        let first = self.total_requests.is_zero();
+        self.total_requests += U256::from(1);
+
+        if success {
+            self.successful_requests += U256::from(1);
+            self.avg_confidence_bps = (
+                self.avg_confidence_bps * (self.successful_requests - U256::from(1)) + confidence_bps
+            ) / self.successful_requests;
+        } else {
+            self.failed_requests += U256::from(1);
+        }
+        self.success_rate = self.successful_requests * U256::from(100) / self.total_requests;
+
+        self.total_gas_used += gas_used;
+        if first {
+            self.min_gas_used = gas_used;
+            self.max_gas_used = gas_used;
+            self.ema_gas_used = gas_used;
+        } else {
+            self.min_gas_used = self.min_gas_used.min(gas_used);
+            self.max_gas_used = self.max_gas_used.max(gas_used);
+            self.ema_gas_used = ema(self.ema_gas_used, gas_used);
+        }
+
+        match source {
+            SOURCE_OFF_CHAIN => self.off_chain_requests += U256::from(1),
+            SOURCE_CACHED => self.cached_requests += U256::from(1),
+            _ => self.on_chain_requests += U256::from(1),
+        }
+
+        // Estadísticas guardadas antes de existir el histograma
+        if self.input_size_histogram.len() != INPUT_SIZE_BUCKETS {
+            self.input_size_histogram.resize(INPUT_SIZE_BUCKETS, U256::ZERO);
+        }
+        self.input_size_histogram[input_size_bucket(input_size)] += U256::from(1);
+        self.input_size_p50 = self.input_size_percentile(50);
+        self.input_size_p95 = self.input_size_percentile(95);
+    }
+
+    // Límite del primer cubo que acumula al menos `pct`% de las peticiones.
+    // El histograma tiene tamaño fijo, así que el coste está acotado
+    pub fn input_size_percentile(&self, pct: u64) -> U256 {
+        let total: U256 = self.input_size_histogram.iter().fold(U256::ZERO, |sum, count| sum + *count);
+        if total.is_zero() {
+            return U256::ZERO;
+        }
+        // ceil(total * pct / 100) peticiones, al menos una
+        let rank = (total * U256::from(pct)).div_ceil(U256::from(100)).max(U256::from(1));
+        let mut seen = U256::ZERO;
+        for (bucket, count) in self.input_size_histogram.iter().enumerate() {
+            seen += *count;
+            if seen >= rank {
+                return INPUT_SIZE_BOUNDS
+                    .get(bucket)
+                    .map(|bound| U256::from(*bound))
+                    .unwrap_or(U256::MAX);
+            }
+        }
+        U256::MAX
+    }
+
+    // Porcentaje (0-100) servido desde la caché
+    pub fn cache_hit_rate(&self) -> U256 {
+        percent(self.cached_requests, self.total_requests)
+    }
+
+    // Porcentaje (0-100) resuelto off-chain
+    pub fn off_chain_ratio(&self) -> U256 {
+        percent(self.off_chain_requests, self.total_requests)
+    }
+}
+
+impl Default for ProcessingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn input_size_bucket(input_size: usize) -> usize {
+    INPUT_SIZE_BOUNDS
+        .iter()
+        .position(|&bound| input_size <= bound)
+        .unwrap_or(INPUT_SIZE_BOUNDS.len())
+}
+
+fn ema(current: U256, sample: U256) -> U256 {
+    let weight = U256::from(EMA_WEIGHT);
+    (current * (weight - U256::from(1)) + sample) / weight
+}
+
+fn percent(part: U256, total: U256) -> U256 {
+    if total.is_zero() {
+        return U256::ZERO;
+    }
+    part * U256::from(100) / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_tracks_outcomes_gas_and_sources() {
+        let mut stats = ProcessingStats::new();
+
+        stats.record(SOURCE_ON_CHAIN, 16, U256::from(800), true, U256::from(9_000));
+        stats.record(SOURCE_ON_CHAIN, 100, U256::from(1_600), false, U256::ZERO);
+        stats.record(SOURCE_CACHED, 16, U256::from(400), true, U256::from(7_000));
+        stats.record(SOURCE_OFF_CHAIN, 5_000, U256::from(1_200), true, U256::from(8_000));
+
+        assert_eq!(stats.total_requests, U256::from(4));
+        assert_eq!(stats.successful_requests, U256::from(3));
+        assert_eq!(stats.failed_requests, U256::from(1));
+        assert_eq!(stats.success_rate, U256::from(75));
+        // Los fallos no cuentan en la confianza media
+        assert_eq!(stats.avg_confidence_bps, U256::from(8_000));
+
+        assert_eq!(stats.total_gas_used, U256::from(4_000));
+        assert_eq!(stats.min_gas_used, U256::from(400));
+        assert_eq!(stats.max_gas_used, U256::from(1_600));
+        assert!(stats.ema_gas_used > stats.min_gas_used && stats.ema_gas_used < stats.max_gas_used);
+
+        assert_eq!(stats.on_chain_requests, U256::from(2));
+        assert_eq!(stats.cached_requests, U256::from(1));
+        assert_eq!(stats.off_chain_requests, U256::from(1));
+        assert_eq!(stats.cache_hit_rate(), U256::from(25));
+        assert_eq!(stats.off_chain_ratio(), U256::from(25));
+
+        assert_eq!(stats.input_size_histogram[0], U256::from(2));
+        assert_eq!(stats.input_size_histogram[2], U256::from(1));
+        assert_eq!(stats.input_size_histogram[INPUT_SIZE_BUCKETS - 1], U256::from(1));
+
+        // 16, 16, 100, 5000: la mediana cae en el primer cubo y el p95 en el último
+        assert_eq!(stats.input_size_p50, U256::from(32));
+        assert_eq!(stats.input_size_p95, U256::MAX);
+    }
+
+    #[test]
+    fn test_input_size_percentiles() {
+        let mut stats = ProcessingStats::new();
+        assert_eq!(stats.input_size_percentile(50), U256::ZERO);
+
+        // 90 entradas pequeñas, 8 medianas y 2 grandes
+        for _ in 0..90 {
+            stats.record(SOURCE_ON_CHAIN, 20, U256::from(100), true, U256::ZERO);
+        }
+        for _ in 0..8 {
+            stats.record(SOURCE_ON_CHAIN, 300, U256::from(100), true, U256::ZERO);
+        }
+        for _ in 0..2 {
+            stats.record(SOURCE_ON_CHAIN, 2_000, U256::from(100), true, U256::ZERO);
+        }
+        assert_eq!(stats.input_size_p50, U256::from(32));
+        assert_eq!(stats.input_size_p95, U256::from(512));
+        assert_eq!(stats.input_size_percentile(90), U256::from(32));
+        assert_eq!(stats.input_size_percentile(99), U256::from(4096));
+        assert_eq!(stats.input_size_percentile(100), U256::from(4096));
+        assert!(stats.input_size_p50 <= stats.input_size_p95);
+    }
+
+    #[test]
+    fn test_input_size_buckets() {
+        assert_eq!(input_size_bucket(0), 0);
+        assert_eq!(input_size_bucket(32), 0);
+        assert_eq!(input_size_bucket(33), 1);
+        assert_eq!(input_size_bucket(4096), INPUT_SIZE_BOUNDS.len() - 1);
+        assert_eq!(input_size_bucket(4097), INPUT_SIZE_BOUNDS.len());
+    }
+}
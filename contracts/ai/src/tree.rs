@@ -0,0 +1,349 @@
+// Ensembles de árboles de decisión (random forest / gradient boosting)
+// evaluados en punto fijo Q16.16, igual que las redes de engine.rs.
+
+use crate::engine::Reader;
+
+const ENSEMBLE_MAGIC: &[u8; 4] = b"BSTE";
+const ENSEMBLE_VERSION: u8 = 1;
+const LEAF_TAG: u16 = u16::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Sum,
+    Average,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    // Va a la izquierda si features[feature] <= threshold
+    Split {
+        feature: u16,
+        threshold: i32,
+        left: u16,
+        right: u16,
+    },
+    Leaf {
+        values: Vec<i32>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    pub nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn evaluate(&self, features: &[i32]) -> &[i32] {
+        let mut index = 0usize;
+        loop {
+            match &self.nodes[index] {
+                Node::Split {
+                    feature,
+                    threshold,
+                    left,
+                    right,
+                } => {
+                    index = if features[*feature as usize] <= *threshold {
+                        *left as usize
+                    } else {
+                        *right as usize
+                    };
+                }
+                Node::Leaf { values } => return values,
+            }
+        }
+    }
+
+    // Los hijos van siempre detrás del padre: recorriendo al revés, la
+    // profundidad de los hijos ya está calculada. Lineal en nodos.
+    pub fn depth(&self) -> usize {
+        let mut depths = vec![0usize; self.nodes.len()];
+        for index in (0..self.nodes.len()).rev() {
+            if let Node::Split { left, right, .. } = &self.nodes[index] {
+                depths[index] = 1 + depths[*left as usize].max(depths[*right as usize]);
+            }
+        }
+        depths.first().copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEnsemble {
+    pub num_features: usize,
+    pub num_outputs: usize,
+    pub aggregation: Aggregation,
+    pub base_scores: Vec<i32>,
+    pub trees: Vec<Tree>,
+}
+
+impl TreeEnsemble {
+    // Formato: "BSTE" | version u8 | aggregation u8 | num_features u16 |
+    // num_outputs u16 | num_trees u16 | base_scores i32[num_outputs] |
+    // por árbol: num_nodes u16 | nodos
+    // Nodo interno: feature u16 | threshold i32 | left u16 | right u16
+    // Hoja: 0xFFFF | values i32[num_outputs]
+    // Los hijos siempre tienen un índice mayor que el padre, así que
+    // todo árbol decodificado termina, y cada nodo tiene como mucho un padre:
+    // con hijos compartidos sería un DAG con exponencialmente muchos caminos.
+    pub fn decode(data: &[u8]) -> Result<Self, Vec<u8>> {
+        let mut reader = Reader::new(data);
+        if reader.take(4)? != ENSEMBLE_MAGIC {
+            return Err("Invalid ensemble magic".into());
+        }
+        if reader.u8()? != ENSEMBLE_VERSION {
+            return Err("Unsupported ensemble version".into());
+        }
+
+        let aggregation = match reader.u8()? {
+            0 => Aggregation::Sum,
+            1 => Aggregation::Average,
+            _ => return Err("Unknown aggregation".into()),
+        };
+        let num_features = reader.u16()? as usize;
+        let num_outputs = reader.u16()? as usize;
+        let num_trees = reader.u16()? as usize;
+        if num_features == 0 || num_outputs == 0 || num_trees == 0 {
+            return Err("Empty ensemble".into());
+        }
+
+        let base_scores = reader.i32_vec(num_outputs)?;
+
+        let mut trees = Vec::with_capacity(num_trees);
+        for _ in 0..num_trees {
+            let num_nodes = reader.u16()? as usize;
+            if num_nodes == 0 {
+                return Err("Empty tree".into());
+            }
+
+            let mut nodes = Vec::with_capacity(num_nodes);
+            let mut has_parent = vec![false; num_nodes];
+            for index in 0..num_nodes {
+                let tag = reader.u16()?;
+                if tag == LEAF_TAG {
+                    nodes.push(Node::Leaf {
+                        values: reader.i32_vec(num_outputs)?,
+                    });
+                    continue;
+                }
+
+                let threshold = reader.i32()?;
+                let left = reader.u16()?;
+                let right = reader.u16()?;
+                if tag as usize >= num_features {
+                    return Err("Split feature out of range".into());
+                }
+                for child in [left, right] {
+                    if child as usize <= index || child as usize >= num_nodes {
+                        return Err("Invalid child index".into());
+                    }
+                    if has_parent[child as usize] {
+                        return Err("Shared child node".into());
+                    }
+                    has_parent[child as usize] = true;
+                }
+                nodes.push(Node::Split {
+                    feature: tag,
+                    threshold,
+                    left,
+                    right,
+                });
+            }
+            trees.push(Tree { nodes });
+        }
+
+        if !reader.is_empty() {
+            return Err("Trailing ensemble data".into());
+        }
+        Ok(Self {
+            num_features,
+            num_outputs,
+            aggregation,
+            base_scores,
+            trees,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(ENSEMBLE_MAGIC);
+        out.push(ENSEMBLE_VERSION);
+        out.push(match self.aggregation {
+            Aggregation::Sum => 0,
+            Aggregation::Average => 1,
+        });
+        out.extend_from_slice(&(self.num_features as u16).to_be_bytes());
+        out.extend_from_slice(&(self.num_outputs as u16).to_be_bytes());
+        out.extend_from_slice(&(self.trees.len() as u16).to_be_bytes());
+        for score in &self.base_scores {
+            out.extend_from_slice(&score.to_be_bytes());
+        }
+        for tree in &self.trees {
+            out.extend_from_slice(&(tree.nodes.len() as u16).to_be_bytes());
+            for node in &tree.nodes {
+                match node {
+                    Node::Split {
+                        feature,
+                        threshold,
+                        left,
+                        right,
+                    } => {
+                        out.extend_from_slice(&feature.to_be_bytes());
+                        out.extend_from_slice(&threshold.to_be_bytes());
+                        out.extend_from_slice(&left.to_be_bytes());
+                        out.extend_from_slice(&right.to_be_bytes());
+                    }
+                    Node::Leaf { values } => {
+                        out.extend_from_slice(&LEAF_TAG.to_be_bytes());
+                        for value in values {
+                            out.extend_from_slice(&value.to_be_bytes());
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    pub fn evaluate(&self, features: &[i32]) -> Result<Vec<i32>, Vec<u8>> {
+        if features.len() != self.num_features {
+            return Err("Feature count mismatch".into());
+        }
+
+        let mut sums: Vec<i64> = vec![0; self.num_outputs];
+        for tree in &self.trees {
+            for (sum, value) in sums.iter_mut().zip(tree.evaluate(features)) {
+                *sum = sum.saturating_add(*value as i64);
+            }
+        }
+
+        let count = self.trees.len() as i64;
+        Ok(sums
+            .into_iter()
+            .zip(self.base_scores.iter())
+            .map(|(sum, base)| {
+                let aggregated = match self.aggregation {
+                    Aggregation::Sum => sum,
+                    Aggregation::Average => sum / count,
+                };
+                aggregated
+                    .saturating_add(*base as i64)
+                    .clamp(i32::MIN as i64, i32::MAX as i64) as i32
+            })
+            .collect())
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.trees.iter().map(|t| t.depth()).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::ONE;
+
+    fn stump(feature: u16, threshold: i32, low: i32, high: i32) -> Tree {
+        Tree {
+            nodes: vec![
+                Node::Split {
+                    feature,
+                    threshold,
+                    left: 1,
+                    right: 2,
+                },
+                Node::Leaf { values: vec![low] },
+                Node::Leaf { values: vec![high] },
+            ],
+        }
+    }
+
+    fn ensemble(aggregation: Aggregation) -> TreeEnsemble {
+        TreeEnsemble {
+            num_features: 2,
+            num_outputs: 1,
+            aggregation,
+            base_scores: vec![ONE / 4],
+            trees: vec![stump(0, ONE / 2, -ONE, ONE), stump(1, ONE / 2, 0, 2 * ONE)],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let model = ensemble(Aggregation::Sum);
+        assert_eq!(TreeEnsemble::decode(&model.encode()).unwrap(), model);
+    }
+
+    #[test]
+    fn test_sum_and_average() {
+        let features = [ONE, 0];
+        // árbol 0 -> ONE, árbol 1 -> 0
+        let sum = ensemble(Aggregation::Sum).evaluate(&features).unwrap();
+        assert_eq!(sum, vec![ONE + ONE / 4]);
+
+        let avg = ensemble(Aggregation::Average).evaluate(&features).unwrap();
+        assert_eq!(avg, vec![ONE / 2 + ONE / 4]);
+    }
+
+    #[test]
+    fn test_rejects_backward_children() {
+        let mut model = ensemble(Aggregation::Sum);
+        model.trees[0].nodes[0] = Node::Split {
+            feature: 0,
+            threshold: 0,
+            left: 0,
+            right: 2,
+        };
+        assert!(TreeEnsemble::decode(&model.encode()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_feature() {
+        let mut model = ensemble(Aggregation::Sum);
+        model.trees[1] = stump(5, 0, 0, 0);
+        assert!(TreeEnsemble::decode(&model.encode()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_shared_children() {
+        let split = |left, right| Node::Split {
+            feature: 0,
+            threshold: 0,
+            left,
+            right,
+        };
+        let mut model = ensemble(Aggregation::Sum);
+        // El nodo 2 colgaría del 0 y del 1
+        model.trees[0].nodes = vec![
+            split(1, 2),
+            split(2, 3),
+            Node::Leaf { values: vec![0] },
+            Node::Leaf { values: vec![0] },
+        ];
+        assert!(TreeEnsemble::decode(&model.encode()).is_err());
+
+        model.trees[0].nodes = vec![split(1, 1), Node::Leaf { values: vec![0] }];
+        assert!(TreeEnsemble::decode(&model.encode()).is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(ensemble(Aggregation::Sum).max_depth(), 1);
+
+        // Una cadena de 2.000 splits: cada uno tiene una hoja y sigue por la derecha
+        let levels = 2_000u16;
+        let mut nodes = Vec::new();
+        for level in 0..levels {
+            let index = 2 * level;
+            nodes.push(Node::Split {
+                feature: 0,
+                threshold: 0,
+                left: index + 1,
+                right: index + 2,
+            });
+            nodes.push(Node::Leaf { values: vec![0] });
+        }
+        nodes.push(Node::Leaf { values: vec![0] });
+        let tree = Tree { nodes };
+        assert_eq!(tree.depth(), levels as usize);
+    }
+}
@@ -0,0 +1,88 @@
+// Compromiso Merkle sobre los pesos de un modelo subidos por trozos.
+// Cada hoja es keccak256(indice u32 BE || trozo), así el orden de los
+// trozos forma parte del compromiso.
+
+use alloy_primitives::{keccak256, FixedBytes};
+
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024;
+// 64 MiB por subida; los índices de las hojas son u32
+pub const MAX_CHUNK_COUNT: u32 = 4 * 1024;
+
+pub fn chunk_leaf(index: u32, chunk: &[u8]) -> FixedBytes<32> {
+    let mut data = Vec::with_capacity(4 + chunk.len());
+    data.extend_from_slice(&index.to_be_bytes());
+    data.extend_from_slice(chunk);
+    keccak256(&data)
+}
+
+// Los nodos impares suben sin emparejar al siguiente nivel.
+pub fn merkle_root(leaves: &[FixedBytes<32>]) -> FixedBytes<32> {
+    if leaves.is_empty() {
+        return FixedBytes::ZERO;
+    }
+
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity(level.len().div_ceil(2));
+        for pair in level.chunks(2) {
+            if pair.len() == 2 {
+                let mut data = [0u8; 64];
+                data[..32].copy_from_slice(pair[0].as_slice());
+                data[32..].copy_from_slice(pair[1].as_slice());
+                next.push(keccak256(data));
+            } else {
+                next.push(pair[0]);
+            }
+        }
+        level = next;
+    }
+    level[0]
+}
+
+// Utilidad para el lado host: trocea un blob y calcula la raíz que
+// hay que comprometer antes de subirlo.
+pub fn commit_blob(blob: &[u8], chunk_size: usize) -> (FixedBytes<32>, Vec<Vec<u8>>) {
+    let chunks: Vec<Vec<u8>> = blob.chunks(chunk_size.max(1)).map(|c| c.to_vec()).collect();
+    let leaves: Vec<FixedBytes<32>> = chunks
+        .iter()
+        .enumerate()
+        .map(|(i, c)| chunk_leaf(i as u32, c))
+        .collect();
+    (merkle_root(&leaves), chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_chunk_root_is_leaf() {
+        let (root, chunks) = commit_blob(&[1, 2, 3], 8);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(root, chunk_leaf(0, &[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_root_depends_on_order() {
+        let a = chunk_leaf(0, &[1]);
+        let b = chunk_leaf(1, &[2]);
+        let c = chunk_leaf(2, &[3]);
+        assert_ne!(merkle_root(&[a, b, c]), merkle_root(&[b, a, c]));
+        assert_ne!(chunk_leaf(0, &[1]), chunk_leaf(1, &[1]));
+    }
+
+    #[test]
+    fn test_commit_blob_matches_manual_tree() {
+        let blob: Vec<u8> = (0..10).collect();
+        let (root, chunks) = commit_blob(&blob, 4);
+        assert_eq!(chunks.len(), 3);
+
+        let leaves: Vec<FixedBytes<32>> = chunks
+            .iter()
+            .enumerate()
+            .map(|(i, c)| chunk_leaf(i as u32, c))
+            .collect();
+        assert_eq!(root, merkle_root(&leaves));
+        assert_ne!(root, FixedBytes::ZERO);
+    }
+}
@@ -3,54 +3,382 @@ pragma solidity ^0.8.19;
 
 
 interface IAIProcessor {
+    enum ModelKind {
+        NeuralNetwork,
+        TreeEnsemble,
+        LinearModel,
+        QuantizedNetwork
+    }
+
     struct ModelConfig {
+        uint256 modelId;
+        uint256 version;
+        ModelKind modelKind;
         uint256 inputSize;
         uint256 outputSize;
         uint256 batchSize;
         uint256 computeUnits;
+        bytes32 weightsRoot;
+        bytes inputSchema;
+        bytes outputSchema;
+        bytes preprocessing;
         bool isActive;
     }
 
     struct InferenceResult {
         uint256 requestId;
+        uint256 modelId;
+        uint256 modelVersion;
         bytes inputHash;
         bytes output;
+        uint256 predictedClass;
         uint256 confidence;
+        uint256 confidenceBps;
         uint256 timestamp;
         uint256 gasUsed;
+        bool success;
     }
 
     struct ProcessingStats {
         uint256 totalRequests;
-        uint256 totalGasUsed;
-        uint256 avgProcessingTime;
+        uint256 successfulRequests;
+        uint256 failedRequests;
         uint256 successRate;
+        uint256 totalGasUsed;
+        uint256 minGasUsed;
+        uint256 maxGasUsed;
+        uint256 emaGasUsed;
+        uint256 avgConfidenceBps;
+        uint256 onChainRequests;
+        uint256 offChainRequests;
+        uint256 cachedRequests;
+        // Entradas <= 32, 64, 128, 256, 512, 1024, 4096 bytes y el resto
+        uint256[] inputSizeHistogram;
+        // Cotas de la mediana y el p95 del tamaño de entrada según los cubos;
+        // type(uint256).max si caen por encima de 4096 bytes
+        uint256 inputSizeP50;
+        uint256 inputSizeP95;
+    }
+
+    struct ResultPage {
+        InferenceResult[] results;
+        uint256 nextCursor;
+        bool hasMore;
     }
 
+    struct RequestOrigin {
+        address sender;
+        uint256 modelId;
+        uint256 blockNumber;
+        uint256 timestamp;
+    }
+
+    // Un límite a 0 significa sin límite
+    struct QuotaConfig {
+        uint256 windowBlocks;
+        uint256 epochBlocks;
+        uint256 callerRequestsPerWindow;
+        uint256 callerComputeUnitsPerEpoch;
+        uint256 modelRequestsPerWindow;
+        uint256 modelComputeUnitsPerEpoch;
+    }
+
+    // token == address(0) cobra en ETH nativo
+    struct PricingConfig {
+        address token;
+        uint256 baseFee;
+        uint256 feePerComputeUnit;
+        uint256 feePerInputByte;
+    }
+
+    struct License {
+        bytes termsUri;
+        bytes32 termsHash;
+        bool commercialUse;
+        bool requiresAcceptance;
+    }
+
+    struct RemainingQuota {
+        uint256 requests;
+        uint256 computeUnits;
+        uint256 windowResetsAt;
+        uint256 epochResetsAt;
+    }
+
+    struct CircuitBreaker {
+        uint256 maxErrorRateBps;
+        uint256 minAvgConfidenceBps;
+        uint256 maxAvgConfidenceBps;
+        uint256 minSamples;
+        uint256 windowRequests;
+        uint256 windowFailures;
+        uint256 windowConfidenceBps;
+        uint256 previousRequests;
+        uint256 previousFailures;
+        uint256 previousConfidenceBps;
+        bool tripped;
+    }
+
+    // Errores (mismos selectores que AIError en contracts/ai/src/errors.rs)
+    error NotOwner(address caller);
+    error NotPendingOwner(address caller);
+    error MissingRole(bytes32 role, address account);
+    error OwnerAdminRole(address account);
+    error ModelNotFound(uint256 modelId);
+    error ModelVersionNotFound(uint256 modelId, uint256 version);
+    error ModelInactive(uint256 modelId, uint256 version);
+    error VersionAlreadyActive(uint256 modelId, uint256 version);
+    error NoPreviousVersion(uint256 modelId);
+    error WeightsAlreadyCommitted(uint256 modelId, uint256 version);
+    error WeightsNotSet(uint256 modelId, uint256 version);
+    error UploadNotStarted(uint256 modelId, uint256 version);
+    error UploadTooLarge(uint256 totalSize, uint256 maxSize);
+    error UnexpectedChunk(uint256 expected, uint256 actual);
+    error InvalidChunkSize(uint256 size);
+    error UploadIncomplete(uint256 received, uint256 expected);
+    error UploadSizeMismatch(uint256 expected, uint256 actual);
+    error WeightsRootMismatch(bytes32 committed, bytes32 computed);
+    error InvalidModelData(string reason);
+    error InvalidSchema(string reason);
+    error InvalidPipeline(string reason);
+    error FeatureCountExceeded(uint256 max, uint256 actual);
+    error InputTooLarge(uint256 max, uint256 actual);
+    error InputTooShort(uint256 expected, uint256 actual);
+    error TrailingInput(uint256 expected, uint256 actual);
+    error MalformedInput(uint256 field, string reason);
+    error InvalidInput(string reason);
+    error BatchTooLarge(uint256 max, uint256 actual);
+    error InferenceFailed(string reason);
+    error StatsNotFound(uint256 modelId);
+    error RequestExpired(uint256 requestId, uint256 deadline);
+    error RequestNotFound(uint256 requestId);
+    error InvalidPageSize(uint256 limit, uint256 max);
+    error InvalidTimeRange(uint256 fromTime, uint256 toTime);
+    error Paused();
+    error NotPaused();
+    error CircuitBreakerOpen(uint256 modelId);
+    error BreakerNotConfigured(uint256 modelId);
+    error InvalidBreakerConfig();
+    error RequestQuotaExceeded(uint256 modelId, address account, uint256 limit);
+    error ComputeQuotaExceeded(uint256 modelId, address account, uint256 limit);
+    error InvalidQuotaConfig(string reason);
+    error IncorrectPayment(uint256 expected, uint256 actual);
+    error UnsupportedFeeToken(address token);
+    error NothingToWithdraw(address account, address token);
+    error FeeTransferFailed(address token, uint256 amount);
+    error NotModelPublisher(uint256 modelId, address account);
+    error LocalIdTooLarge(uint256 localId);
+    error LicenseNotAccepted(uint256 modelId, address account);
+    error LicenseTermsMismatch(uint256 modelId, bytes32 expected, bytes32 actual);
+    error InvalidPlatformFee(uint256 feeBps, uint256 max);
+
+    // Eventos (source: 0 on-chain, 1 off-chain, 2 caché)
+    event ModelRegistered(uint256 indexed modelId, uint256 inputSize, uint256 outputSize);
+    event ModelUpdated(uint256 indexed modelId, uint256 version);
+    event InferenceCompleted(
+        uint256 indexed requestId,
+        uint256 indexed modelId,
+        bytes32 inputHash,
+        bytes32 outputHash,
+        uint256 confidence,
+        uint8 source
+    );
+    event CacheHit(bytes32 indexed inputHash, uint256 indexed modelId, uint256 hits);
+    // reason: 0 caducada, 1 capacidad (LRU), 2 invalidada
+    event CacheEvicted(bytes32 indexed inputHash, uint256 indexed modelId, uint8 reason);
+    event StatsUpdated(uint256 indexed modelId, uint256 totalRequests, uint256 totalGasUsed, uint256 successRate);
+
     // Funciones
     function registerModel(
         uint256 modelId,
+        ModelKind modelKind,
         uint256 inputSize,
         uint256 outputSize,
         uint256 batchSize,
         uint256 computeUnits
+    ) external returns (uint256 version);
+
+    function setModelSchema(
+        uint256 modelId,
+        uint256 version,
+        bytes calldata inputSchema,
+        bytes calldata outputSchema
+    ) external returns (bool);
+
+    function setModelPreprocessing(
+        uint256 modelId,
+        uint256 version,
+        bytes calldata pipeline
     ) external returns (bool);
 
+    function beginWeightUpload(
+        uint256 modelId,
+        uint256 version,
+        bytes32 committedRoot,
+        uint256 totalSize,
+        uint256 chunkCount
+    ) external returns (bool);
+
+    function uploadWeightChunk(
+        uint256 modelId,
+        uint256 version,
+        uint256 chunkIndex,
+        bytes calldata chunk
+    ) external returns (bool);
+
+    function finalizeWeightUpload(uint256 modelId, uint256 version) external returns (bool);
+
+    function cancelWeightUpload(uint256 modelId, uint256 version) external returns (bool);
+
     function processInference(
         uint256 modelId,
         bytes calldata inputData
-    ) external returns (InferenceResult memory);
+    ) external payable returns (InferenceResult memory);
 
     function batchProcess(
         uint256 modelId,
         bytes[] calldata inputs
-    ) external returns (InferenceResult[] memory);
+    ) external payable returns (InferenceResult[] memory);
 
     function getModelConfig(
         uint256 modelId
     ) external view returns (ModelConfig memory);
 
+    function activateModelVersion(uint256 modelId, uint256 version) external returns (bool);
+
+    function deactivateModelVersion(uint256 modelId, uint256 version) external returns (bool);
+
+    function rollbackModel(uint256 modelId) external returns (uint256 version);
+
+    function getModelVersion(
+        uint256 modelId,
+        uint256 version
+    ) external view returns (ModelConfig memory);
+
+    function getActiveVersion(uint256 modelId) external view returns (uint256);
+
     function getProcessingStats(
         uint256 modelId
     ) external view returns (ProcessingStats memory);
-} 
\ No newline at end of file
+
+    function getRequestOrigin(
+        uint256 requestId
+    ) external view returns (RequestOrigin memory);
+
+    // Consulta de resultados (limit máximo 100)
+    function getInferenceResult(
+        uint256 requestId
+    ) external view returns (InferenceResult memory);
+
+    function getResultsByModel(
+        uint256 modelId,
+        uint256 cursor,
+        uint256 limit
+    ) external view returns (ResultPage memory);
+
+    function getResultsByRequester(
+        address requester,
+        uint256 cursor,
+        uint256 limit
+    ) external view returns (ResultPage memory);
+
+    function getResultsByTimeRange(
+        uint256 fromTime,
+        uint256 toTime,
+        uint256 cursor,
+        uint256 limit
+    ) external view returns (ResultPage memory);
+
+    function countResults() external view returns (uint256);
+
+    function countResultsByModel(uint256 modelId) external view returns (uint256);
+
+    function countResultsByRequester(address requester) external view returns (uint256);
+
+    // Control de acceso
+    function hasRole(bytes32 role, address account) external view returns (bool);
+
+    function grantRole(bytes32 role, address account) external returns (bool);
+
+    function revokeRole(bytes32 role, address account) external returns (bool);
+
+    function renounceRole(bytes32 role) external returns (bool);
+
+    function owner() external view returns (address);
+
+    function pendingOwner() external view returns (address);
+
+    function transferOwnership(address newOwner) external returns (bool);
+
+    function acceptOwnership() external returns (bool);
+
+    // Parada de emergencia y circuit breaker
+    function pause() external returns (bool);
+
+    function unpause() external returns (bool);
+
+    function paused() external view returns (bool);
+
+    function setCircuitBreaker(
+        uint256 modelId,
+        uint256 maxErrorRateBps,
+        uint256 minAvgConfidenceBps,
+        uint256 maxAvgConfidenceBps,
+        uint256 minSamples
+    ) external returns (bool);
+
+    function resetCircuitBreaker(uint256 modelId) external returns (bool);
+
+    function getCircuitBreaker(
+        uint256 modelId
+    ) external view returns (CircuitBreaker memory);
+
+    // Cuotas de uso
+    function setQuota(uint256 modelId, QuotaConfig calldata quota) external returns (bool);
+
+    function getQuota(uint256 modelId) external view returns (QuotaConfig memory);
+
+    function setQuotaExempt(address account, bool exempt) external returns (bool);
+
+    function isQuotaExempt(address account) external view returns (bool);
+
+    function getRemainingQuota(
+        uint256 modelId,
+        address account
+    ) external view returns (RemainingQuota memory);
+
+    // Tarifas por inferencia. Se pagan por adelantado, pero solo se cobran
+    // las inferencias correctas: la parte de las fallidas queda en
+    // accruedFees del pagador y se recupera con withdrawFees
+    function setFeeToken(address token) external returns (bool);
+
+    function feeToken() external view returns (address);
+
+    function getModelOwner(uint256 modelId) external view returns (address);
+
+    function setModelPricing(uint256 modelId, PricingConfig calldata pricing) external returns (bool);
+
+    function getModelPricing(uint256 modelId) external view returns (PricingConfig memory);
+
+    function quoteFee(uint256 modelId, uint256 inputSize) external view returns (uint256);
+
+    function accruedFees(address account, address token) external view returns (uint256);
+
+    function withdrawFees(address token) external returns (uint256);
+
+    // Marketplace: los editores (PUBLISHER_ROLE) publican en el espacio
+    // publisherModelId(editor, idLocal)
+    function publisherModelId(address publisher, uint256 localId) external view returns (uint256);
+
+    function setPlatformFee(uint256 feeBps, address treasury) external returns (bool);
+
+    function platformFee() external view returns (uint256 feeBps, address treasury);
+
+    function setModelLicense(uint256 modelId, License calldata license) external returns (bool);
+
+    function getModelLicense(uint256 modelId) external view returns (License memory);
+
+    function acceptModelLicense(uint256 modelId, bytes32 termsHash) external returns (bool);
+
+    function hasAcceptedLicense(uint256 modelId, address account) external view returns (bool);
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+
+// Lo implementan los contratos que piden inferencias off-chain al
+// AIProcessor de Stylus. Se llama con un límite de gas acotado; si revierte,
+// el resultado queda guardado igualmente y la entrega se puede reintentar
+// con retryCallback(requestId, gasLimit).
+interface IInferenceCallback {
+    function onInferenceResult(
+        uint256 requestId,
+        bytes calldata output,
+        uint256 confidence
+    ) external;
+}
+
+// Parte del AIProcessor de Stylus que usan quienes piden inferencias
+// asíncronas. Si la entrada es demasiado cara para procesarla on-chain,
+// devuelve un resultado pendiente (computationSource == OFF_CHAIN) cuyo
+// requestId llegará después a onInferenceResult.
+interface IAsyncInferenceProcessor {
+    struct InferenceResult {
+        uint256 requestId;
+        bytes inputHash;
+        bytes output;
+        uint256 confidence;
+        uint256 timestamp;
+        uint256 gasUsed;
+        uint8 computationSource;
+        bool success;
+    }
+
+    function processInference(
+        uint256 modelId,
+        bytes calldata inputData
+    ) external payable returns (InferenceResult memory);
+}
@@ -5,11 +5,12 @@ import "@openzeppelin/contracts/access/AccessControl.sol";
 import "@openzeppelin/contracts/security/ReentrancyGuard.sol";
 import "@openzeppelin/contracts/security/Pausable.sol";
 import "../interfaces/IAIProcessor.sol";
+import "../interfaces/IInferenceCallback.sol";
 import "../optimizations/AdvancedBatchProcessor.sol";
 import "../cache/DistributedCacheV2.sol";
 
 
-contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
+contract AIOracle is AccessControl, ReentrancyGuard, Pausable, IInferenceCallback {
     bytes32 public constant ADMIN_ROLE = keccak256("ADMIN_ROLE");
     bytes32 public constant OPERATOR_ROLE = keccak256("OPERATOR_ROLE");
 
@@ -40,6 +41,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         // Modelo de predicción de rendimiento
         aiProcessor.registerModel(
             PERFORMANCE_MODEL_ID,
+            IAIProcessor.ModelKind.NeuralNetwork,
             1024, // inputSize
             128,  // outputSize
             32,   // batchSize
@@ -49,6 +51,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         // Modelo de generación de rutas de aprendizaje
         aiProcessor.registerModel(
             LEARNING_PATH_MODEL_ID,
+            IAIProcessor.ModelKind.NeuralNetwork,
             2048, // inputSize
             512,  // outputSize
             16,   // batchSize
@@ -58,6 +61,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         // Modelo de detección de fraude
         aiProcessor.registerModel(
             FRAUD_DETECTION_MODEL_ID,
+            IAIProcessor.ModelKind.NeuralNetwork,
             512,  // inputSize
             64,   // outputSize
             64,   // batchSize
@@ -72,7 +76,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(student, courseId);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
@@ -86,11 +90,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256[] memory) {
         bytes memory input = abi.encode(student);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             LEARNING_PATH_MODEL_ID,
             input
         );
         
+        if (!result.success) {
+            return new uint256[](0);
+        }
         return abi.decode(result.output, (uint256[]));
     }
 
@@ -101,7 +108,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (bool) {
         bytes memory input = abi.encode(user, activityHash);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             FRAUD_DETECTION_MODEL_ID,
             input
         );
@@ -135,12 +142,25 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         
         uint256[] memory predictions = new uint256[](results.length);
         for (uint256 i = 0; i < results.length; i++) {
-            predictions[i] = results[i].confidence;
+            // Un fallo queda con confianza 0, como en _processInference
+            predictions[i] = results[i].success ? results[i].confidence : 0;
         }
         
         return predictions;
     }
 
+    // Los fallos del modelo no revierten en el procesador (se registran con
+    // success = false para las estadísticas y el circuit breaker), así que
+    // tampoco aquí: revertir desharía ese registro. Un fallo llega con
+    // confianza 0 y salida vacía; quien decodifica la salida o invierte la
+    // confianza debe mirar success antes.
+    function _processInference(
+        uint256 modelId,
+        bytes memory input
+    ) internal view returns (IAIProcessor.InferenceResult memory result) {
+        result = aiProcessor.processInference(modelId, input);
+    }
+
     
     function getModelStats(
         uint256 modelId
@@ -202,6 +222,111 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         distributedCache.setCache(key, result, expiresAt);
     }
 
+    // ========== ASYNC RESULTS ==========
+
+    // Procesador de Stylus que resuelve off-chain las entradas demasiado
+    // grandes y devuelve el resultado llamando a onInferenceResult
+    address public asyncProcessor;
+
+    // Mismo valor que ComputationSource::OffChain en AIProcessorStylus.rs
+    uint8 private constant SOURCE_OFF_CHAIN = 1;
+
+    struct AsyncRequest {
+        address requester;
+        uint256 modelId;
+    }
+
+    struct AsyncResult {
+        bytes output;
+        uint256 confidence;
+        uint256 receivedAt;
+    }
+
+    // Peticiones enviadas desde este contrato que esperan su resultado
+    mapping(uint256 => AsyncRequest) public asyncRequests;
+    mapping(uint256 => AsyncResult) public asyncResults;
+
+    event AsyncProcessorSet(address indexed processor);
+    event AsyncInferenceRequested(uint256 indexed requestId, uint256 indexed modelId, address indexed requester);
+    event AsyncResultReceived(uint256 indexed requestId, uint256 confidence);
+    event AsyncResultConsumed(uint256 indexed requestId, address indexed requester);
+
+    function setAsyncProcessor(address _processor) external onlyRole(DEFAULT_ADMIN_ROLE) {
+        require(_processor != address(0), "Invalid address");
+        asyncProcessor = _processor;
+        emit AsyncProcessorSet(_processor);
+    }
+
+    // Si el procesador resuelve la inferencia en el acto (on-chain o desde
+    // su caché) se devuelve ya con requestId 0; si la manda off-chain, queda
+    // registrada a nombre de quien la pidió hasta que llegue el resultado.
+    // Los reembolsos de peticiones caducadas se acreditan a este contrato.
+    function requestAsyncInference(
+        uint256 modelId,
+        bytes calldata input
+    ) external payable whenNotPaused nonReentrant returns (
+        uint256 requestId,
+        bytes memory output,
+        uint256 confidence
+    ) {
+        require(asyncProcessor != address(0), "Async processor not set");
+
+        IAsyncInferenceProcessor.InferenceResult memory result =
+            IAsyncInferenceProcessor(asyncProcessor).processInference{value: msg.value}(modelId, input);
+
+        if (result.computationSource != SOURCE_OFF_CHAIN) {
+            return (0, result.output, result.confidence);
+        }
+
+        requestId = result.requestId;
+        asyncRequests[requestId] = AsyncRequest({
+            requester: msg.sender,
+            modelId: modelId
+        });
+        emit AsyncInferenceRequested(requestId, modelId, msg.sender);
+    }
+
+    // Debe ser barato: se ejecuta con el gas limitado del procesador
+    function onInferenceResult(
+        uint256 requestId,
+        bytes calldata output,
+        uint256 confidence
+    ) external override {
+        require(msg.sender == asyncProcessor, "Only async processor");
+        require(asyncRequests[requestId].requester != address(0), "Unknown request");
+        require(asyncResults[requestId].receivedAt == 0, "Result already received");
+
+        asyncResults[requestId] = AsyncResult({
+            output: output,
+            confidence: confidence,
+            receivedAt: block.timestamp
+        });
+        emit AsyncResultReceived(requestId, confidence);
+    }
+
+    function getAsyncResult(
+        uint256 requestId
+    ) external view returns (bytes memory output, uint256 confidence, bool ready) {
+        AsyncResult storage result = asyncResults[requestId];
+        return (result.output, result.confidence, result.receivedAt != 0);
+    }
+
+    // Entrega el resultado a quien pidió la inferencia y libera la petición
+    function consumeAsyncResult(
+        uint256 requestId
+    ) external nonReentrant returns (bytes memory output, uint256 confidence) {
+        AsyncRequest memory request = asyncRequests[requestId];
+        require(request.requester == msg.sender, "Not the requester");
+        AsyncResult memory result = asyncResults[requestId];
+        require(result.receivedAt != 0, "Result not ready");
+
+        delete asyncRequests[requestId];
+        delete asyncResults[requestId];
+        emit AsyncResultConsumed(requestId, msg.sender);
+
+        return (result.output, result.confidence);
+    }
+
     // ========== SCHOLARSHIP FUNCTIONS ==========
     
     function evaluateScholarshipCandidate(
@@ -211,7 +336,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256 score, bool recommendation) {
         bytes memory input = abi.encode(candidate, programId, candidateData);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID, // Reuse performance model for scholarship evaluation
             input
         );
@@ -223,7 +348,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     function getScholarshipEligibilityScore(address student) external view returns (uint256) {
         bytes memory input = abi.encode(student);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
@@ -234,11 +359,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     function recommendScholarships(address student) external view returns (uint256[] memory) {
         bytes memory input = abi.encode(student);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             LEARNING_PATH_MODEL_ID,
             input
         );
         
+        if (!result.success) {
+            return new uint256[](0);
+        }
         return abi.decode(result.output, (uint256[]));
     }
 
@@ -251,7 +379,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(candidate, jobId, candidateData);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID, // Reuse performance model for job matching
             input
         );
@@ -262,11 +390,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     function recommendJobs(address candidate) external view returns (uint256[] memory) {
         bytes memory input = abi.encode(candidate);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             LEARNING_PATH_MODEL_ID, // Reuse learning path model for job recommendations
             input
         );
         
+        if (!result.success) {
+            return new uint256[](0);
+        }
         return abi.decode(result.output, (uint256[]));
     }
     
@@ -279,7 +410,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) {
         bytes memory input = abi.encode(candidate);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
@@ -303,11 +434,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     function recommendCourses(address student) external view returns (uint256[] memory) {
         bytes memory input = abi.encode(student);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             LEARNING_PATH_MODEL_ID,
             input
         );
         
+        if (!result.success) {
+            return new uint256[](0);
+        }
         return abi.decode(result.output, (uint256[]));
     }
     
@@ -317,11 +451,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(student, courseId);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
         
+        if (!result.success) {
+            return 0;
+        }
         // Return difficulty score (higher = more difficult)
         return 100 - result.confidence; // Invert confidence to get difficulty
     }
@@ -332,11 +469,14 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(student, courseId);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
         
+        if (!result.success) {
+            return 0;
+        }
         // Convert confidence to estimated hours (40-200 hours based on difficulty)
         return 40 + ((100 - result.confidence) * 160 / 100);
     }
@@ -350,7 +490,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (bool) {
         bytes memory input = abi.encode(certificateHash, issuer, recipient);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             FRAUD_DETECTION_MODEL_ID,
             input
         );
@@ -362,7 +502,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         bytes32 dataHash = keccak256(certificateData);
         bytes memory input = abi.encode(msg.sender, dataHash);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             FRAUD_DETECTION_MODEL_ID,
             input
         );
@@ -382,7 +522,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     function calculateReputationScore(address user) external view returns (uint256) {
         bytes memory input = abi.encode(user);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
@@ -395,7 +535,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256 riskScore, string memory behaviorType) {
         bytes memory input = abi.encode(user);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             FRAUD_DETECTION_MODEL_ID,
             input
         );
@@ -419,7 +559,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(courseId, contentHash);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             PERFORMANCE_MODEL_ID,
             input
         );
@@ -433,7 +573,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
     ) external view returns (uint256) {
         bytes memory input = abi.encode(submissionHash, referenceData);
         
-        IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+        IAIProcessor.InferenceResult memory result = _processInference(
             FRAUD_DETECTION_MODEL_ID,
             input
         );
@@ -471,7 +611,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
         EvaluationResult[] memory results = new EvaluationResult[](requests.length);
         
         for (uint256 i = 0; i < requests.length; i++) {
-            IAIProcessor.InferenceResult memory aiResult = aiProcessor.processInference(
+            IAIProcessor.InferenceResult memory aiResult = _processInference(
                 requests[i].requestType == 1 ? PERFORMANCE_MODEL_ID :
                 requests[i].requestType == 2 ? LEARNING_PATH_MODEL_ID :
                 FRAUD_DETECTION_MODEL_ID,
@@ -502,7 +642,7 @@ contract AIOracle is AccessControl, ReentrancyGuard, Pausable {
                 certificates[i].recipient
             );
             
-            IAIProcessor.InferenceResult memory result = aiProcessor.processInference(
+            IAIProcessor.InferenceResult memory result = _processInference(
                 FRAUD_DETECTION_MODEL_ID,
                 input
             );
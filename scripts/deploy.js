@@ -21,6 +21,8 @@ async function main() {
     const aiOracle = await AIOracle.deploy(aiProcessor.address);
     await aiOracle.deployed();
     console.log("AIOracle desplegado en:", aiOracle.address);
+    // El oráculo atiende a muchos usuarios: no consume cuota del procesador
+    await (await aiProcessor.setQuotaExempt(aiOracle.address, true)).wait();
 
     // 3. Desplegar EDUToken
     console.log("\nDesplegando EDUToken...");
@@ -28,6 +30,8 @@ async function main() {
     const eduToken = await EDUToken.deploy();
     await eduToken.deployed();
     console.log("EDUToken desplegado en:", eduToken.address);
+    // Las tarifas de inferencia se pueden cobrar en EDU
+    await (await aiProcessor.setFeeToken(eduToken.address)).wait();
 
     // 4. Desplegar CourseNFT
     console.log("\nDesplegando CourseNFT...");
@@ -60,7 +60,7 @@ async function main() {
     const stats = await aiProcessor.getProcessingStats(1);
     console.log(`Total de solicitudes: ${stats.totalRequests}`);
     console.log(`Gas total usado: ${stats.totalGasUsed}`);
-    console.log(`Tiempo promedio de procesamiento: ${stats.avgProcessingTime}`);
+    console.log(`Gas mínimo / máximo / EMA: ${stats.minGasUsed} / ${stats.maxGasUsed} / ${stats.emaGasUsed}`);
     console.log(`Tasa de éxito: ${stats.successRate}%`);
 
     // 4. Costos en USD
@@ -93,7 +93,10 @@ async function main() {
         stats: {
             totalRequests: stats.totalRequests.toString(),
             totalGasUsed: stats.totalGasUsed.toString(),
-            avgProcessingTime: stats.avgProcessingTime.toString(),
+            minGasUsed: stats.minGasUsed.toString(),
+            maxGasUsed: stats.maxGasUsed.toString(),
+            emaGasUsed: stats.emaGasUsed.toString(),
+            failedRequests: stats.failedRequests.toString(),
             successRate: stats.successRate.toString()
         },
         costs: {
@@ -591,10 +591,9 @@ describe("BrainSafes - AI Processor Stylus Testing", function () {
       console.log("🤖 AI Processing Statistics:");
       console.log(`- Total requests: ${stats.totalRequests}`);
       console.log(`- Total gas used: ${stats.totalGasUsed}`);
-      console.log(`- Average processing time: ${stats.avgProcessingTime}`);
+      console.log(`- Gas min/max/EMA: ${stats.minGasUsed}/${stats.maxGasUsed}/${stats.emaGasUsed}`);
       console.log(`- Success rate: ${stats.successRate}`);
-      console.log(`- Cache hit rate: ${stats.cacheHitRate}`);
-      console.log(`- Off-chain ratio: ${stats.offChainRatio}`);
+      console.log(`- On-chain/off-chain/cached: ${stats.onChainRequests}/${stats.offChainRequests}/${stats.cachedRequests}`);
 
       expect(stats.totalRequests).to.equal(10);
       expect(stats.successRate).to.be.gt(0);